@@ -0,0 +1,83 @@
+//! Benchmarks crawling a website served by a local mock server.
+//! Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mockito::{Mock, Server, ServerGuard};
+use spider_crab::SpiderCrab;
+use tokio::runtime::Builder;
+
+/// Number of HTML pages of the mock website
+const PAGES: usize = 10;
+/// Number of images on each page
+const IMAGES_PER_PAGE: usize = 10;
+/// Size of each image in bytes
+const IMAGE_BYTES: usize = 256 * 1024;
+
+/// Starts a mock website where every page links to the next page and to its own images
+async fn start_website() -> (ServerGuard, Vec<Mock>) {
+    let mut server = Server::new_async().await;
+    let mut mocks = Vec::<Mock>::new();
+    let image = vec![0u8; IMAGE_BYTES];
+
+    for page in 0..PAGES {
+        let mut body = format!("<!DOCTYPE html><html><title>Page {}</title><body>", page);
+        if page + 1 < PAGES {
+            body.push_str(&format!("<a href=\"/page{}.html\">Next</a>", page + 1));
+        }
+        for i in 0..IMAGES_PER_PAGE {
+            let path = format!("/images/{}-{}.png", page, i);
+            body.push_str(&format!("<img src=\"{}\" alt=\"Image {}\">", path, i));
+            for method in ["GET", "HEAD"] {
+                let mock = server
+                    .mock(method, path.as_str())
+                    .with_header("content-type", "image/png")
+                    .with_body(&image)
+                    .create_async()
+                    .await;
+                mocks.push(mock);
+            }
+        }
+        body.push_str("</body></html>");
+
+        let path = if page == 0 {
+            String::from("/")
+        } else {
+            format!("/page{}.html", page)
+        };
+        let mock = server
+            .mock("GET", path.as_str())
+            .with_header("content-type", "text/html")
+            .with_body(body)
+            .create_async()
+            .await;
+        mocks.push(mock);
+    }
+    (server, mocks)
+}
+
+/// Crawls the whole mock website at `url`
+async fn crawl(url: &str, head_for_assets: bool) {
+    let mut spider_crab = SpiderCrab::new(&[url]);
+    spider_crab.options.head_for_assets = head_for_assets;
+    assert!(spider_crab.visit_website(url).await);
+}
+
+fn bench_asset_checks(c: &mut Criterion) {
+    let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    let (server, _mocks) = runtime.block_on(start_website());
+    let url = server.url() + "/";
+
+    c.bench_function("crawl, assets checked with GET", |b| {
+        b.to_async(&runtime).iter(|| crawl(&url, false))
+    });
+    c.bench_function("crawl, assets checked with HEAD", |b| {
+        b.to_async(&runtime).iter(|| crawl(&url, true))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_asset_checks
+}
+criterion_main!(benches);
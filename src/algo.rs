@@ -3,22 +3,89 @@
 use async_recursion::async_recursion;
 use log::{error, info, warn};
 use petgraph::graph::NodeIndex;
-use reqwest::{Client, Response};
-use scraper::{Element, Html};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, ETAG, LAST_MODIFIED, LINK, LOCATION};
+use reqwest::{Method, StatusCode};
+use scraper::{Element, ElementRef, Html, Selector};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::mpsc::Sender;
 use std::sync::Mutex;
+use std::time::Instant;
 use url::Url;
 
-use crate::error::{SpiderError, SpiderErrorType};
-use crate::url_helpers::{check_host, get_url_from_element};
+use crate::error::{Severity, SpiderError, SpiderErrorType};
+use crate::events::CrawlEvent;
+use crate::fetch::{
+    FetchError, FetchRequest, FetchedResponse, Fetcher, HostThrottle, RequestLimiter,
+};
+use crate::pause::PauseHandle;
+use crate::robots::RobotsCache;
+use crate::url_helpers::{
+    check_host, escapes_host_root, find_case_mismatch, get_canonical_url, get_link_header_urls,
+    get_url_attribute, get_url_from_element, get_urls_from_inline_attribute, get_urls_from_json_ld,
+    get_urls_from_text, is_asset_url, is_likely_mistyped_relative_url, is_protocol_relative_url,
+    is_render_blocking, is_subresource, is_valid_language_tag, is_valid_mailto,
+    parse_relative_or_absolute_url, validate_srcset,
+};
 use crate::{Link, Page, PageGraph, PageMap, SpiderOptions};
 
-/// Attempts to retrieve the HTTP ContentType from a Response and check if it is some form of HTML document.
+/// State of a single crawl, created when the crawl starts and dropped when it is done.
+/// `SpiderOptions` describe how to crawl, this holds what the crawl keeps track of while it runs,
+/// so that consecutive crawls (ie. with `SpiderCrab::watch()`) never share robots.txt rules or request timings.
+pub struct CrawlContext<'a> {
+    /// Fetcher that the requests of the crawl are sent with
+    pub fetcher: &'a dyn Fetcher,
+    /// Options controlling behavior of the crawl
+    pub options: &'a SpiderOptions,
+    /// Pauses and resumes the crawl. Checked before every request is sent.
+    pub pause_handle: PauseHandle,
+    /// Progress of the crawl is sent through this channel while the crawl is running
+    pub event_sender: Option<Sender<CrawlEvent>>,
+    /// Enforces `options.max_concurrency` across all pages of the crawl
+    pub request_limiter: RequestLimiter,
+    /// Enforces `options.request_delay` for each host across all pages of the crawl
+    pub host_throttle: HostThrottle,
+    /// Rules of the `robots.txt` of each host, retrieved while crawling if `options.respect_robots` is set
+    pub robots: RobotsCache,
+}
+
+impl<'a> CrawlContext<'a> {
+    /// Creates the state of a new crawl that sends requests with `fetcher`.
+    /// Requests wait while `pause_handle` is paused, and events are sent through `event_sender` if there is one.
+    pub fn new(
+        fetcher: &'a dyn Fetcher,
+        options: &'a SpiderOptions,
+        pause_handle: PauseHandle,
+        event_sender: Option<Sender<CrawlEvent>>,
+    ) -> Self {
+        Self {
+            fetcher,
+            options,
+            pause_handle,
+            event_sender,
+            request_limiter: RequestLimiter::default(),
+            host_throttle: HostThrottle::default(),
+            robots: RobotsCache::default(),
+        }
+    }
+
+    /// Sends `event` through the `event_sender`, if there is one
+    pub(crate) fn send_event(&self, event: CrawlEvent) {
+        if let Some(sender) = &self.event_sender {
+            // Nobody listening for events is not a problem for the crawl
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// Attempts to retrieve the HTTP ContentType from the headers of a response and check if it is some form of HTML document.
 /// Returns `(true, Some(content_type: String))` if the ContentType is some form of HTML document.
 /// Returns `(false, Some(content_type: String))` if the ContentType is not HTML.
 /// Returns `(false, None)` if failed to get the ContentType
-fn check_content_type(response: &Response) -> (bool, Option<String>) {
-    if response.headers().contains_key("Content-Type") {
-        let content_type = response.headers().get("Content-Type").unwrap().to_str();
+fn check_content_type(headers: &HeaderMap) -> (bool, Option<String>) {
+    if headers.contains_key("Content-Type") {
+        let content_type = headers.get("Content-Type").unwrap().to_str();
         if let Ok(content_type) = content_type {
             let mut content_type = content_type.to_string().to_lowercase();
             let split_index: Option<usize> = content_type.find(';');
@@ -41,10 +108,269 @@ fn check_content_type(response: &Response) -> (bool, Option<String>) {
     (false, None)
 }
 
+/// Returns why a page could not be retrieved, for the `reason` of its `UnableToRetrieve` error
+fn get_retrieve_failure_reason(e: &FetchError) -> String {
+    if e.timed_out {
+        String::from("the request timed out")
+    } else {
+        e.message.clone()
+    }
+}
+
+/// Returns `true` if `response_result` failed in a way that may not happen again: a connection error or a `5XX` status code
+fn is_transient_failure(response_result: &Result<FetchedResponse, FetchError>) -> bool {
+    match response_result {
+        Ok(response) => response.status.is_server_error(),
+        Err(e) => e.status.map_or(true, |status| status.is_server_error()),
+    }
+}
+
+/// Returns `true` if responses with `content_type` are text that is not HTML (ie. `text/plain` or an XML sitemap)
+fn is_text_content_type(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|c| c.starts_with("text/") || c.ends_with("xml"))
+}
+
+/// Returns `true` if responses with `content_type` are text that should be compressed (HTML, CSS and JavaScript)
+fn is_compressible_content_type(content_type: Option<&str>) -> bool {
+    matches!(
+        content_type,
+        Some("text/html" | "html" | "text/css" | "text/javascript" | "application/javascript")
+    )
+}
+
+/// Name of the `<meta>` element that lists the rules to skip for a page (ie. `<meta name="spider-crab-skip-checks" content="missing-title,empty-script">`)
+pub const SKIP_CHECKS_META_NAME: &str = "spider-crab-skip-checks";
+
+/// Returns the rules listed by the `SKIP_CHECKS_META_NAME` `<meta>` elements of `html`. Unknown rule names are logged and ignored.
+fn get_skipped_rules(html: &Html, url: &Url) -> Vec<SpiderErrorType> {
+    let selector = Selector::parse(&format!("meta[name=\"{}\"]", SKIP_CHECKS_META_NAME)).unwrap();
+    let mut rules = Vec::<SpiderErrorType>::new();
+    for meta in html.select(&selector) {
+        let content = meta.attr("content").unwrap_or_default();
+        for name in content.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match SpiderErrorType::from_str(name) {
+                Ok(rule) => rules.push(rule),
+                Err(_) => warn!(
+                    "Unknown rule {} in {} of page {}",
+                    name, SKIP_CHECKS_META_NAME, url
+                ),
+            }
+        }
+    }
+    rules
+}
+
+/// Attributes that reference elements on the same page by a whitespace separated list of their ids
+const ARIA_REFERENCE_ATTRIBUTES: &[&str] = &[
+    "aria-labelledby",
+    "aria-describedby",
+    "aria-controls",
+    "aria-owns",
+    "aria-flowto",
+    "aria-details",
+    "aria-errormessage",
+    "aria-activedescendant",
+];
+
+/// Returns each element of `html` that references an id in one of the `ARIA_REFERENCE_ATTRIBUTES`
+/// that no element has, with the name of the attribute and the missing id
+fn get_broken_aria_references(html: &Html) -> Vec<(ElementRef<'_>, &'static str, &str)> {
+    let elements: Vec<ElementRef> = html
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .collect();
+    let ids: HashSet<&str> = elements.iter().filter_map(|e| e.attr("id")).collect();
+
+    let mut broken = Vec::new();
+    for e in elements {
+        for attribute in ARIA_REFERENCE_ATTRIBUTES {
+            let references = e.attr(attribute).unwrap_or_default().split_whitespace();
+            for id in references.filter(|id| !ids.contains(id)) {
+                broken.push((e, *attribute, id));
+            }
+        }
+    }
+    broken
+}
+
+/// Returns the anchors of `html` that a URL fragment can point to, the `id` of every element and the `name` of every `<a>` element
+fn get_anchors(html: &Html) -> HashSet<String> {
+    let mut anchors = HashSet::<String>::new();
+    for e in html
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if let Some(id) = e.attr("id") {
+            anchors.insert(id.to_string());
+        }
+        if let Some(name) = e.attr("name").filter(|_| e.value().name() == "a") {
+            anchors.insert(name.to_string());
+        }
+    }
+    anchors
+}
+
+/// Returns the number of words in the visible text of the body of `html`.
+/// Text inside of `<script>`, `<style>`, `<noscript>` and `<template>` elements is not counted.
+fn get_word_count(html: &Html) -> usize {
+    let body_selector = Selector::parse("body").unwrap();
+    let Some(body) = html.select(&body_selector).next() else {
+        return 0;
+    };
+    body.descendants()
+        .filter_map(|node| {
+            let text = node.value().as_text()?;
+            let hidden = node.ancestors().filter_map(ElementRef::wrap).any(|e| {
+                matches!(
+                    e.value().name(),
+                    "script" | "style" | "noscript" | "template"
+                )
+            });
+            (!hidden).then(|| text.split_whitespace().count())
+        })
+        .sum()
+}
+
+/// Returns `true` if `element` or any of its ancestors matches `options.skip_selector`
+fn is_skipped_by_selector(element: ElementRef, options: &SpiderOptions) -> bool {
+    match &options.skip_selector {
+        Some(selector) => {
+            selector.matches(&element)
+                || element
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .any(|a| selector.matches(&a))
+        }
+        None => false,
+    }
+}
+
+/// Parses the content of every `<noscript>` element of `html` as markup, like a browser with JavaScript disabled does.
+/// The parser treats that content as text, so the links and images inside of it are only found in the returned fragments.
+/// `<noscript>` elements that are skipped (see `SpiderOptions::skip_class` and `SpiderOptions::skip_selector`) are not parsed.
+fn parse_noscript_fragments(html: &Html, options: &SpiderOptions) -> Vec<Html> {
+    let noscript_selector = Selector::parse("noscript").unwrap();
+    html.select(&noscript_selector)
+        .filter(|e| {
+            !e.has_class(&options.skip_class, scraper::CaseSensitivity::CaseSensitive)
+                && !is_skipped_by_selector(*e, options)
+        })
+        .map(|e| Html::parse_fragment(&e.text().collect::<String>()))
+        .collect()
+}
+
+/// Returns `true` if the `<a>` element has an accessible name.
+/// Links are named by their text, an `aria-label`, `aria-labelledby` or `title` attribute, or the `alt` text of an image inside of them.
+fn has_accessible_name(element: ElementRef) -> bool {
+    let non_empty = |s: Option<&str>| s.is_some_and(|s| !s.trim().is_empty());
+
+    element.text().any(|t| !t.trim().is_empty())
+        || non_empty(element.attr("aria-label"))
+        || non_empty(element.attr("aria-labelledby"))
+        || non_empty(element.attr("title"))
+        || element
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .any(|e| e.value().name() == "img" && non_empty(e.attr("alt")))
+}
+
+/// Adds an edge from the node given by `node_index` to the page at `next_url`.
+/// If the page has not been discovered yet, a node is added for it and its index is returned.
+/// URLs longer than `options.max_url_length` are recorded when their node is added.
+/// No edge is added if `options.memory_bounded` is set.
+fn add_link(
+    graph: &mut PageGraph,
+    page_map: &mut PageMap,
+    options: &SpiderOptions,
+    node_index: NodeIndex,
+    next_url: &Url,
+    html: String,
+) -> Option<NodeIndex> {
+    // Check to see if the target URL has already been visited
+    if let Some(existing_page) = page_map.get(next_url) {
+        // Target URL has already been visited
+        if !options.memory_bounded {
+            graph.add_edge(node_index, *existing_page, Link { html });
+        }
+        return None;
+    }
+
+    // Absurdly long URLs break email clients and caches
+    let length = next_url.as_str().len();
+    if options.max_url_length.is_some_and(|max| length > max) {
+        let page = graph.node_weight_mut(node_index).unwrap();
+        if options.is_rule_enabled(SpiderErrorType::UrlTooLong, &page.url) {
+            warn!(
+                "Page {} links to a URL that is {} characters long",
+                page.url, length
+            );
+            page.push_error(SpiderError {
+                error_type: SpiderErrorType::UrlTooLong,
+                source_page: Some(page.url.to_string()),
+                target_page: Some(next_url.to_string()),
+                count: Some(length),
+                severity: Severity::Warning,
+                ..SpiderError::default()
+            });
+        }
+    }
+
+    // Target URL has not been visited yet, add a node to the graph
+    let new_node = graph.add_node(Page::new(next_url));
+
+    // Add an edge to the graph connecting current page to the target page
+    if !options.memory_bounded {
+        graph.add_edge(node_index, new_node, Link { html });
+    }
+
+    // Add an entry to the page HashMap to mark that we're going to visit the page
+    page_map.insert(next_url.clone(), new_node);
+
+    Some(new_node)
+}
+
+/// Adds a link from the node given by `node_index` to every absolute URL found in the raw `contents` of the page at `url`.
+/// URLs that the page already links to are skipped. Newly discovered pages are pushed onto `new_nodes`.
+#[allow(clippy::too_many_arguments)]
+fn add_text_links(
+    graph: &mut PageGraph,
+    page_map: &mut PageMap,
+    options: &SpiderOptions,
+    node_index: NodeIndex,
+    url: &Url,
+    contents: &str,
+    current_depth: i32,
+    new_nodes: &mut Vec<(NodeIndex, Url)>,
+) {
+    for url_str in get_urls_from_text(contents) {
+        let Some(next_url) = parse_relative_or_absolute_url(url, &url_str, &options.query_params)
+        else {
+            info!("Ignoring possible URL {} in text of page {}", url_str, url);
+            continue;
+        };
+        let is_linked = page_map
+            .get(&next_url)
+            .is_some_and(|existing| graph.contains_edge(node_index, *existing));
+        if is_linked {
+            continue;
+        }
+
+        let new_node = add_link(graph, page_map, options, node_index, &next_url, url_str);
+        if let Some(new_node) = new_node {
+            if options.is_within_max_depth(url, &next_url, current_depth) {
+                new_nodes.push((new_node, next_url));
+            }
+        }
+    }
+}
+
 /// Recursive function that visits the URL of the node given by `node_index` in the graph locked by the `graph_mutex`.
 /// Keeps track of pages that were already visited by inserting URLs into the HashMap locked behind the `page_map_mutex`.
-/// Behavior can be controlled via the `options` parameter.
+/// Behavior can be controlled via the `options` of the `context`.
 /// Current distance from the root node is given by the `current_depth` parameter.
+/// If `follow_links` is `false`, the links of the page are recorded, but none of them are visited.
 /// Will recursive call itself until one of the following occurs:
 /// * `current_depth` reaches `options.max_depth`
 /// * Domain name of the newly discovered URL does not match the `options.domain_name`
@@ -56,24 +382,141 @@ fn check_content_type(response: &Response) -> (bool, Option<String>) {
 pub async fn visit_page(
     node_index: NodeIndex,
     url: Url,
-    client: &Client,
-    options: &SpiderOptions,
+    context: &CrawlContext<'_>,
     graph_mutex: &Mutex<&mut PageGraph>,
     page_map_mutex: &Mutex<&mut PageMap>,
     current_depth: i32,
+    follow_links: bool,
 ) -> bool {
+    let fetcher = context.fetcher;
+    let options = context.options;
     let mut new_nodes = Vec::<(NodeIndex, Url)>::new();
     let mut found_problem: bool = false;
     // Reserve some space for our new node indices.
     new_nodes.reserve(64);
 
-    {
+    if options.shard.is_some_and(|shard| !shard.contains(&url)) {
+        info!("Not visiting {}, it belongs to another shard", url);
+        return true;
+    }
+
+    if options.is_trusted(&url) {
+        info!("Not visiting {}, its host is trusted", url);
+        let mut graph = graph_mutex.lock().unwrap();
+        graph.node_weight_mut(node_index).unwrap().good = Some(true);
+        return true;
+    }
+
+    context.send_event(CrawlEvent::PageStarted { url: url.clone() });
+
+    'page: {
         // Send an HTTP(S) GET request for the desired URL
-        let response_result = client
-            .request(reqwest::Method::GET, url.clone())
-            .send()
+        context.pause_handle.wait_while_paused().await;
+        context
+            .host_throttle
+            .wait(&url, options.request_delay)
             .await;
-        let response: Response;
+        // Released once the body of the response has been read, or when the page is done
+        let mut request_permit = context
+            .request_limiter
+            .acquire(options.max_concurrency)
+            .await;
+        // Assets are only requested to check their status, their body is never needed
+        let (subresource, protocol_relative) = {
+            let graph = graph_mutex.lock().unwrap();
+            let page = graph.node_weight(node_index).unwrap();
+            (page.subresource, page.protocol_relative)
+        };
+        // Pages found through protocol-relative links are only checked, unless they are treated as in-host
+        let in_host = check_host(&options.hosts, &url)
+            && (options.protocol_relative_in_host || !protocol_relative);
+        let is_asset = options.head_for_assets && (subresource || is_asset_url(&url));
+        let mut request = match options.request_method(&url) {
+            Some(method) => FetchRequest::new(method, &url),
+            None if is_asset => FetchRequest::head(&url),
+            None => FetchRequest::get(&url),
+        };
+        // Credentials are only sent to the website, never to the websites it links to
+        if check_host(&options.hosts, &url) {
+            request.basic_auth = options.basic_auth.clone();
+        }
+        let mut request_start = Instant::now();
+        let mut response_result = fetcher.fetch(request.clone()).await;
+        let head_rejected = response_result.as_ref().is_ok_and(|r| {
+            matches!(
+                r.status,
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            )
+        });
+        if request.method == Method::HEAD && head_rejected {
+            info!("HEAD request to {} was rejected, sending GET request", url);
+            request.method = Method::GET;
+            context
+                .host_throttle
+                .wait(&url, options.request_delay)
+                .await;
+            request_start = Instant::now();
+            response_result = fetcher.fetch(request.clone()).await;
+        }
+
+        // Retry once if the server asked us to come back later
+        let retry_after = response_result
+            .as_ref()
+            .ok()
+            .and_then(|r| r.retry_after())
+            .filter(|delay| options.max_retry_after.is_some_and(|max| *delay <= max));
+        if let Some(delay) = retry_after {
+            info!("Retrying {} after {:?}", url, delay);
+            // Other requests can be sent while waiting
+            drop(request_permit);
+            tokio::time::sleep(delay).await;
+            context.pause_handle.wait_while_paused().await;
+            context
+                .host_throttle
+                .wait(&url, options.request_delay)
+                .await;
+            request_permit = context
+                .request_limiter
+                .acquire(options.max_concurrency)
+                .await;
+            request_start = Instant::now();
+            response_result = fetcher.fetch(request.clone()).await;
+        }
+
+        // Retry connection errors and server errors, waiting twice as long before each retry
+        for attempt in 0..options.max_retries {
+            if !is_transient_failure(&response_result) {
+                break;
+            }
+            let backoff = options
+                .retry_backoff
+                .saturating_mul(2u32.saturating_pow(attempt));
+            info!(
+                "Retrying {} after {:?} (retry {} of {})",
+                url,
+                backoff,
+                attempt + 1,
+                options.max_retries
+            );
+            drop(request_permit);
+            tokio::time::sleep(backoff).await;
+            context.pause_handle.wait_while_paused().await;
+            context
+                .host_throttle
+                .wait(&url, options.request_delay)
+                .await;
+            request_permit = context
+                .request_limiter
+                .acquire(options.max_concurrency)
+                .await;
+            request_start = Instant::now();
+            response_result = fetcher.fetch(request.clone()).await;
+        }
+        let mut response: FetchedResponse;
+        // Page is text that is not HTML, it is only scanned for URLs
+        let is_text_page: bool;
+        // Page is outside of the domain or an SVG image, it is only parsed for its anchors
+        let anchors_only: bool;
 
         {
             // Acquire a lock on the graph so that we can update it with our findings for this page
@@ -81,106 +524,453 @@ pub async fn visit_page(
             let page = graph.node_weight_mut(node_index).unwrap();
 
             page.visited = true;
-            if response_result.is_err() {
-                page.status_code = response_result.err().unwrap().status();
+            if let Err(e) = &response_result {
+                page.status_code = e.status;
                 page.good = Some(false);
 
                 if options.is_rule_enabled(SpiderErrorType::UnableToRetrieve, &url) {
-                    page.errors.push(SpiderError {
+                    let severity = options.retrieve_severity(&url);
+                    found_problem |= page.push_error(SpiderError {
                         target_page: Some(url.to_string()),
                         error_type: SpiderErrorType::UnableToRetrieve,
+                        reason: Some(get_retrieve_failure_reason(e)),
+                        severity,
                         ..Default::default()
-                    });
-                    return false;
+                    }) && severity == Severity::Error;
                 }
-                return true;
+                break 'page;
             }
 
             response = response_result.unwrap();
+            let response_time = request_start.elapsed();
+            page.response_time = Some(response_time);
+            if options
+                .max_response_time
+                .is_some_and(|max| response_time > max)
+                && options.is_rule_enabled(SpiderErrorType::SlowResponse, &url)
+            {
+                warn!("Page {} took {:?} to respond", url, response_time);
+                found_problem |= page.push_error(SpiderError {
+                    error_type: SpiderErrorType::SlowResponse,
+                    target_page: Some(url.to_string()),
+                    duration_ms: Some(response_time.as_millis() as u64),
+                    ..SpiderError::default()
+                });
+            }
+
+            // Record the HTTP status code and cache validators
+            page.status_code = Some(response.status);
+            let header_str = |name| {
+                response
+                    .headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(String::from)
+            };
+            page.last_modified = header_str(LAST_MODIFIED);
+            page.etag = header_str(ETAG);
+
+            // Fetchers other than the HTTP client only give the URL that they were redirected to
+            page.redirect_chain = std::mem::take(&mut response.redirect_chain);
+            if page.redirect_chain.is_empty() && response.url != url {
+                page.redirect_chain = vec![url.clone(), response.url.clone()];
+            }
+            if options.report_redirects
+                && !page.redirect_chain.is_empty()
+                && options.is_rule_enabled(SpiderErrorType::RedirectChain, &url)
+            {
+                warn!("Page {} redirects to {}", url, response.url);
+                page.push_error(SpiderError {
+                    error_type: SpiderErrorType::RedirectChain,
+                    source_page: Some(url.to_string()),
+                    target_page: Some(response.url.to_string()),
+                    count: Some(page.redirect_chain.len() - 1),
+                    severity: Severity::Warning,
+                    ..SpiderError::default()
+                });
+            }
+
+            // Paths that only work with different casing break on case-sensitive servers
+            if options.check_path_case
+                && options.is_rule_enabled(SpiderErrorType::CaseMismatch, &url)
+            {
+                if let Some(actual_url) = find_case_mismatch(&url, &response.url) {
+                    error!("Page {} only differs in case from {}", url, actual_url);
+                    found_problem |= page.push_error(SpiderError {
+                        error_type: SpiderErrorType::CaseMismatch,
+                        target_page: Some(url.to_string()),
+                        canonical_url: Some(actual_url.to_string()),
+                        ..SpiderError::default()
+                    });
+                }
+            }
+            let expected_status = options.expected_status(&url);
 
-            // Record the HTTP status code
-            page.status_code = Some(response.status());
-            if !response.status().is_success() {
+            // Redirects that the redirect policy does not allow are returned instead of being followed
+            let blocked_redirect = header_str(LOCATION)
+                .filter(|_| response.status.is_redirection())
+                .and_then(|location| response.url.join(&location).ok())
+                .filter(|location| !options.redirect_policy.allows(&url, location));
+            if let (None, Some(location)) = (expected_status, blocked_redirect) {
                 page.good = Some(false);
-                if options.is_rule_enabled(SpiderErrorType::HTTPError, &url) {
-                    page.errors.push(SpiderError {
+                if options.is_rule_enabled(SpiderErrorType::BlockedRedirect, &url) {
+                    error!("Not following redirect from {} to {}", url, location);
+                    found_problem |= page.push_error(SpiderError {
+                        error_type: SpiderErrorType::BlockedRedirect,
+                        source_page: Some(url.to_string()),
+                        target_page: Some(location.to_string()),
+                        http_error_code: Some(response.status.as_u16()),
+                        ..SpiderError::default()
+                    });
+                }
+                break 'page;
+            }
+            let (good, error_type) = match expected_status {
+                // URL has an expected status code, it overrides the goodness policy
+                Some(expected_status) => (
+                    response.status.as_u16() == expected_status,
+                    SpiderErrorType::UnexpectedStatus,
+                ),
+                None => (
+                    options.goodness_policy.is_good_response(
+                        &url,
+                        response.status,
+                        &response.headers,
+                    ),
+                    // Non-2XX responses are HTTP errors, anything else was rejected by the policy
+                    if response.status.is_success() {
+                        SpiderErrorType::RejectedByPolicy
+                    } else {
+                        SpiderErrorType::HTTPError
+                    },
+                ),
+            };
+            page.good = Some(good);
+            if !good {
+                if options.is_rule_enabled(error_type.clone(), &url) {
+                    let severity = options.retrieve_severity(&url);
+                    found_problem |= page.push_error(SpiderError {
                         target_page: Some(url.to_string()),
-                        http_error_code: Some(response.status().as_u16()),
-                        error_type: SpiderErrorType::HTTPError,
+                        http_error_code: Some(response.status.as_u16()),
+                        expected_status_code: expected_status,
+                        error_type,
+                        severity,
                         ..Default::default()
-                    });
-                    return false;
+                    }) && severity == Severity::Error;
                 }
-                return true;
+                break 'page;
             }
 
             // Attempt to get the Content-Type of the page
-            let (parse_html, content_type) = check_content_type(&response);
+            let (parse_html, content_type) = check_content_type(&response.headers);
             page.content_type = content_type.clone();
 
+            // Large text resources should be compressed to save bandwidth
+            if let Some(max_uncompressed_bytes) = options.max_uncompressed_bytes {
+                let size = response
+                    .headers
+                    .get(CONTENT_LENGTH)
+                    .and_then(|l| l.to_str().ok())
+                    .and_then(|l| l.parse::<usize>().ok());
+                if let Some(size) = size {
+                    if response.compressed == Some(false)
+                        && size > max_uncompressed_bytes
+                        && is_compressible_content_type(content_type.as_deref())
+                        && check_host(&options.hosts, &url)
+                        && options.is_rule_enabled(SpiderErrorType::UncompressedResource, &url)
+                    {
+                        warn!("{} is {} bytes, but was not compressed", url, size);
+                        page.push_error(SpiderError {
+                            error_type: SpiderErrorType::UncompressedResource,
+                            target_page: Some(url.to_string()),
+                            size: Some(size),
+                            severity: Severity::Warning,
+                            ..SpiderError::default()
+                        });
+                    }
+                }
+            }
+
+            if is_asset {
+                info!("Not parsing {}, it is an asset", url);
+                break 'page;
+            }
+
+            // Paginated resources link to their other pages through the Link header
+            if options.follow_link_headers && in_host {
+                let mut page_map = page_map_mutex.lock().unwrap();
+                for value in response.headers.get_all(LINK) {
+                    let value = value.to_str().unwrap_or_default();
+                    for url_str in get_link_header_urls(value, &["next", "prev"]) {
+                        let next_url = match parse_relative_or_absolute_url(
+                            &url,
+                            &url_str,
+                            &options.query_params,
+                        ) {
+                            Some(next_url) => next_url,
+                            None => {
+                                warn!("Invalid URL {} in Link header of {}", url_str, url);
+                                continue;
+                            }
+                        };
+                        let new_node = add_link(
+                            &mut graph,
+                            &mut page_map,
+                            options,
+                            node_index,
+                            &next_url,
+                            format!("Link: {}", value),
+                        );
+                        if let Some(new_node) = new_node {
+                            if options.is_within_max_depth(&url, &next_url, current_depth) {
+                                new_nodes.push((new_node, next_url));
+                            }
+                        }
+                    }
+                }
+            }
+
             // If Content-Type is not HTML, then don't try to parse the HTML
-            if !parse_html {
+            is_text_page = !parse_html
+                && options.lenient_html
+                && is_text_content_type(content_type.as_deref());
+            // SVG sprites hold the symbols that the fragments of `<use>` elements point to
+            let is_svg = content_type.as_deref() == Some("image/svg+xml");
+            if !parse_html && !is_text_page && !is_svg {
                 warn!(
                     "Not parsing HTML for: {}, Content-Type is {:?}",
                     url, content_type
                 );
-                return true;
+                break 'page;
             }
 
-            // Check to see if the domain is inside the starting domain.
-            let parse_html = check_host(&options.hosts, &url);
+            // Pages outside of the domain and SVG images are only parsed for the anchors that links point to
+            anchors_only = !is_text_page
+                && if in_host {
+                    is_svg && options.check_fragments
+                } else {
+                    options.check_external_fragments
+                };
 
-            if !parse_html {
+            if !in_host && !anchors_only {
                 info!("Not parsing HTML for: {}, outside of domain", url);
-                return true;
+                break 'page;
+            }
+            if is_svg && !anchors_only {
+                info!("Not parsing SVG image {}, fragments are not checked", url);
+                break 'page;
             }
         }
 
         // Get the Contents of the page
-        let contents = response.text().await;
+        let contents = response.body.await;
+        drop(request_permit);
 
         // Acquire a lock on the graph so that we can update it with our findings for this page
         let mut graph = graph_mutex.lock().unwrap();
         {
             let page = graph.node_weight_mut(node_index).unwrap();
-            if contents.is_err() {
+            if let Err(e) = &contents {
                 page.good = Some(false);
 
                 if options.is_rule_enabled(SpiderErrorType::UnableToRetrieve, &url) {
                     error!("Failed to get contents of page! {}", url);
-                    page.errors.push(SpiderError {
+                    found_problem |= page.push_error(SpiderError {
                         target_page: Some(url.to_string()),
                         error_type: SpiderErrorType::UnableToRetrieve,
+                        reason: Some(get_retrieve_failure_reason(e)),
                         ..Default::default()
                     });
-                    return false;
                 }
-                return true;
+                break 'page;
             }
         }
         let contents = contents.unwrap();
+        if is_text_page {
+            info!("Scanning text of page {} for URLs", url);
+            let mut page_map = page_map_mutex.lock().unwrap();
+            add_text_links(
+                &mut graph,
+                &mut page_map,
+                options,
+                node_index,
+                &url,
+                &contents,
+                current_depth,
+                &mut new_nodes,
+            );
+            break 'page;
+        }
         let html = Html::parse_document(contents.as_str());
+        if anchors_only || options.check_fragments {
+            let page = graph.node_weight_mut(node_index).unwrap();
+            page.anchors = Some(get_anchors(&html));
+        }
+        if anchors_only {
+            break 'page;
+        }
+        // Pages can opt out of checks with a meta element, on top of the rules ignored by the options
+        let skipped_rules = get_skipped_rules(&html, &url);
+        let is_rule_enabled = |rule: SpiderErrorType| {
+            !skipped_rules.contains(&rule) && options.is_rule_enabled(rule, &url)
+        };
+        // Relative URLs in the HTML are resolved against this URL
+        let base_url = options.force_base.as_ref().unwrap_or(&url);
         {
             let page = graph.node_weight_mut(node_index).unwrap();
-            page.good = Some(true);
+            if !options.goodness_policy.is_good_content(&url, &contents) {
+                page.good = Some(false);
+                if is_rule_enabled(SpiderErrorType::RejectedByPolicy) {
+                    found_problem |= page.push_error(SpiderError {
+                        target_page: Some(url.to_string()),
+                        error_type: SpiderErrorType::RejectedByPolicy,
+                        ..Default::default()
+                    });
+                }
+            }
+
+            if let Some(max_page_bytes) = options.max_page_bytes {
+                if contents.len() > max_page_bytes && is_rule_enabled(SpiderErrorType::PageTooLarge)
+                {
+                    warn!(
+                        "Page at {} is {} bytes, larger than {} bytes",
+                        url.as_str(),
+                        contents.len(),
+                        max_page_bytes
+                    );
+                    page.push_error(SpiderError {
+                        error_type: SpiderErrorType::PageTooLarge,
+                        source_page: Some(url.to_string()),
+                        size: Some(contents.len()),
+                        severity: Severity::Warning,
+                        ..SpiderError::default()
+                    });
+                }
+            }
 
             let mut title_element = html.select(options.title_selector.as_ref());
             let title_element = title_element.next();
-            if title_element.is_some() {
-                page.title = Some(title_element.unwrap().inner_html())
-            } else if options.is_rule_enabled(SpiderErrorType::MissingTitle, &url) {
-                page.errors.push(SpiderError {
+            if let Some(title_element) = title_element {
+                page.title = Some(title_element.inner_html())
+            } else if is_rule_enabled(SpiderErrorType::MissingTitle) {
+                page.push_error(SpiderError {
                     error_type: SpiderErrorType::MissingTitle,
                     source_page: Some(url.to_string()),
                     ..SpiderError::default()
                 });
                 warn!("Page at {} does not have a title!", url.as_str());
             }
+
+            if options.record_word_count || options.min_word_count.is_some() {
+                let word_count = get_word_count(&html);
+                page.word_count = Some(word_count);
+                if let Some(min_word_count) = options.min_word_count {
+                    if word_count < min_word_count && is_rule_enabled(SpiderErrorType::ThinContent)
+                    {
+                        warn!(
+                            "Page at {} only has {} words, fewer than {} words",
+                            url.as_str(),
+                            word_count,
+                            min_word_count
+                        );
+                        page.push_error(SpiderError {
+                            error_type: SpiderErrorType::ThinContent,
+                            source_page: Some(url.to_string()),
+                            count: Some(word_count),
+                            severity: Severity::Warning,
+                            ..SpiderError::default()
+                        });
+                    }
+                }
+            }
         }
 
         info!("Visited page {}", url.as_str());
 
-        let elements = html.select(options.element_selector.as_ref());
+        if is_rule_enabled(SpiderErrorType::InvalidSrcset) {
+            for e in html.select(options.srcset_selector.as_ref()) {
+                let srcset = e.attr("srcset").unwrap_or_default();
+                if let Err(reason) = validate_srcset(srcset, e.attr("sizes").is_some()) {
+                    warn!("Invalid srcset on page {}: {}", url.as_str(), reason);
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    page.push_error(SpiderError {
+                        error_type: SpiderErrorType::InvalidSrcset,
+                        source_page: Some(url.to_string()),
+                        attribute: Some("srcset".to_string()),
+                        html: Some(e.html()),
+                        severity: Severity::Warning,
+                        ..SpiderError::default()
+                    });
+                }
+            }
+        }
+
+        if options.check_aria_references && is_rule_enabled(SpiderErrorType::BrokenAriaReference) {
+            let page = graph.node_weight_mut(node_index).unwrap();
+            for (e, attribute, id) in get_broken_aria_references(&html) {
+                error!(
+                    "Element on page {} references missing id {} in {}",
+                    url, id, attribute
+                );
+                let mut target = url.clone();
+                target.set_fragment(Some(id));
+                found_problem |= page.push_error(SpiderError {
+                    error_type: SpiderErrorType::BrokenAriaReference,
+                    source_page: Some(url.to_string()),
+                    target_page: Some(target.to_string()),
+                    attribute: Some(attribute.to_string()),
+                    html: Some(e.html()),
+                    ..SpiderError::default()
+                });
+            }
+        }
+
+        if options.check_lang {
+            let page = graph.node_weight_mut(node_index).unwrap();
+            let lang = html
+                .root_element()
+                .attr("lang")
+                .map(str::trim)
+                .filter(|lang| !lang.is_empty());
+            match lang {
+                None if is_rule_enabled(SpiderErrorType::MissingLang) => {
+                    error!("Page {} does not declare its language!", url);
+                    found_problem |= page.push_error(SpiderError {
+                        error_type: SpiderErrorType::MissingLang,
+                        source_page: Some(url.to_string()),
+                        attribute: Some("lang".to_string()),
+                        ..SpiderError::default()
+                    });
+                }
+                Some(lang)
+                    if !is_valid_language_tag(lang)
+                        && is_rule_enabled(SpiderErrorType::InvalidLang) =>
+                {
+                    error!("Page {} declares the invalid language {:?}!", url, lang);
+                    found_problem |= page.push_error(SpiderError {
+                        error_type: SpiderErrorType::InvalidLang,
+                        source_page: Some(url.to_string()),
+                        attribute: Some("lang".to_string()),
+                        html: Some(format!("<html lang=\"{}\">", lang)),
+                        ..SpiderError::default()
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // The parser treats the content of <noscript> elements as text, like a browser with JavaScript enabled,
+        //  so their fallback links and images are only checked if they are parsed again
+        let noscript_fragments = if options.check_noscript {
+            parse_noscript_fragments(&html, options)
+        } else {
+            vec![]
+        };
+        let elements = html.select(options.element_selector.as_ref()).chain(
+            noscript_fragments
+                .iter()
+                .flat_map(|f| f.select(options.element_selector.as_ref())),
+        );
 
         let mut page_map = page_map_mutex.lock().unwrap();
 
@@ -190,17 +980,55 @@ pub async fn visit_page(
                 continue;
             }
 
+            if is_skipped_by_selector(l, options) {
+                // Link matches the skip selector, or is inside an element that does
+                continue;
+            }
+
+            // Links without any text are not usable with a screen reader
+            if options.check_link_text
+                && l.value().name() == "a"
+                && !has_accessible_name(l)
+                && is_rule_enabled(SpiderErrorType::EmptyLinkText)
+            {
+                error!("Link on page {} has no text: {}", url, l.html());
+
+                let page = graph.node_weight_mut(node_index).unwrap();
+                found_problem |= page.push_error(SpiderError {
+                    error_type: SpiderErrorType::EmptyLinkText,
+                    source_page: Some(url.to_string()),
+                    html: Some(l.html()),
+                    ..SpiderError::default()
+                });
+            }
+
+            // Email links are not visited, so their addresses are only checked for typos
+            if let Some(mailto) = get_url_attribute(l)
+                .filter(|s| options.validate_mailto && s.trim_start().starts_with("mailto:"))
+            {
+                if !is_valid_mailto(mailto) && is_rule_enabled(SpiderErrorType::InvalidURL) {
+                    error!("Page {} links to the invalid email address {}", url, mailto);
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    found_problem |= page.push_error(SpiderError {
+                        error_type: SpiderErrorType::InvalidURL,
+                        source_page: Some(url.to_string()),
+                        target_page: Some(mailto.to_string()),
+                        html: Some(l.html()),
+                        ..SpiderError::default()
+                    });
+                }
+                continue;
+            }
+
             // Parse out a URL from the link
-            let next_url = get_url_from_element(l, &url);
-            if next_url.is_err() {
-                let err = next_url.unwrap_err();
-                if options.is_rule_enabled(err.error_type.clone(), &url) {
+            let next_url =
+                get_url_from_element(l, &url, options.force_base.as_ref(), &options.query_params);
+            if let Err(err) = next_url {
+                if is_rule_enabled(err.error_type.clone()) {
                     error!("Failed to get URL from element: {}", l.html());
 
-                    found_problem = true;
-
                     let page = graph.node_weight_mut(node_index).unwrap();
-                    page.errors.push(err);
+                    found_problem |= page.push_error(err);
                 }
                 continue;
             }
@@ -208,19 +1036,18 @@ pub async fn visit_page(
             let next_url = next_url.unwrap();
             if next_url.is_none() {
                 // Element did not contain a URL, but it was not required, so make sure it's innerHTML contains content
-                // This case only happens for <script> elements
-                if l.inner_html().trim().is_empty()
-                    && options.is_rule_enabled(SpiderErrorType::EmptyScript, &url)
+                // Links that do not lead to a page are also skipped here
+                if l.value().name() == "script"
+                    && l.inner_html().trim().is_empty()
+                    && is_rule_enabled(SpiderErrorType::EmptyScript)
                 {
                     error!(
                         "Script element at page {} is missing content!",
                         url.as_str()
                     );
 
-                    found_problem = true;
-
                     let page = graph.node_weight_mut(node_index).unwrap();
-                    page.errors.push(SpiderError {
+                    found_problem |= page.push_error(SpiderError {
                         error_type: SpiderErrorType::EmptyScript,
                         source_page: Some(url.to_string()),
                         ..SpiderError::default()
@@ -230,47 +1057,435 @@ pub async fn visit_page(
             }
             let next_url = next_url.unwrap();
 
-            // Check to see if the target URL has already been visited
-            let existing_page = page_map.get(&next_url);
-            if existing_page.is_some() {
-                // Target URL has already been visited
-                graph.add_edge(node_index, *existing_page.unwrap(), Link { html: l.html() });
-                continue;
+            // Relative URLs that go above the root of the host are silently clamped to the root, which hides authoring mistakes
+            if let Some(url_str) = get_url_attribute(l).filter(|s| escapes_host_root(base_url, s)) {
+                if is_rule_enabled(SpiderErrorType::PathTraversal) {
+                    warn!(
+                        "Page {} links to {}, which goes above the root of the host",
+                        url, url_str
+                    );
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    page.push_error(SpiderError {
+                        error_type: SpiderErrorType::PathTraversal,
+                        source_page: Some(url.to_string()),
+                        target_page: Some(url_str.to_string()),
+                        html: Some(l.html()),
+                        severity: Severity::Warning,
+                        ..SpiderError::default()
+                    });
+                }
+            }
+
+            // Absolute links within the website should use its canonical scheme and host, old ones are often left over from a migration
+            if let Some(canonical_origin) = &options.canonical_origin {
+                let url_str = get_url_attribute(l).unwrap_or_default();
+                let is_absolute = Url::parse(url_str).is_ok() || is_protocol_relative_url(url_str);
+                let canonical_url = get_canonical_url(canonical_origin, &next_url).filter(|_| {
+                    is_absolute
+                        && (check_host(&options.hosts, &next_url)
+                            || check_host(&options.stale_hosts, &next_url))
+                });
+                if let Some(canonical_url) = canonical_url {
+                    if is_rule_enabled(SpiderErrorType::WrongCanonicalHost) {
+                        warn!(
+                            "Page {} links to {}, which should be {}",
+                            url, next_url, canonical_url
+                        );
+                        let page = graph.node_weight_mut(node_index).unwrap();
+                        page.push_error(SpiderError {
+                            error_type: SpiderErrorType::WrongCanonicalHost,
+                            source_page: Some(url.to_string()),
+                            target_page: Some(next_url.to_string()),
+                            canonical_url: Some(canonical_url.to_string()),
+                            html: Some(l.html()),
+                            severity: Severity::Warning,
+                            ..SpiderError::default()
+                        });
+                    }
+                }
+            }
+
+            // Links to the page they are on do not need its scheme and host
+            if options.check_absolute_self_links && l.value().name() == "a" {
+                let url_str = get_url_attribute(l).unwrap_or_default();
+                let is_absolute = Url::parse(url_str).is_ok() || is_protocol_relative_url(url_str);
+                let mut target = next_url.clone();
+                target.set_fragment(None);
+                if is_absolute
+                    && target == url
+                    && is_rule_enabled(SpiderErrorType::RedundantAbsoluteSelfLink)
+                {
+                    warn!(
+                        "Page {} links to itself with the absolute URL {}",
+                        url, url_str
+                    );
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    page.push_error(SpiderError {
+                        error_type: SpiderErrorType::RedundantAbsoluteSelfLink,
+                        source_page: Some(url.to_string()),
+                        target_page: Some(url_str.to_string()),
+                        html: Some(l.html()),
+                        severity: Severity::Warning,
+                        ..SpiderError::default()
+                    });
+                }
+            }
+
+            // Linking directly to the 404 page is usually a mistake
+            if let Some(not_found_page_path) = &options.not_found_page_path {
+                if next_url.path() == not_found_page_path
+                    && check_host(&options.hosts, &next_url)
+                    && is_rule_enabled(SpiderErrorType::LinkTo404Page)
+                {
+                    warn!("Page {} links directly to the 404 page {}", url, next_url);
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    page.push_error(SpiderError {
+                        error_type: SpiderErrorType::LinkTo404Page,
+                        source_page: Some(url.to_string()),
+                        target_page: Some(next_url.to_string()),
+                        html: Some(l.html()),
+                        severity: Severity::Warning,
+                        ..SpiderError::default()
+                    });
+                }
+            }
+
+            // Remember localized alternates of this page so their reciprocity can be checked later
+            if l.value().name() == "link"
+                && l.attr("hreflang").is_some()
+                && l.attr("rel")
+                    .is_some_and(|rel| rel.split_whitespace().any(|r| r == "alternate"))
+            {
+                let page = graph.node_weight_mut(node_index).unwrap();
+                page.hreflang_alternates.push(next_url.clone());
+            }
+
+            // Fragments of links are checked once all pages have been visited
+            let is_next_in_host = check_host(&options.hosts, &next_url);
+            if (is_next_in_host && options.check_fragments)
+                || (!is_next_in_host && options.check_external_fragments)
+            {
+                let fragment = get_url_attribute(l)
+                    .and_then(|s| base_url.join(s).ok())
+                    .and_then(|u| u.fragment().map(String::from));
+                if let Some(fragment) = fragment {
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    page.linked_fragments.push((next_url.clone(), fragment));
+                }
+            }
+
+            if options.record_render_blocking && is_render_blocking(l) {
+                let page = graph.node_weight_mut(node_index).unwrap();
+                page.render_blocking_resources.push(next_url.clone());
+            }
+
+            // Protocol-relative URLs inherit the scheme of the current page, so they are easy to write by accident
+            let protocol_relative_url =
+                get_url_attribute(l).filter(|s| is_protocol_relative_url(s));
+            if let Some(url_str) = protocol_relative_url {
+                if is_likely_mistyped_relative_url(url_str) {
+                    warn!(
+                        "Page {} links to protocol-relative URL {}, was a relative path intended?",
+                        url, url_str
+                    );
+                }
+                if options.protocol_relative_in_host && !check_host(&options.hosts, &next_url) {
+                    info!(
+                        "Protocol-relative URL {} on page {} points outside of the crawled hosts",
+                        url_str, url
+                    );
+                }
+            }
+
+            let new_node = add_link(
+                &mut graph,
+                &mut page_map,
+                options,
+                node_index,
+                &next_url,
+                l.html(),
+            );
+            if let Some(new_node) = new_node {
+                let new_page = graph.node_weight_mut(new_node).unwrap();
+                new_page.subresource = is_subresource(l);
+                new_page.protocol_relative = protocol_relative_url.is_some();
+                if !options.is_within_max_depth(&url, &next_url, current_depth) {
+                    // If we have reached max depth, then do not add the new node to the
+                    // new_nodes list. This prevents us from visiting those nodes after
+                    // this loop finishes
+                    continue;
+                }
+
+                new_nodes.push((new_node, next_url));
+            } else if protocol_relative_url.is_none() {
+                // Pages that are also linked without a protocol-relative URL are parsed, no matter which link was found first
+                let existing_node = page_map[&next_url];
+                graph
+                    .node_weight_mut(existing_node)
+                    .unwrap()
+                    .protocol_relative = false;
+            }
+        }
+
+        // URLs in inline event handlers and data-* attributes are found with a heuristic, so they are only checked when asked for
+        if !options.inline_url_attributes.is_empty() {
+            for e in html
+                .root_element()
+                .descendants()
+                .filter_map(ElementRef::wrap)
+            {
+                if e.has_class(&options.skip_class, scraper::CaseSensitivity::CaseSensitive)
+                    || is_skipped_by_selector(e, options)
+                {
+                    continue;
+                }
+
+                for attribute in &options.inline_url_attributes {
+                    let Some(value) = e.attr(attribute) else {
+                        continue;
+                    };
+                    for url_str in get_urls_from_inline_attribute(value) {
+                        let Some(next_url) = parse_relative_or_absolute_url(
+                            base_url,
+                            &url_str,
+                            &options.query_params,
+                        ) else {
+                            info!(
+                                "Ignoring possible URL {} in {} attribute on page {}",
+                                url_str, attribute, url
+                            );
+                            continue;
+                        };
+
+                        let new_node = add_link(
+                            &mut graph,
+                            &mut page_map,
+                            options,
+                            node_index,
+                            &next_url,
+                            e.html(),
+                        );
+                        if let Some(new_node) = new_node {
+                            if options.is_within_max_depth(&url, &next_url, current_depth) {
+                                new_nodes.push((new_node, next_url));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Custom components keep their URLs in attributes that are configured by the user
+        for (selector, attribute) in &options.extra_url_attributes {
+            for e in html.select(selector) {
+                if e.has_class(&options.skip_class, scraper::CaseSensitivity::CaseSensitive)
+                    || is_skipped_by_selector(e, options)
+                {
+                    continue;
+                }
+                let Some(url_str) = e.attr(attribute) else {
+                    continue;
+                };
+
+                let next_url = Some(url_str).filter(|u| !u.is_empty()).and_then(|u| {
+                    parse_relative_or_absolute_url(base_url, u, &options.query_params)
+                });
+                if let Some(next_url) = next_url {
+                    let new_node = add_link(
+                        &mut graph,
+                        &mut page_map,
+                        options,
+                        node_index,
+                        &next_url,
+                        e.html(),
+                    );
+                    if let Some(new_node) = new_node {
+                        if options.is_within_max_depth(&url, &next_url, current_depth) {
+                            new_nodes.push((new_node, next_url));
+                        }
+                    }
+                    continue;
+                }
+
+                let error_type = if url_str.is_empty() {
+                    SpiderErrorType::EmptyAttribute
+                } else {
+                    SpiderErrorType::InvalidURL
+                };
+                if is_rule_enabled(error_type.clone()) {
+                    error!("Invalid {} attribute on page {}", attribute, url);
+                    let page = graph.node_weight_mut(node_index).unwrap();
+                    found_problem |= page.push_error(SpiderError {
+                        error_type,
+                        attribute: Some(attribute.clone()),
+                        source_page: Some(url.to_string()),
+                        target_page: (!url_str.is_empty()).then(|| url_str.to_string()),
+                        html: Some(e.html()),
+                        ..SpiderError::default()
+                    });
+                }
             }
+        }
+
+        if options.check_json_ld {
+            for script in html.select(options.json_ld_selector.as_ref()) {
+                let json = match serde_json::from_str::<Value>(&script.inner_html()) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        if is_rule_enabled(SpiderErrorType::InvalidJsonLd) {
+                            warn!("Failed to parse JSON-LD on page {}: {}", url, e);
+                            let page = graph.node_weight_mut(node_index).unwrap();
+                            page.push_error(SpiderError {
+                                error_type: SpiderErrorType::InvalidJsonLd,
+                                source_page: Some(url.to_string()),
+                                html: Some(script.html()),
+                                severity: Severity::Warning,
+                                ..SpiderError::default()
+                            });
+                        }
+                        continue;
+                    }
+                };
 
-            // Target URL has not been visited yet, add a node to the graph
-            let new_node = graph.add_node(Page::new(&next_url));
+                for (key, url_str) in get_urls_from_json_ld(&json) {
+                    let link_html = format!("\"{}\": \"{}\"", key, url_str);
+                    let next_url =
+                        parse_relative_or_absolute_url(base_url, &url_str, &options.query_params);
+                    let next_url = match next_url {
+                        Some(next_url) => next_url,
+                        None => {
+                            if is_rule_enabled(SpiderErrorType::InvalidURL) {
+                                error!("Invalid URL in JSON-LD on page {}: {}", url, url_str);
+                                let page = graph.node_weight_mut(node_index).unwrap();
+                                found_problem |= page.push_error(SpiderError {
+                                    error_type: SpiderErrorType::InvalidURL,
+                                    source_page: Some(url.to_string()),
+                                    target_page: Some(url_str),
+                                    html: Some(link_html),
+                                    ..SpiderError::default()
+                                });
+                            }
+                            continue;
+                        }
+                    };
+
+                    let new_node = add_link(
+                        &mut graph,
+                        &mut page_map,
+                        options,
+                        node_index,
+                        &next_url,
+                        link_html,
+                    );
+                    if let Some(new_node) = new_node {
+                        if options.is_within_max_depth(&url, &next_url, current_depth) {
+                            new_nodes.push((new_node, next_url));
+                        }
+                    }
+                }
+            }
+        }
 
-            // Add an edge to the graph connecting current page to the target page
-            graph.add_edge(node_index, new_node, Link { html: l.html() });
+        // Links that the HTML parser dropped (ie. in malformed markup) are found in the raw body
+        if options.lenient_html {
+            add_text_links(
+                &mut graph,
+                &mut page_map,
+                options,
+                node_index,
+                &url,
+                &contents,
+                current_depth,
+                &mut new_nodes,
+            );
+        }
+    }
 
-            // Add an entry to the page HashMap to mark that we're going to visit the page
-            page_map.insert(next_url.clone(), new_node);
+    if !follow_links {
+        new_nodes.clear();
+    }
 
-            if current_depth == options.max_depth {
-                // If we have reached max depth, then do not add the new node to the
-                // new_nodes list. This prevents us from visiting those nodes after
-                // this loop finishes
+    // Pages that robots.txt disallows crawling are not visited.
+    // Only the robots.txt of the hosts that are crawled is retrieved, links to other websites are only checked
+    if options.respect_robots {
+        let mut allowed_nodes = Vec::<(NodeIndex, Url)>::with_capacity(new_nodes.len());
+        for (node, next_url) in new_nodes {
+            if !check_host(&options.hosts, &next_url) {
+                allowed_nodes.push((node, next_url));
+                continue;
+            }
+            context.robots.fetch_rules(&next_url, context).await;
+            if context.robots.is_allowed(&next_url) {
+                allowed_nodes.push((node, next_url));
                 continue;
             }
+            info!("Not visiting {}, it is disallowed by robots.txt", next_url);
+            if options.strict_robots
+                && options.is_rule_enabled(SpiderErrorType::RobotsDisallowed, &url)
+            {
+                error!(
+                    "Page {} links to {}, which robots.txt disallows",
+                    url, next_url
+                );
+                let mut graph = graph_mutex.lock().unwrap();
+                let page = graph.node_weight_mut(node_index).unwrap();
+                found_problem |= page.push_error(SpiderError {
+                    error_type: SpiderErrorType::RobotsDisallowed,
+                    source_page: Some(url.to_string()),
+                    target_page: Some(next_url.to_string()),
+                    ..SpiderError::default()
+                });
+            }
+        }
+        new_nodes = allowed_nodes;
+    }
 
-            new_nodes.push((new_node, next_url));
+    if context.event_sender.is_some() {
+        let graph = graph_mutex.lock().unwrap();
+        let page = graph.node_weight(node_index).unwrap();
+        context.send_event(CrawlEvent::PageFinished {
+            url: url.clone(),
+            status_code: page.status_code.map(|s| s.as_u16()),
+            good: page.good,
+            last_modified: page.last_modified.clone(),
+            etag: page.etag.clone(),
+        });
+        for error in &page.errors {
+            context.send_event(CrawlEvent::error_found(&url, error));
         }
     }
 
+    // The page is done, only its errors are needed from now on
+    if options.memory_bounded {
+        let mut graph = graph_mutex.lock().unwrap();
+        graph.node_weight_mut(node_index).unwrap().evict();
+    }
+
     let mut futures_vec = Vec::new();
     futures_vec.reserve_exact(new_nodes.len());
 
     // Create a future for each node we discovered
     for (node, next_url) in new_nodes {
+        if !options.is_url_matched(&next_url) {
+            info!(
+                "Not visiting {}, it does not match the URL filter",
+                next_url
+            );
+            continue;
+        }
+        if !options.is_external_link_checked(&next_url, current_depth) {
+            info!("Not checking external link {}", next_url);
+            continue;
+        }
+        let next_depth = options.link_depth(&url, &next_url, current_depth);
         futures_vec.push(visit_page(
             node,
             next_url,
-            client,
-            options,
+            context,
             graph_mutex,
             page_map_mutex,
-            current_depth + 1,
+            next_depth,
+            true,
         ));
     }
 
@@ -285,8 +1500,7 @@ pub async fn visit_page(
 /// Entry point to the page traversal algorithm.
 pub async fn visit_root_page(
     url: &Url,
-    client: &Client,
-    options: &SpiderOptions,
+    context: &CrawlContext<'_>,
     graph: &Mutex<&mut PageGraph>,
     page_map: &Mutex<&mut PageMap>,
 ) -> bool {
@@ -300,5 +1514,159 @@ pub async fn visit_root_page(
         page_map.lock().unwrap().insert(url.clone(), root_index);
     }
 
-    visit_page(root_index, url.clone(), client, options, graph, page_map, 0).await
+    visit_page(root_index, url.clone(), context, graph, page_map, 0, true).await
+}
+
+/// Visits each page in `urls` and then recursively calls `visit_page()` on all links contained in those pages.
+/// Entry point to the page traversal algorithm when starting from multiple pages.
+pub async fn visit_root_pages(
+    urls: &[Url],
+    context: &CrawlContext<'_>,
+    graph: &Mutex<&mut PageGraph>,
+    page_map: &Mutex<&mut PageMap>,
+) -> bool {
+    let mut roots = Vec::<(NodeIndex, Url)>::new();
+    {
+        // Insert all root pages before visiting any of them,
+        //  so that links between root pages do not cause a page to be visited twice
+        let mut graph = graph.lock().unwrap();
+        let mut page_map = page_map.lock().unwrap();
+        for url in urls {
+            if page_map.contains_key(url) {
+                continue;
+            }
+            let root_index = graph.add_node(Page::new(url));
+            page_map.insert(url.clone(), root_index);
+            roots.push((root_index, url.clone()));
+        }
+    }
+
+    let futures_vec = roots
+        .into_iter()
+        .map(|(root_index, url)| visit_page(root_index, url, context, graph, page_map, 0, true));
+
+    let result = futures::future::join_all(futures_vec).await;
+
+    !result.contains(&false)
+}
+
+/// Checks that every visited page declared as an hreflang alternate declares the original page as an alternate in return.
+/// Records a `HreflangMismatch` error on pages whose alternates do not link back.
+/// Returns `true` if no errors were found.
+pub fn check_hreflang_reciprocity(
+    graph: &mut PageGraph,
+    page_map: &PageMap,
+    options: &SpiderOptions,
+) -> bool {
+    let mut mismatches = Vec::<(NodeIndex, Url)>::new();
+    for node_index in graph.node_indices() {
+        let page = graph.node_weight(node_index).unwrap();
+        for alternate in &page.hreflang_alternates {
+            if *alternate == page.url {
+                continue;
+            }
+            let alternate_page = match page_map.get(alternate) {
+                Some(i) => graph.node_weight(*i).unwrap(),
+                None => continue,
+            };
+            // Only pages that were parsed can be checked for reciprocity
+            if alternate_page.good != Some(true) {
+                continue;
+            }
+            if !alternate_page.hreflang_alternates.contains(&page.url) {
+                mismatches.push((node_index, alternate.clone()));
+            }
+        }
+    }
+
+    let mut result = true;
+    for (node_index, alternate) in mismatches {
+        let page = graph.node_weight_mut(node_index).unwrap();
+        if !options.is_rule_enabled(SpiderErrorType::HreflangMismatch, &page.url) {
+            continue;
+        }
+        error!(
+            "Page {} declares {} as an alternate, but it does not link back!",
+            page.url, alternate
+        );
+        if page.push_error(SpiderError {
+            error_type: SpiderErrorType::HreflangMismatch,
+            source_page: Some(page.url.to_string()),
+            target_page: Some(alternate.to_string()),
+            ..SpiderError::default()
+        }) {
+            result = false;
+        }
+    }
+    result
+}
+
+/// Checks that every fragment in `Page::linked_fragments` names an anchor on the linked page.
+/// Only linked pages that were parsed for their anchors are checked. The empty fragment and `#top` always scroll to the top of a page.
+/// Records a `MissingFragment` error on pages that link to a missing anchor.
+/// Returns `true` if no errors were found.
+pub fn check_fragments(graph: &mut PageGraph, page_map: &PageMap, options: &SpiderOptions) -> bool {
+    let mut missing = Vec::<(NodeIndex, Url)>::new();
+    for node_index in graph.node_indices() {
+        let page = graph.node_weight(node_index).unwrap();
+        for (target, fragment) in &page.linked_fragments {
+            if fragment.is_empty() || fragment.eq_ignore_ascii_case("top") {
+                continue;
+            }
+            let anchors = page_map
+                .get(target)
+                .and_then(|i| graph.node_weight(*i).unwrap().anchors.as_ref());
+            if anchors.is_some_and(|a| !a.contains(fragment)) {
+                let mut target = target.clone();
+                target.set_fragment(Some(fragment));
+                missing.push((node_index, target));
+            }
+        }
+    }
+
+    let mut result = true;
+    for (node_index, target) in missing {
+        let page = graph.node_weight_mut(node_index).unwrap();
+        if !options.is_rule_enabled(SpiderErrorType::MissingFragment, &page.url) {
+            continue;
+        }
+        error!(
+            "Page {} links to {}, but the linked page has no such anchor!",
+            page.url, target
+        );
+        if page.push_error(SpiderError {
+            error_type: SpiderErrorType::MissingFragment,
+            source_page: Some(page.url.to_string()),
+            target_page: Some(target.to_string()),
+            ..SpiderError::default()
+        }) {
+            result = false;
+        }
+    }
+    result
+}
+
+/// Visits the page pointed to by `url` without visiting any of the pages it links to.
+/// Returns the `Page` that was recorded for `url`.
+pub async fn check_page(url: &Url, context: &CrawlContext<'_>) -> Page {
+    let mut graph = PageGraph::new();
+    let mut page_map = PageMap::new();
+    let root_index = graph.add_node(Page::new(url));
+    page_map.insert(url.clone(), root_index);
+
+    let graph_mutex = Mutex::new(&mut graph);
+    let page_map_mutex = Mutex::new(&mut page_map);
+
+    visit_page(
+        root_index,
+        url.clone(),
+        context,
+        &graph_mutex,
+        &page_map_mutex,
+        0,
+        false,
+    )
+    .await;
+
+    graph.remove_node(root_index).unwrap()
 }
@@ -0,0 +1,534 @@
+//! Holds the `SpiderConfig` struct used to read `SpiderOptions` from a TOML configuration file
+
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::Method;
+use scraper::selector::CssLocalName;
+use scraper::Selector;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+use url::{Host, Url};
+
+use crate::error::SpiderErrorType;
+use crate::fetch::RedirectPolicy;
+use crate::url_helpers::{QueryParamFilter, Shard};
+use crate::SpiderOptions;
+
+/// Name of the configuration file that is read from the working directory if no other file is given
+pub const DEFAULT_CONFIG_FILE: &str = "spidercrab.toml";
+
+/// Options read from a TOML configuration file. Keys are named after the command line flags (ie. `check-json-ld = true`).
+/// Options that are not set in the file keep their value in `SpiderOptions`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SpiderConfig {
+    /// Additional hosts to crawl (ie. `cdn.example.com`)
+    pub hosts: Vec<String>,
+    pub depth: Option<i32>,
+    pub ignore_query_depth: Option<bool>,
+    pub element_select: Option<String>,
+    pub title_select: Option<String>,
+    pub srcset_select: Option<String>,
+    pub json_ld_select: Option<String>,
+    pub skip_class: Option<String>,
+    pub skip_select: Option<String>,
+    pub protocol_relative_in_host: Option<bool>,
+    pub max_page_bytes: Option<usize>,
+    pub max_uncompressed_bytes: Option<usize>,
+    pub max_url_length: Option<usize>,
+    pub min_word_count: Option<usize>,
+    pub keep_query_params: Option<Vec<String>>,
+    pub strip_query_params: Option<Vec<String>>,
+    pub check_hreflang: Option<bool>,
+    pub not_found_page: Option<String>,
+    pub check_json_ld: Option<bool>,
+    pub lenient_html: Option<bool>,
+    pub check_path_case: Option<bool>,
+    pub check_external_fragments: Option<bool>,
+    pub check_fragments: Option<bool>,
+    pub sitemap_priority_order: Option<bool>,
+    /// Longest `Retry-After` delay to wait for, in seconds
+    pub max_retry_after: Option<u64>,
+    /// Times to retry failed requests
+    pub retries: Option<u32>,
+    /// Milliseconds to wait before the first retry of a failed request
+    pub retry_backoff: Option<u64>,
+    pub memory_bounded: Option<bool>,
+    pub check_link_text: Option<bool>,
+    pub check_noscript: Option<bool>,
+    pub check_aria_references: Option<bool>,
+    pub check_lang: Option<bool>,
+    pub validate_mailto: Option<bool>,
+    pub check_absolute_self_links: Option<bool>,
+    pub follow_link_headers: Option<bool>,
+    pub head_for_assets: Option<bool>,
+    /// Expected HTTP status codes, given as `pattern=status` like `--expect-status`
+    pub expect_status: Vec<String>,
+    /// HTTP methods to request URLs with, given as `pattern=method` like `--request-method`
+    pub request_method: Vec<String>,
+    /// Extra attributes to read URLs from, given as `selector=attribute` like `--url-attribute`
+    pub url_attribute: Vec<String>,
+    pub inline_url_attributes: Option<Vec<String>>,
+    #[serde(rename = "match")]
+    pub url_match: Option<String>,
+    /// Shard of the URLs to fetch, given as `index/count` like `--shard`
+    pub shard: Option<String>,
+    pub canonical_origin: Option<String>,
+    pub force_base: Option<String>,
+    pub stale_hosts: Vec<String>,
+    pub max_external_depth: Option<i32>,
+    pub soft_hosts: Vec<String>,
+    pub check_external: Option<bool>,
+    pub trusted_hosts: Vec<String>,
+    pub index_files: Option<Vec<String>>,
+    /// Hosts that resolve to a fixed IP address, given as `host=ip` like `--resolve`
+    pub resolve: Vec<String>,
+    pub max_concurrency: Option<usize>,
+    pub respect_robots: Option<bool>,
+    pub strict_robots: Option<bool>,
+    /// Milliseconds to wait between requests to the same host
+    pub delay: Option<u64>,
+    pub http2_prior_knowledge: Option<bool>,
+    pub pool_max_idle: Option<usize>,
+    /// Seconds until idle connections are closed
+    pub pool_idle_timeout: Option<u64>,
+    /// Seconds between TCP keepalive probes
+    pub tcp_keepalive: Option<u64>,
+    /// Seconds to wait for a response before giving up on a page
+    pub timeout: Option<u64>,
+    /// Milliseconds a response may take before the page is recorded as slow
+    pub max_response_time: Option<u64>,
+    /// `all`, `same-domain` or `same-host`
+    pub redirect_policy: Option<String>,
+    pub user_agent: Option<String>,
+    /// Extra headers sent with every request, given as `name: value` like `--header`
+    pub header: Vec<String>,
+    /// HTTP Basic Auth credentials, given as `username:password` like `--auth`
+    pub auth: Option<String>,
+    pub cookies: Option<bool>,
+    pub proxy: Option<String>,
+    /// Accept invalid TLS certificates, like `--insecure`
+    pub insecure: Option<bool>,
+    pub report_redirects: Option<bool>,
+    /// URL patterns to ignore, by rule name (ie. `missing-title = ["https://example.com/old.html"]`)
+    pub ignore: HashMap<String, Vec<String>>,
+}
+
+/// Returns an `InvalidData` error for an invalid value of the configuration key `key`
+fn invalid_value(key: &str, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid value for {}: {}", key, message),
+    )
+}
+
+fn parse_selector(key: &str, selector: &str) -> io::Result<Selector> {
+    Selector::parse(selector).map_err(|e| invalid_value(key, format!("{:?}", e)))
+}
+
+/// Parses an extra attribute to read URLs from, given as `selector=attribute` (ie. `[data-href]=data-href`)
+pub fn parse_url_attribute(s: &str) -> Result<(Selector, String), String> {
+    let (selector, attribute) = s
+        .rsplit_once('=')
+        .filter(|(_, attribute)| !attribute.is_empty())
+        .ok_or_else(|| String::from("expected selector=attribute"))?;
+    let selector = Selector::parse(selector).map_err(|e| format!("{:?}", e))?;
+    Ok((selector, attribute.to_string()))
+}
+
+/// Parses an expected HTTP status code given as `pattern=status` (ie. `*/old/*=410`)
+pub fn parse_expected_status(s: &str) -> Option<(String, u16)> {
+    let (pattern, status) = s.rsplit_once('=')?;
+    Some((pattern.to_string(), status.parse::<u16>().ok()?))
+}
+
+/// Parses the HTTP method to request URLs with, given as `pattern=method` (ie. `*/api/*=POST`)
+pub fn parse_request_method(s: &str) -> Option<(String, Method)> {
+    let (pattern, method) = s.rsplit_once('=')?;
+    let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes()).ok()?;
+    Some((pattern.to_string(), method))
+}
+
+/// Parses a request header given as `name: value` (ie. `Accept: application/json`)
+pub fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("{:?}, expected name: value", s))?;
+    let name = HeaderName::from_str(name.trim())
+        .map_err(|_| format!("{:?} is not a valid header name", name.trim()))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("{:?} is not a valid header value", value.trim()))?;
+    Ok((name, value))
+}
+
+#[test]
+fn test_parse_header() {
+    let (name, value) = parse_header("X-Api-Key: secret").unwrap();
+    assert_eq!(name, "x-api-key");
+    assert_eq!(value, "secret");
+    let (name, value) = parse_header("Accept:application/json").unwrap();
+    assert_eq!(name, "accept");
+    assert_eq!(value, "application/json");
+    assert!(parse_header("X-Api-Key").is_err());
+    assert!(parse_header("Bad Name: value").is_err());
+    assert!(parse_header("X-Api-Key: line\nbreak").is_err());
+}
+
+/// Parses HTTP Basic Auth credentials given as `username:password`. The password may contain colons, the user name may not
+pub fn parse_basic_auth(s: &str) -> Option<(String, String)> {
+    let (username, password) = s.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+#[test]
+fn test_parse_basic_auth() {
+    assert_eq!(
+        parse_basic_auth("user:pa:ss"),
+        Some((String::from("user"), String::from("pa:ss")))
+    );
+    assert_eq!(parse_basic_auth("user"), None);
+}
+
+/// Parses a host that resolves to a fixed IP address, given as `host=ip` (ie. `staging.example.com=127.0.0.1`)
+pub fn parse_host_override(s: &str) -> Option<(String, IpAddr)> {
+    let (host, ip) = s.split_once('=')?;
+    Some((host.to_string(), ip.parse::<IpAddr>().ok()?))
+}
+
+impl SpiderConfig {
+    /// Parses a configuration from the TOML document `s`
+    pub fn parse(s: &str) -> io::Result<Self> {
+        toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Reads a configuration from the TOML file at `path`
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Sets every option of `options` that is given by this configuration
+    pub fn apply(&self, options: &mut SpiderOptions) -> io::Result<()> {
+        for host in &self.hosts {
+            options
+                .hosts
+                .push(Host::parse(host).map_err(|e| invalid_value("hosts", e))?);
+        }
+        if let Some(depth) = self.depth {
+            options.max_depth = depth;
+        }
+        if let Some(v) = self.ignore_query_depth {
+            options.ignore_query_depth = v;
+        }
+
+        if let Some(s) = &self.element_select {
+            *options.element_selector = parse_selector("element-select", s)?;
+        }
+        if let Some(s) = &self.title_select {
+            *options.title_selector = parse_selector("title-select", s)?;
+        }
+        if let Some(s) = &self.srcset_select {
+            *options.srcset_selector = parse_selector("srcset-select", s)?;
+        }
+        if let Some(s) = &self.json_ld_select {
+            *options.json_ld_selector = parse_selector("json-ld-select", s)?;
+        }
+        if let Some(s) = &self.skip_class {
+            options.skip_class = CssLocalName::from(s.as_str());
+        }
+        if let Some(s) = &self.skip_select {
+            options.skip_selector = Some(parse_selector("skip-select", s)?);
+        }
+
+        if let Some(names) = &self.keep_query_params {
+            options.query_params = QueryParamFilter::Allow(names.iter().cloned().collect());
+        } else if let Some(names) = &self.strip_query_params {
+            options.query_params = QueryParamFilter::Deny(names.iter().cloned().collect());
+        }
+
+        if let Some(v) = self.protocol_relative_in_host {
+            options.protocol_relative_in_host = v;
+        }
+        if let Some(v) = self.check_hreflang {
+            options.check_hreflang_reciprocity = v;
+        }
+        if let Some(v) = self.check_json_ld {
+            options.check_json_ld = v;
+        }
+        if let Some(v) = self.check_external_fragments {
+            options.check_external_fragments = v;
+        }
+        if let Some(v) = self.check_fragments {
+            options.check_fragments = v;
+        }
+        if let Some(v) = self.check_path_case {
+            options.check_path_case = v;
+        }
+        if let Some(v) = self.lenient_html {
+            options.lenient_html = v;
+        }
+        if let Some(v) = self.sitemap_priority_order {
+            options.sitemap_priority_order = v;
+        }
+        if let Some(v) = self.memory_bounded {
+            options.memory_bounded = v;
+        }
+        if let Some(v) = self.check_link_text {
+            options.check_link_text = v;
+        }
+        if let Some(v) = self.check_noscript {
+            options.check_noscript = v;
+        }
+        if let Some(v) = self.check_aria_references {
+            options.check_aria_references = v;
+        }
+        if let Some(v) = self.check_lang {
+            options.check_lang = v;
+        }
+        if let Some(v) = self.validate_mailto {
+            options.validate_mailto = v;
+        }
+        if let Some(v) = self.check_absolute_self_links {
+            options.check_absolute_self_links = v;
+        }
+        if let Some(v) = self.follow_link_headers {
+            options.follow_link_headers = v;
+        }
+        if let Some(v) = self.head_for_assets {
+            options.head_for_assets = v;
+        }
+
+        if self.max_page_bytes.is_some() {
+            options.max_page_bytes = self.max_page_bytes;
+        }
+        if self.max_uncompressed_bytes.is_some() {
+            options.max_uncompressed_bytes = self.max_uncompressed_bytes;
+        }
+        if self.max_url_length.is_some() {
+            options.max_url_length = self.max_url_length;
+        }
+        if self.min_word_count.is_some() {
+            options.min_word_count = self.min_word_count;
+        }
+        if self.max_external_depth.is_some() {
+            options.max_external_link_depth = self.max_external_depth;
+        }
+        if let Some(v) = self.check_external {
+            options.check_external = v;
+        }
+        if let Some(seconds) = self.max_retry_after {
+            options.max_retry_after = Some(Duration::from_secs(seconds));
+        }
+        if let Some(retries) = self.retries {
+            options.max_retries = retries;
+        }
+        if let Some(ms) = self.retry_backoff {
+            options.retry_backoff = Duration::from_millis(ms);
+        }
+        if self.not_found_page.is_some() {
+            options.not_found_page_path = self.not_found_page.clone();
+        }
+        if let Some(index_files) = &self.index_files {
+            options.index_files = index_files.clone();
+        }
+        if let Some(attributes) = &self.inline_url_attributes {
+            options.inline_url_attributes = attributes.clone();
+        }
+
+        for e in &self.expect_status {
+            let expected_status = parse_expected_status(e).ok_or_else(|| {
+                invalid_value("expect-status", format!("{}, expected pattern=status", e))
+            })?;
+            options.expected_statuses.push(expected_status);
+        }
+        for m in &self.request_method {
+            let request_method = parse_request_method(m).ok_or_else(|| {
+                invalid_value("request-method", format!("{}, expected pattern=method", m))
+            })?;
+            options.request_methods.push(request_method);
+        }
+        for a in &self.url_attribute {
+            let url_attribute =
+                parse_url_attribute(a).map_err(|e| invalid_value("url-attribute", e))?;
+            options.extra_url_attributes.push(url_attribute);
+        }
+
+        if let Some(max_concurrency) = self.max_concurrency {
+            options.max_concurrency = max_concurrency;
+        }
+        if let Some(v) = self.respect_robots {
+            options.respect_robots = v;
+        }
+        if let Some(v) = self.strict_robots {
+            options.strict_robots = v;
+        }
+        if let Some(ms) = self.delay {
+            options.request_delay = Some(Duration::from_millis(ms));
+        }
+        if let Some(v) = self.http2_prior_knowledge {
+            options.http2_prior_knowledge = v;
+        }
+        if self.pool_max_idle.is_some() {
+            options.pool_max_idle_per_host = self.pool_max_idle;
+        }
+        if let Some(seconds) = self.pool_idle_timeout {
+            options.pool_idle_timeout = Some(Duration::from_secs(seconds));
+        }
+        if let Some(seconds) = self.tcp_keepalive {
+            options.tcp_keepalive = Some(Duration::from_secs(seconds));
+        }
+        if let Some(seconds) = self.timeout {
+            options.timeout = Some(Duration::from_secs(seconds));
+        }
+        if let Some(ms) = self.max_response_time {
+            options.max_response_time = Some(Duration::from_millis(ms));
+        }
+        if let Some(v) = self.report_redirects {
+            options.report_redirects = v;
+        }
+        if self.user_agent.is_some() {
+            options.user_agent = self.user_agent.clone();
+        }
+        if let Some(auth) = &self.auth {
+            options.basic_auth = Some(
+                parse_basic_auth(auth)
+                    .ok_or_else(|| invalid_value("auth", "expected username:password"))?,
+            );
+        }
+        if let Some(v) = self.cookies {
+            options.enable_cookies = v;
+        }
+        if self.proxy.is_some() {
+            options.proxy = self.proxy.clone();
+        }
+        if let Some(v) = self.insecure {
+            options.accept_invalid_certs = v;
+        }
+        for h in &self.header {
+            let (name, value) = parse_header(h).map_err(|e| invalid_value("header", e))?;
+            options.headers.append(name, value);
+        }
+        if let Some(policy) = &self.redirect_policy {
+            options.redirect_policy = RedirectPolicy::from_str(policy)
+                .map_err(|e| invalid_value("redirect-policy", e))?;
+        }
+        for r in &self.resolve {
+            let (host, ip) = parse_host_override(r)
+                .ok_or_else(|| invalid_value("resolve", format!("{}, expected host=ip", r)))?;
+            options.host_overrides.insert(host, ip);
+        }
+
+        if let Some(url_match) = &self.url_match {
+            options.url_match = Some(Regex::new(url_match).map_err(|e| invalid_value("match", e))?);
+        }
+        if let Some(shard) = &self.shard {
+            options.shard = Some(Shard::from_str(shard).map_err(|e| invalid_value("shard", e))?);
+        }
+        if let Some(canonical_origin) = &self.canonical_origin {
+            options.canonical_origin = Some(
+                Url::parse(canonical_origin).map_err(|e| invalid_value("canonical-origin", e))?,
+            );
+        }
+        if let Some(force_base) = &self.force_base {
+            options.force_base =
+                Some(Url::parse(force_base).map_err(|e| invalid_value("force-base", e))?);
+        }
+        for host in &self.stale_hosts {
+            options
+                .stale_hosts
+                .push(Host::parse(host).map_err(|e| invalid_value("stale-hosts", e))?);
+        }
+        for host in &self.soft_hosts {
+            options
+                .soft_hosts
+                .push(Host::parse(host).map_err(|e| invalid_value("soft-hosts", e))?);
+        }
+        for host in &self.trusted_hosts {
+            options
+                .trusted_external_hosts
+                .push(Host::parse(host).map_err(|e| invalid_value("trusted-hosts", e))?);
+        }
+
+        for (rule, urls) in &self.ignore {
+            let error_type = SpiderErrorType::from_str(rule)
+                .map_err(|_| invalid_value("ignore", format!("unknown rule {}", rule)))?;
+            options
+                .ignore_patterns
+                .entry(error_type)
+                .or_default()
+                .extend(urls.iter().cloned());
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_apply_config_file() {
+    let path = std::env::temp_dir().join(format!("spidercrab-test-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        r#"
+depth = 3
+hosts = ["cdn.example.com"]
+skip-select = "footer"
+check-json-ld = true
+max-page-bytes = 100000
+expect-status = ["*/old/*=410"]
+request-method = ["*/api/*=post"]
+url-attribute = ["[data-href]=data-href"]
+match = "/blog/"
+resolve = ["staging.example.com=127.0.0.1"]
+
+[ignore]
+missing-title = ["https://example.com/archive/"]
+"#,
+    )
+    .unwrap();
+
+    let config = SpiderConfig::from_file(path.to_str().unwrap());
+    fs::remove_file(&path).unwrap();
+
+    let mut options = SpiderOptions::new(&["https://example.com/"]);
+    config.unwrap().apply(&mut options).unwrap();
+
+    assert_eq!(options.max_depth, 3);
+    assert_eq!(options.hosts.len(), 2);
+    assert!(options.skip_selector.is_some());
+    assert!(options.check_json_ld);
+    assert!(!options.check_link_text);
+    assert_eq!(options.max_page_bytes, Some(100000));
+    assert_eq!(
+        options.expected_statuses,
+        vec![("*/old/*".to_string(), 410)]
+    );
+    assert_eq!(
+        options.request_methods,
+        vec![("*/api/*".to_string(), Method::POST)]
+    );
+    assert_eq!(options.extra_url_attributes.len(), 1);
+    assert_eq!(options.extra_url_attributes[0].1, "data-href");
+    assert!(options.url_match.is_some());
+    assert_eq!(
+        options.host_overrides.get("staging.example.com"),
+        Some(&IpAddr::from([127, 0, 0, 1]))
+    );
+    assert!(!options.is_rule_enabled(
+        SpiderErrorType::MissingTitle,
+        &Url::parse("https://example.com/archive/").unwrap()
+    ));
+}
+
+#[test]
+fn test_invalid_config() {
+    assert!(SpiderConfig::parse("no-such-option = true").is_err());
+    assert!(SpiderConfig::parse("depth = \"deep\"").is_err());
+
+    let config =
+        SpiderConfig::parse("[ignore]\nno-such-rule = [\"https://example.com/\"]").unwrap();
+    assert!(config.apply(&mut SpiderOptions::default()).is_err());
+}
@@ -0,0 +1,57 @@
+//! Holds functions to render the errors of a crawl as CSV
+use crate::error::SpiderError;
+use crate::SpiderCrab;
+
+/// Header row of the CSV output, see `SpiderCrab::to_csv()`
+pub const CSV_HEADER: &str = "source_page,target_page,error_type,http_status,attribute";
+
+/// Returns `field` quoted as a CSV field if it contains a comma, a quote or a line break
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[test]
+fn test_escape_csv_field() {
+    assert_eq!(
+        escape_csv_field("https://example.com/"),
+        "https://example.com/"
+    );
+    assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+    assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(escape_csv_field(""), "");
+}
+
+/// Returns the CSV row of `error`, with empty fields for the values it does not have
+fn get_csv_row(error: &SpiderError) -> String {
+    [
+        error.source_page.clone().unwrap_or_default(),
+        error.target_page.clone().unwrap_or_default(),
+        error.error_type.get_rule_name().to_string(),
+        error
+            .http_error_code
+            .map(|c| c.to_string())
+            .unwrap_or_default(),
+        error.attribute.clone().unwrap_or_default(),
+    ]
+    .iter()
+    .map(|field| escape_csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+impl SpiderCrab {
+    /// Returns the errors of the crawl as CSV, one row per error after the `CSV_HEADER` row.
+    /// Errors are in the same order as the summary.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for error in self.report().sorted_errors() {
+            csv.push_str(&get_csv_row(error));
+            csv.push('\n');
+        }
+        csv
+    }
+}
@@ -0,0 +1,211 @@
+//! Holds the `CachingResolver` that the HTTP client uses to look up hosts
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// How long the addresses of a host are remembered before it is looked up again
+pub const DEFAULT_DNS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Addresses of a host, and when they were looked up
+#[derive(Debug)]
+struct CachedAddrs {
+    addrs: Vec<SocketAddr>,
+    resolved: Instant,
+}
+
+/// Addresses of a host, shared by every lookup of the host that starts before they expire.
+/// The cell stays empty while the host is looked up, and after a lookup failed.
+type CacheEntry = Arc<OnceCell<CachedAddrs>>;
+
+/// DNS resolver that remembers the addresses of every host it looked up for `ttl`, so that each host is only looked up once in a while.
+/// Concurrent lookups of the same host wait for a single lookup.
+/// Hosts in the overrides map are never looked up, they always resolve to the given IP address (like `/etc/hosts`).
+#[derive(Debug, Clone)]
+pub struct CachingResolver {
+    overrides: HashMap<String, IpAddr>,
+    ttl: Duration,
+    cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+impl Default for CachingResolver {
+    fn default() -> Self {
+        Self {
+            overrides: HashMap::new(),
+            ttl: DEFAULT_DNS_CACHE_TTL,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl CachingResolver {
+    pub fn new(overrides: HashMap<String, IpAddr>) -> Self {
+        Self {
+            overrides,
+            ..Default::default()
+        }
+    }
+
+    /// Sets how long the addresses of a host are remembered, see `DEFAULT_DNS_CACHE_TTL`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the cached addresses of `host`, if it has been looked up and they have not expired
+    pub fn cached(&self, host: &str) -> Option<Vec<SocketAddr>> {
+        let cache = self.cache.lock().unwrap();
+        let cached = cache.get(host)?.get()?;
+        if cached.resolved.elapsed() > self.ttl {
+            return None;
+        }
+        Some(cached.addrs.clone())
+    }
+
+    /// Looks up the addresses of `host`. The port of the returned addresses is `0`, the HTTP client replaces it with the port of the URL.
+    pub async fn lookup(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        self.lookup_with(host, |host| async move {
+            Ok(tokio::net::lookup_host((host.as_str(), 0)).await?.collect())
+        })
+        .await
+    }
+
+    /// Looks up the addresses of `host` with `look_up`, unless they are cached or another lookup of `host` is in progress.
+    async fn lookup_with<F, Fut>(&self, host: &str, look_up: F) -> io::Result<Vec<SocketAddr>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = io::Result<Vec<SocketAddr>>>,
+    {
+        if let Some(ip) = self.overrides.get(host) {
+            return Ok(vec![SocketAddr::new(*ip, 0)]);
+        }
+
+        let entry = {
+            let mut cache = self.cache.lock().unwrap();
+            let entry = cache.entry(host.to_string()).or_default();
+            // Expired addresses are looked up again, lookups that are still in progress are waited for
+            if entry
+                .get()
+                .is_some_and(|cached| cached.resolved.elapsed() > self.ttl)
+            {
+                *entry = CacheEntry::default();
+            }
+            entry.clone()
+        };
+        // Failed lookups are not cached, the next lookup of `host` tries again.
+        // Addresses expire `ttl` after the lookup that found them.
+        let cached = entry
+            .get_or_try_init(|| async {
+                let addrs = look_up(host.to_string()).await?;
+                Ok::<_, io::Error>(CachedAddrs {
+                    addrs,
+                    resolved: Instant::now(),
+                })
+            })
+            .await?;
+        Ok(cached.addrs.clone())
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_caching_resolver() {
+    let resolver = CachingResolver::new(HashMap::from([(
+        String::from("staging.example.com"),
+        IpAddr::from([127, 0, 0, 1]),
+    )]));
+
+    let addrs = resolver.lookup("staging.example.com").await.unwrap();
+    assert_eq!(addrs, vec![SocketAddr::from(([127, 0, 0, 1], 0))]);
+    // Overrides are not cached, they are never looked up
+    assert_eq!(resolver.cached("staging.example.com"), None);
+
+    assert_eq!(resolver.cached("localhost"), None);
+    let addrs = resolver.lookup("localhost").await.unwrap();
+    assert!(!addrs.is_empty());
+    assert_eq!(resolver.cached("localhost"), Some(addrs));
+}
+
+#[tokio::test]
+async fn test_caching_resolver_concurrent_lookups() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let resolver = CachingResolver::default();
+    let lookups = AtomicUsize::new(0);
+    let look_up = |_| async {
+        lookups.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok(vec![SocketAddr::from(([192, 0, 2, 1], 0))])
+    };
+
+    let results =
+        futures::future::join_all((0..10).map(|_| resolver.lookup_with("example.com", look_up)))
+            .await;
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    for addrs in results {
+        assert_eq!(addrs.unwrap(), vec![SocketAddr::from(([192, 0, 2, 1], 0))]);
+    }
+}
+
+#[tokio::test]
+async fn test_caching_resolver_ttl() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let resolver = CachingResolver::default().with_ttl(Duration::from_millis(50));
+    let lookups = AtomicUsize::new(0);
+    let look_up = |_| async {
+        lookups.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![SocketAddr::from(([192, 0, 2, 1], 0))])
+    };
+
+    resolver.lookup_with("example.com", look_up).await.unwrap();
+    resolver.lookup_with("example.com", look_up).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(resolver.cached("example.com"), None);
+    resolver.lookup_with("example.com", look_up).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_caching_resolver_failed_lookup() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let resolver = CachingResolver::default().with_ttl(Duration::from_millis(50));
+    let lookups = AtomicUsize::new(0);
+    let look_up = |_| async {
+        if lookups.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err(io::Error::other("lookup failed"));
+        }
+        Ok(vec![SocketAddr::from(([192, 0, 2, 1], 0))])
+    };
+
+    assert!(resolver.lookup_with("example.com", look_up).await.is_err());
+    assert_eq!(resolver.cached("example.com"), None);
+
+    // Addresses found after the TTL of the failed lookup has passed are still cached for the whole TTL
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    resolver.lookup_with("example.com", look_up).await.unwrap();
+    assert_eq!(
+        resolver.cached("example.com"),
+        Some(vec![SocketAddr::from(([192, 0, 2, 1], 0))])
+    );
+    resolver.lookup_with("example.com", look_up).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+}
@@ -2,6 +2,50 @@
 use crate::{Link, Page, PageGraph, SpiderCrab};
 use petgraph::dot::{Config, Dot};
 use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Controls how pages are grouped into `subgraph cluster_*` blocks in the Dot output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterBy {
+    /// One cluster per host
+    Host,
+    /// One cluster per host and first path segment (ie. `example.com/blog`)
+    Path,
+}
+
+impl FromStr for ClusterBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "host" => Ok(ClusterBy::Host),
+            "path" => Ok(ClusterBy::Path),
+            _ => Err(format!(
+                "Invalid cluster mode {:?}, expected host or path",
+                s
+            )),
+        }
+    }
+}
+
+/// Returns the name of the cluster that `page` belongs in
+fn get_cluster_name(page: &Page, cluster_by: ClusterBy) -> String {
+    let host = page.url.host_str().unwrap_or_default();
+    match cluster_by {
+        ClusterBy::Host => host.to_string(),
+        ClusterBy::Path => {
+            let segment = page
+                .url
+                .path_segments()
+                .and_then(|mut s| s.next())
+                .unwrap_or_default();
+            format!("{}/{}", host, segment)
+        }
+    }
+}
 
 fn get_link_dot_attributes(
     _graph: &PageGraph,
@@ -24,13 +68,37 @@ fn get_page_dot_attributes(_graph: &PageGraph, (_index, page): (NodeIndex, &Page
     };
 
     format!(
-        "label=\"{}\n{}\", color={}",
-        title,
-        page.url.as_str(),
+        "label=\"{}\\n{}\", color={}",
+        escape_dot_string(&title),
+        escape_dot_string(page.url.as_str()),
         color
     )
 }
 
+/// Escapes `s` so that it can be placed inside of a double-quoted DOT string.
+/// Backslashes and double-quotes are escaped, and newlines are replaced with the DOT `\n` line break.
+fn escape_dot_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_escape_dot_string() {
+    assert_eq!(escape_dot_string("Plain"), "Plain");
+    assert_eq!(escape_dot_string("Say \"Hi\""), "Say \\\"Hi\\\"");
+    assert_eq!(escape_dot_string("C:\\path"), "C:\\\\path");
+    assert_eq!(escape_dot_string("Two\r\nLines"), "Two\\nLines");
+}
+
 impl SpiderCrab {
     pub fn get_dot_format(&self) -> String {
         format!(
@@ -43,4 +111,45 @@ impl SpiderCrab {
             )
         )
     }
+
+    /// Same as `get_dot_format()`, but pages are grouped into `subgraph cluster_*` blocks as given by `cluster_by`
+    pub fn get_clustered_dot_format(&self, cluster_by: ClusterBy) -> String {
+        let mut clusters = BTreeMap::<String, Vec<NodeIndex>>::new();
+        for index in self.graph.node_indices() {
+            let page = self.graph.node_weight(index).unwrap();
+            clusters
+                .entry(get_cluster_name(page, cluster_by))
+                .or_default()
+                .push(index);
+        }
+
+        let mut dot = String::from("digraph {\n");
+        for (i, (name, nodes)) in clusters.iter().enumerate() {
+            writeln!(dot, "    subgraph cluster_{} {{", i).unwrap();
+            writeln!(dot, "        label={:?}", name).unwrap();
+            for index in nodes {
+                let page = self.graph.node_weight(*index).unwrap();
+                writeln!(
+                    dot,
+                    "        {} [ {} ]",
+                    index.index(),
+                    get_page_dot_attributes(&self.graph, (*index, page))
+                )
+                .unwrap();
+            }
+            dot.push_str("    }\n");
+        }
+        for edge in self.graph.edge_references() {
+            writeln!(
+                dot,
+                "    {} -> {} [ {} ]",
+                edge.source().index(),
+                edge.target().index(),
+                get_link_dot_attributes(&self.graph, edge)
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
@@ -1,6 +1,9 @@
 //! Holds the custom SpiderError struct used by spider crab
 
 use enum_iterator::{all, Sequence};
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::str::FromStr;
 
 #[derive(Debug, Eq, PartialEq, Hash, Sequence, Clone)]
@@ -12,6 +15,30 @@ pub enum SpiderErrorType {
     EmptyAttribute,
     MissingTitle,
     EmptyScript,
+    InvalidSrcset,
+    PageTooLarge,
+    HreflangMismatch,
+    LinkTo404Page,
+    RejectedByPolicy,
+    InvalidJsonLd,
+    PathTraversal,
+    EmptyLinkText,
+    UnexpectedStatus,
+    UncompressedResource,
+    WrongCanonicalHost,
+    CaseMismatch,
+    UrlTooLong,
+    MissingFragment,
+    SitemapMismatch,
+    ThinContent,
+    BrokenAriaReference,
+    RobotsDisallowed,
+    BlockedRedirect,
+    RedirectChain,
+    RedundantAbsoluteSelfLink,
+    SlowResponse,
+    MissingLang,
+    InvalidLang,
     #[doc(hidden)]
     FailedCrawl,
     #[doc(hidden)]
@@ -19,7 +46,13 @@ pub enum SpiderErrorType {
 }
 
 impl SpiderErrorType {
-    fn get_rule_name(&self) -> &'static str {
+    /// Returns the stable code of this error type, which is the name of its rule (ie. `http-error`).
+    /// Codes can be parsed back into error types with `SpiderErrorType::from_str()`.
+    pub fn code(&self) -> &'static str {
+        self.get_rule_name()
+    }
+
+    pub(crate) fn get_rule_name(&self) -> &'static str {
         match self {
             SpiderErrorType::UnableToRetrieve => "unable-to-retrieve",
             SpiderErrorType::HTTPError => "http-error",
@@ -28,12 +61,169 @@ impl SpiderErrorType {
             SpiderErrorType::EmptyAttribute => "empty-attribute",
             SpiderErrorType::MissingTitle => "missing-title",
             SpiderErrorType::EmptyScript => "empty-script",
+            SpiderErrorType::InvalidSrcset => "invalid-srcset",
+            SpiderErrorType::PageTooLarge => "page-too-large",
+            SpiderErrorType::HreflangMismatch => "hreflang-mismatch",
+            SpiderErrorType::LinkTo404Page => "link-to-404-page",
+            SpiderErrorType::RejectedByPolicy => "rejected-by-policy",
+            SpiderErrorType::InvalidJsonLd => "invalid-json-ld",
+            SpiderErrorType::PathTraversal => "path-traversal",
+            SpiderErrorType::EmptyLinkText => "empty-link-text",
+            SpiderErrorType::UnexpectedStatus => "unexpected-status",
+            SpiderErrorType::UncompressedResource => "uncompressed-resource",
+            SpiderErrorType::WrongCanonicalHost => "wrong-canonical-host",
+            SpiderErrorType::CaseMismatch => "case-mismatch",
+            SpiderErrorType::UrlTooLong => "url-too-long",
+            SpiderErrorType::MissingFragment => "missing-fragment",
+            SpiderErrorType::SitemapMismatch => "sitemap-mismatch",
+            SpiderErrorType::ThinContent => "thin-content",
+            SpiderErrorType::BrokenAriaReference => "broken-aria-reference",
+            SpiderErrorType::RobotsDisallowed => "robots-disallowed",
+            SpiderErrorType::BlockedRedirect => "blocked-redirect",
+            SpiderErrorType::RedirectChain => "redirect-chain",
+            SpiderErrorType::RedundantAbsoluteSelfLink => "redundant-absolute-self-link",
+            SpiderErrorType::SlowResponse => "slow-response",
+            SpiderErrorType::MissingLang => "missing-lang",
+            SpiderErrorType::InvalidLang => "invalid-lang",
             SpiderErrorType::FailedCrawl => "failed-crawl",
             SpiderErrorType::ParseError => "parse-error",
         }
     }
 }
 
+/// Returns the URL of the documentation of the rule named `$rule`
+macro_rules! rule_doc_url {
+    ($rule:literal) => {
+        Some(concat!(
+            "https://github.com/tsengia/spider-crab/blob/main/docs/rules.md#",
+            $rule
+        ))
+    };
+}
+
+impl SpiderErrorType {
+    /// Returns the URL of the page that explains this rule and how to fix it
+    pub fn doc_url(&self) -> Option<&'static str> {
+        match self {
+            SpiderErrorType::UnableToRetrieve => rule_doc_url!("unable-to-retrieve"),
+            SpiderErrorType::HTTPError => rule_doc_url!("http-error"),
+            SpiderErrorType::InvalidURL => rule_doc_url!("invalid-url"),
+            SpiderErrorType::MissingAttribute => rule_doc_url!("missing-attribute"),
+            SpiderErrorType::EmptyAttribute => rule_doc_url!("empty-attribute"),
+            SpiderErrorType::MissingTitle => rule_doc_url!("missing-title"),
+            SpiderErrorType::EmptyScript => rule_doc_url!("empty-script"),
+            SpiderErrorType::InvalidSrcset => rule_doc_url!("invalid-srcset"),
+            SpiderErrorType::PageTooLarge => rule_doc_url!("page-too-large"),
+            SpiderErrorType::HreflangMismatch => rule_doc_url!("hreflang-mismatch"),
+            SpiderErrorType::LinkTo404Page => rule_doc_url!("link-to-404-page"),
+            SpiderErrorType::RejectedByPolicy => rule_doc_url!("rejected-by-policy"),
+            SpiderErrorType::InvalidJsonLd => rule_doc_url!("invalid-json-ld"),
+            SpiderErrorType::PathTraversal => rule_doc_url!("path-traversal"),
+            SpiderErrorType::EmptyLinkText => rule_doc_url!("empty-link-text"),
+            SpiderErrorType::UnexpectedStatus => rule_doc_url!("unexpected-status"),
+            SpiderErrorType::UncompressedResource => rule_doc_url!("uncompressed-resource"),
+            SpiderErrorType::WrongCanonicalHost => rule_doc_url!("wrong-canonical-host"),
+            SpiderErrorType::CaseMismatch => rule_doc_url!("case-mismatch"),
+            SpiderErrorType::UrlTooLong => rule_doc_url!("url-too-long"),
+            SpiderErrorType::MissingFragment => rule_doc_url!("missing-fragment"),
+            SpiderErrorType::SitemapMismatch => rule_doc_url!("sitemap-mismatch"),
+            SpiderErrorType::ThinContent => rule_doc_url!("thin-content"),
+            SpiderErrorType::BrokenAriaReference => rule_doc_url!("broken-aria-reference"),
+            SpiderErrorType::RobotsDisallowed => rule_doc_url!("robots-disallowed"),
+            SpiderErrorType::BlockedRedirect => rule_doc_url!("blocked-redirect"),
+            SpiderErrorType::RedirectChain => rule_doc_url!("redirect-chain"),
+            SpiderErrorType::RedundantAbsoluteSelfLink => {
+                rule_doc_url!("redundant-absolute-self-link")
+            }
+            SpiderErrorType::SlowResponse => rule_doc_url!("slow-response"),
+            SpiderErrorType::MissingLang => rule_doc_url!("missing-lang"),
+            SpiderErrorType::InvalidLang => rule_doc_url!("invalid-lang"),
+            SpiderErrorType::FailedCrawl | SpiderErrorType::ParseError => None,
+        }
+    }
+}
+
+/// Exit code when no errors were found
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code when the crawl failed for a reason not covered by another exit code
+pub const EXIT_FAILED_CRAWL: i32 = 1;
+/// Exit code when a page could not be retrieved, or returned an unexpected HTTP status code
+pub const EXIT_BROKEN_LINK: i32 = 2;
+/// Exit code when a page contains invalid or incomplete HTML (ie. a link without an `href`)
+pub const EXIT_INVALID_HTML: i32 = 3;
+/// Exit code when a page failed a content check (ie. an invalid `srcset`)
+pub const EXIT_CONTENT_CHECK: i32 = 4;
+/// Exit code when the user supplied an invalid rule name
+pub const EXIT_USAGE: i32 = 64;
+
+impl SpiderErrorType {
+    /// Returns the process exit code used when an error of this type fails a crawl
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SpiderErrorType::UnableToRetrieve
+            | SpiderErrorType::HTTPError
+            | SpiderErrorType::RejectedByPolicy
+            | SpiderErrorType::UnexpectedStatus
+            | SpiderErrorType::CaseMismatch
+            | SpiderErrorType::MissingFragment
+            | SpiderErrorType::BlockedRedirect
+            | SpiderErrorType::RedirectChain => EXIT_BROKEN_LINK,
+            SpiderErrorType::InvalidURL
+            | SpiderErrorType::MissingAttribute
+            | SpiderErrorType::EmptyAttribute
+            | SpiderErrorType::MissingTitle
+            | SpiderErrorType::EmptyScript
+            | SpiderErrorType::EmptyLinkText
+            | SpiderErrorType::BrokenAriaReference
+            | SpiderErrorType::MissingLang
+            | SpiderErrorType::InvalidLang => EXIT_INVALID_HTML,
+            SpiderErrorType::InvalidSrcset
+            | SpiderErrorType::PageTooLarge
+            | SpiderErrorType::HreflangMismatch
+            | SpiderErrorType::LinkTo404Page
+            | SpiderErrorType::InvalidJsonLd
+            | SpiderErrorType::PathTraversal
+            | SpiderErrorType::UncompressedResource
+            | SpiderErrorType::WrongCanonicalHost
+            | SpiderErrorType::UrlTooLong
+            | SpiderErrorType::SitemapMismatch
+            | SpiderErrorType::ThinContent
+            | SpiderErrorType::RobotsDisallowed
+            | SpiderErrorType::RedundantAbsoluteSelfLink
+            | SpiderErrorType::SlowResponse => EXIT_CONTENT_CHECK,
+            SpiderErrorType::FailedCrawl => EXIT_FAILED_CRAWL,
+            SpiderErrorType::ParseError => EXIT_USAGE,
+        }
+    }
+}
+
+/// Returns the exit code for a crawl that found `errors`. Warnings are ignored.
+/// If errors of several types were found, the lowest exit code wins, so broken links take priority.
+pub fn exit_code_for_errors<'a>(errors: impl Iterator<Item = &'a SpiderError>) -> i32 {
+    errors
+        .filter(|e| !e.is_warning())
+        .map(|e| e.error_type.exit_code())
+        .min()
+        .unwrap_or(EXIT_SUCCESS)
+}
+
+/// Sorts `errors` by source page, rule name and target page, so that their order does not depend on the order pages were visited in.
+/// Missing source and target pages sort first.
+pub fn sort_errors(errors: &mut [&SpiderError]) {
+    errors.sort_by(|a, b| {
+        let key = |e: &SpiderError| {
+            (
+                e.source_page.clone(),
+                e.error_type.get_rule_name(),
+                e.target_page.clone(),
+                e.attribute.clone(),
+                e.html.clone(),
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+}
+
 impl FromStr for SpiderErrorType {
     type Err = SpiderError;
 
@@ -50,19 +240,91 @@ impl FromStr for SpiderErrorType {
     }
 }
 
-#[derive(Debug)]
+/// Error types are serialized as their rule name (ie. `http-error`)
+impl Serialize for SpiderErrorType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_rule_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for SpiderErrorType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        SpiderErrorType::from_str(&code)
+            .map_err(|_| D::Error::custom(format!("unknown error type {:?}", code)))
+    }
+}
+
+/// How severe a `SpiderError` is
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Errors cause the crawl to fail
+    #[default]
+    Error,
+    /// Warnings are recorded, but do not cause the crawl to fail
+    Warning,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 /// Custom error type for Spider Crab
 pub struct SpiderError {
     pub source_page: Option<String>,
     pub target_page: Option<String>,
     pub http_error_code: Option<u16>,
+    /// HTTP status code that the target page was expected to return
+    #[serde(default)]
+    pub expected_status_code: Option<u16>,
+    /// Canonical form of `target_page`
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    #[serde(rename = "type")]
     pub error_type: SpiderErrorType,
     pub html: Option<String>,
     pub attribute: Option<String>,
+    /// Size of the page or resource in bytes
+    pub size: Option<usize>,
+    /// Number of items that a rule counts (ie. the words of a page, the characters of a URL or the redirects of a chain)
+    #[serde(default)]
+    pub count: Option<usize>,
+    /// Duration that a rule measures in milliseconds (ie. the response time of a page)
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Line of the file that could not be parsed, counting from 1
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Why the file could not be parsed, or why the page could not be retrieved
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
 impl std::error::Error for SpiderError {}
 
+/// Errors are serialized with their human readable message as the `message` field, which is ignored when they are deserialized
+impl Serialize for SpiderError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SpiderError", 15)?;
+        s.serialize_field("source_page", &self.source_page)?;
+        s.serialize_field("target_page", &self.target_page)?;
+        s.serialize_field("http_error_code", &self.http_error_code)?;
+        s.serialize_field("expected_status_code", &self.expected_status_code)?;
+        s.serialize_field("canonical_url", &self.canonical_url)?;
+        s.serialize_field("type", &self.error_type)?;
+        s.serialize_field("html", &self.html)?;
+        s.serialize_field("attribute", &self.attribute)?;
+        s.serialize_field("size", &self.size)?;
+        s.serialize_field("count", &self.count)?;
+        s.serialize_field("duration_ms", &self.duration_ms)?;
+        s.serialize_field("severity", &self.severity)?;
+        s.serialize_field("line", &self.line)?;
+        s.serialize_field("reason", &self.reason)?;
+        s.serialize_field("message", &self.get_message())?;
+        s.end()
+    }
+}
+
 impl std::fmt::Display for SpiderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let message = self.get_message();
@@ -82,55 +344,537 @@ impl Default for SpiderError {
             source_page: None,
             target_page: None,
             http_error_code: None,
+            expected_status_code: None,
+            canonical_url: None,
             html: None,
             attribute: None,
+            size: None,
+            count: None,
+            duration_ms: None,
+            severity: Severity::Error,
+            line: None,
+            reason: None,
         }
     }
 }
 
+/// Returns the value of a text field of an error for its message, or `unknown` if the field is missing (ie. in a deserialized error)
+fn text(field: &Option<String>) -> &str {
+    field.as_deref().unwrap_or("unknown")
+}
+
+/// Returns the value of a numeric field of an error for its message, or `unknown` if the field is missing
+fn number<T: std::fmt::Display>(field: &Option<T>) -> String {
+    field
+        .as_ref()
+        .map_or_else(|| String::from("unknown"), T::to_string)
+}
+
 impl SpiderError {
-    fn get_message(&self) -> String {
+    /// Returns `true` if this error is only a warning and should not fail the crawl
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+
+    /// Returns a short hint on how to fix this error
+    pub fn suggestion(&self) -> String {
         match self.error_type {
-            SpiderErrorType::UnableToRetrieve => format!(
-                "Failed to retrieve content for page {:?}!",
-                self.target_page.as_ref().unwrap()
+            SpiderErrorType::UnableToRetrieve => {
+                String::from("check that the URL is correct and that the server is reachable")
+            }
+            SpiderErrorType::HTTPError => match self.http_error_code {
+                Some(404) | Some(410) => String::from("update or remove the broken link"),
+                Some(401) | Some(403) => String::from(
+                    "check that the page is public, or ignore the URL if it requires a login",
+                ),
+                Some(429) => String::from("crawl the site more slowly, or ignore the URL"),
+                Some(code) if code >= 500 => {
+                    String::from("check the server logs, the server failed to respond to the request")
+                }
+                _ => String::from("update the link to point to a page that returns a 2XX status code"),
+            },
+            SpiderErrorType::InvalidURL => String::from("fix the syntax of the URL"),
+            SpiderErrorType::MissingAttribute => format!(
+                "add an {} attribute or remove the element",
+                self.attribute.as_deref().unwrap_or("URL")
             ),
+            SpiderErrorType::EmptyAttribute => format!(
+                "fill in the {} attribute or remove the element",
+                self.attribute.as_deref().unwrap_or("URL")
+            ),
+            SpiderErrorType::MissingTitle => String::from("add a <title> element to the <head> of the page"),
+            SpiderErrorType::EmptyScript => {
+                String::from("add a src attribute or JavaScript code, or remove the <script> element")
+            }
+            SpiderErrorType::InvalidSrcset => {
+                String::from("give each image candidate a unique width (ie. 480w) or density (ie. 2x) descriptor")
+            }
+            SpiderErrorType::PageTooLarge => {
+                String::from("split the page up, or move inline content into separate files")
+            }
+            SpiderErrorType::HreflangMismatch => {
+                String::from("add an hreflang alternate link back to this page on the alternate page")
+            }
+            SpiderErrorType::LinkTo404Page => {
+                String::from("link to the intended page instead of the 404 page")
+            }
+            SpiderErrorType::RejectedByPolicy => {
+                String::from("update the page so that it meets the goodness policy")
+            }
+            SpiderErrorType::InvalidJsonLd => {
+                String::from("fix the JSON syntax of the structured data")
+            }
+            SpiderErrorType::PathTraversal => {
+                String::from("remove the extra ../ segments, or use an absolute path")
+            }
+            SpiderErrorType::EmptyLinkText => {
+                String::from("add text to the link, or give it an aria-label attribute")
+            }
+            SpiderErrorType::UnexpectedStatus => String::from(
+                "update the page to return the expected status code, or update the expected status code",
+            ),
+            SpiderErrorType::UncompressedResource => {
+                String::from("enable gzip or brotli compression for text resources on the server")
+            }
+            SpiderErrorType::WrongCanonicalHost => match &self.canonical_url {
+                Some(canonical_url) => format!("link to {} instead", canonical_url),
+                None => String::from("link to the canonical scheme and host instead"),
+            },
+            SpiderErrorType::CaseMismatch => match &self.canonical_url {
+                Some(canonical_url) => format!("link to {} instead, paths are case-sensitive on most servers", canonical_url),
+                None => String::from("match the casing of the path on the server, paths are case-sensitive on most servers"),
+            },
+            SpiderErrorType::UrlTooLong => {
+                String::from("shorten the URL, ie. by removing unneeded query parameters")
+            }
+            SpiderErrorType::MissingFragment => {
+                String::from("link to an anchor that exists on the linked page, or remove the fragment")
+            }
+            SpiderErrorType::SitemapMismatch => match &self.target_page {
+                Some(_) => String::from("link to the page from the website, or remove it from the sitemap"),
+                None => String::from("add the page to the sitemap"),
+            },
+            SpiderErrorType::ThinContent => {
+                String::from("add more content to the page, or merge it into a related page")
+            }
+            SpiderErrorType::RobotsDisallowed => {
+                String::from("allow crawling the page in robots.txt, or remove the link")
+            }
+            SpiderErrorType::RedundantAbsoluteSelfLink => String::from(
+                "link to the page with a relative URL, or a fragment (ie. #section) for a part of it",
+            ),
+            SpiderErrorType::MissingLang => {
+                String::from("add a lang attribute with the language of the page to the <html> element (ie. lang=\"en\")")
+            }
+            SpiderErrorType::InvalidLang => String::from(
+                "use a BCP 47 language tag, ie. en for English or en-US for English as used in the United States",
+            ),
+            SpiderErrorType::SlowResponse => {
+                String::from("speed up the server, ie. by caching the page or optimizing slow queries")
+            }
+            SpiderErrorType::BlockedRedirect | SpiderErrorType::RedirectChain => {
+                String::from("link directly to the page that the redirect points to")
+            }
+            SpiderErrorType::BrokenAriaReference => String::from(
+                "give the referenced element this id, or reference the id of an existing element",
+            ),
+            SpiderErrorType::FailedCrawl => String::from("fix the errors reported above"),
+            SpiderErrorType::ParseError => {
+                String::from("use one of the rule names listed in the README")
+            }
+        }
+    }
+
+    pub(crate) fn get_message(&self) -> String {
+        match self.error_type {
+            SpiderErrorType::UnableToRetrieve => match &self.reason {
+                Some(reason) => format!(
+                    "Failed to retrieve content for page {:?}: {}!",
+                    text(&self.target_page),
+                    reason
+                ),
+                None => format!(
+                    "Failed to retrieve content for page {:?}!",
+                    text(&self.target_page)
+                ),
+            },
             SpiderErrorType::HTTPError => format!(
-                "HTTP GET request received status code {:?} for page {:?}!",
-                self.http_error_code.as_ref().unwrap(),
-                self.target_page.as_ref().unwrap()
+                "HTTP GET request received status code {} for page {:?}!",
+                number(&self.http_error_code),
+                text(&self.target_page)
             ),
             SpiderErrorType::InvalidURL => format!(
                 "Page at {:?} contains a reference to an invalid URL {:?}!",
-                self.source_page.as_ref().unwrap(),
-                self.target_page.as_ref().unwrap()
+                text(&self.source_page),
+                text(&self.target_page)
             ),
             SpiderErrorType::MissingAttribute => format!(
                 "Page at {:?} contains an element with no {:?} attribute! Element is: {:?}",
-                self.source_page.as_ref().unwrap(),
-                self.attribute.as_ref().unwrap(),
-                self.html.as_ref().unwrap()
+                text(&self.source_page),
+                text(&self.attribute),
+                text(&self.html)
             ),
             SpiderErrorType::EmptyAttribute => format!(
                 "Page at {:?} contains a link with an empty {:?} attribute! Element is: {:?}",
-                self.source_page.as_ref().unwrap(),
-                self.attribute.as_ref().unwrap(),
-                self.html.as_ref().unwrap()
+                text(&self.source_page),
+                text(&self.attribute),
+                text(&self.html)
             ),
             SpiderErrorType::MissingTitle => format!(
                 "Page at {:?} does not have a title!",
-                self.source_page.as_ref().unwrap()
+                text(&self.source_page)
             ),
             SpiderErrorType::EmptyScript => format!(
                 "Page at {:?} has a <script> tag with no `src` attribute and no JavaScript code inside!",
-                self.source_page.as_ref().unwrap()
+                text(&self.source_page)
+            ),
+            SpiderErrorType::InvalidSrcset => format!(
+                "Page at {:?} contains an element with an invalid {:?} attribute! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.attribute),
+                text(&self.html)
+            ),
+            SpiderErrorType::PageTooLarge => format!(
+                "Page at {:?} is too large! Page is {} bytes.",
+                text(&self.source_page),
+                number(&self.size)
+            ),
+            SpiderErrorType::HreflangMismatch => format!(
+                "Page at {:?} declares {:?} as an hreflang alternate, but it does not declare the page as an alternate in return!",
+                text(&self.source_page),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::LinkTo404Page => format!(
+                "Page at {:?} links directly to the 404 page {:?}! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.target_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::RejectedByPolicy => format!(
+                "Page {:?} was rejected by the goodness policy!",
+                text(&self.target_page)
+            ),
+            SpiderErrorType::InvalidJsonLd => format!(
+                "Page at {:?} contains JSON-LD structured data that could not be parsed! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::PathTraversal => format!(
+                "Page at {:?} links to {:?}, which goes above the root of the host! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.target_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::EmptyLinkText => format!(
+                "Page at {:?} contains a link with no text or accessible name! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::UnexpectedStatus => format!(
+                "HTTP GET request received status code {} for page {:?}, but {} was expected!",
+                number(&self.http_error_code),
+                text(&self.target_page),
+                number(&self.expected_status_code)
+            ),
+            SpiderErrorType::UncompressedResource => format!(
+                "Resource {:?} is {} bytes, but was not compressed!",
+                text(&self.target_page),
+                number(&self.size)
+            ),
+            SpiderErrorType::WrongCanonicalHost => format!(
+                "Page at {:?} links to {:?}, which does not use the canonical scheme and host! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.target_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::CaseMismatch => format!(
+                "Page {:?} only differs in case from {:?}, it is not found on case-sensitive servers!",
+                text(&self.target_page),
+                text(&self.canonical_url)
+            ),
+            SpiderErrorType::UrlTooLong => format!(
+                "Page at {:?} links to a URL that is {} characters long! URL is: {:?}",
+                text(&self.source_page),
+                number(&self.count),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::MissingFragment => format!(
+                "Page at {:?} links to {:?}, but the linked page has no such anchor!",
+                text(&self.source_page),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::SitemapMismatch => match &self.target_page {
+                Some(target_page) => format!(
+                    "Page {:?} is listed in sitemap {:?}, but no crawled page links to it!",
+                    target_page,
+                    text(&self.source_page)
+                ),
+                None => format!(
+                    "Page {:?} was crawled, but it is not listed in the sitemap!",
+                    text(&self.source_page)
+                ),
+            },
+            SpiderErrorType::RobotsDisallowed => format!(
+                "Page at {:?} links to {:?}, which robots.txt does not allow crawling!",
+                text(&self.source_page),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::BlockedRedirect => format!(
+                "Page {:?} redirects to {:?}, which the redirect policy does not allow following!",
+                text(&self.source_page),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::RedundantAbsoluteSelfLink => format!(
+                "Page at {:?} links to itself with the absolute URL {:?}, a relative URL would be clearer!",
+                text(&self.source_page),
+                text(&self.target_page)
+            ),
+            SpiderErrorType::RedirectChain => format!(
+                "Page {:?} redirects to {:?} after {} redirects!",
+                text(&self.source_page),
+                text(&self.target_page),
+                number(&self.count)
+            ),
+            SpiderErrorType::BrokenAriaReference => format!(
+                "Page at {:?} references {:?} in the {} attribute, but no element has that id! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.target_page),
+                text(&self.attribute),
+                text(&self.html)
+            ),
+            SpiderErrorType::ThinContent => format!(
+                "Page at {:?} only has {} words of visible text!",
+                text(&self.source_page),
+                number(&self.count)
+            ),
+            SpiderErrorType::MissingLang => format!(
+                "Page at {:?} does not declare its language with a lang attribute on the <html> element!",
+                text(&self.source_page)
+            ),
+            SpiderErrorType::InvalidLang => format!(
+                "Page at {:?} declares its language with an invalid language tag! Element is: {:?}",
+                text(&self.source_page),
+                text(&self.html)
+            ),
+            SpiderErrorType::SlowResponse => format!(
+                "Page {:?} took {} ms to respond!",
+                text(&self.target_page),
+                number(&self.duration_ms)
             ),
             SpiderErrorType::FailedCrawl => {
                 String::from("Found a problem while crawling the target webpage!")
             },
-            SpiderErrorType::ParseError => {
-                String::from("Could not parse string into error type!")
-            }
+            SpiderErrorType::ParseError => match (&self.source_page, &self.reason) {
+                (Some(file), Some(reason)) => match self.line {
+                    Some(line) => format!("Could not parse line {} of {:?}: {}!", line, file, reason),
+                    None => format!("Could not read {:?}: {}!", file, reason),
+                },
+                _ => String::from("Could not parse string into error type!"),
+            },
         }
     }
 }
+
+#[test]
+fn test_suggestions() {
+    for error_type in all::<SpiderErrorType>() {
+        let error = SpiderError {
+            error_type: error_type.clone(),
+            ..SpiderError::default()
+        };
+        assert!(
+            !error.suggestion().is_empty(),
+            "No suggestion for {:?}",
+            error_type
+        );
+    }
+
+    let missing_href = SpiderError {
+        error_type: SpiderErrorType::MissingAttribute,
+        attribute: Some("href".to_string()),
+        ..SpiderError::default()
+    };
+    assert_eq!(
+        missing_href.suggestion(),
+        "add an href attribute or remove the element"
+    );
+
+    let not_found = SpiderError {
+        error_type: SpiderErrorType::HTTPError,
+        http_error_code: Some(404),
+        ..SpiderError::default()
+    };
+    assert_eq!(not_found.suggestion(), "update or remove the broken link");
+
+    let server_error = SpiderError {
+        error_type: SpiderErrorType::HTTPError,
+        http_error_code: Some(503),
+        ..SpiderError::default()
+    };
+    assert!(server_error.suggestion().contains("server"));
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(SpiderErrorType::HTTPError.exit_code(), 2);
+    assert_eq!(SpiderErrorType::UnableToRetrieve.exit_code(), 2);
+    assert_eq!(SpiderErrorType::UnexpectedStatus.exit_code(), 2);
+    assert_eq!(SpiderErrorType::MissingAttribute.exit_code(), 3);
+    assert_eq!(SpiderErrorType::EmptyAttribute.exit_code(), 3);
+    assert_eq!(SpiderErrorType::InvalidURL.exit_code(), 3);
+    assert_eq!(SpiderErrorType::MissingTitle.exit_code(), 3);
+    assert_eq!(SpiderErrorType::EmptyScript.exit_code(), 3);
+    assert_eq!(SpiderErrorType::InvalidSrcset.exit_code(), 4);
+    assert_eq!(SpiderErrorType::HreflangMismatch.exit_code(), 4);
+    assert_eq!(SpiderErrorType::FailedCrawl.exit_code(), 1);
+    assert_eq!(SpiderErrorType::ParseError.exit_code(), 64);
+
+    // Every error type fails with a non-zero exit code
+    for error_type in all::<SpiderErrorType>() {
+        assert_ne!(error_type.exit_code(), EXIT_SUCCESS, "{:?}", error_type);
+    }
+
+    let error = |error_type: SpiderErrorType, severity: Severity| SpiderError {
+        error_type,
+        severity,
+        ..SpiderError::default()
+    };
+    let errors = [
+        error(SpiderErrorType::MissingTitle, Severity::Error),
+        error(SpiderErrorType::HTTPError, Severity::Error),
+        error(SpiderErrorType::PageTooLarge, Severity::Warning),
+    ];
+    assert_eq!(exit_code_for_errors(errors.iter()), EXIT_BROKEN_LINK);
+    assert_eq!(exit_code_for_errors(errors[2..].iter()), EXIT_SUCCESS);
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    let error = SpiderError {
+        error_type: SpiderErrorType::HTTPError,
+        source_page: Some(String::from("https://example.com/")),
+        target_page: Some(String::from("https://example.com/missing.html")),
+        http_error_code: Some(404),
+        ..SpiderError::default()
+    };
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["type"], "http-error");
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["message"], error.get_message());
+
+    let deserialized: SpiderError = serde_json::from_value(json).unwrap();
+    assert_eq!(deserialized.error_type, SpiderErrorType::HTTPError);
+    assert_eq!(deserialized.error_type.code(), "http-error");
+    assert_eq!(deserialized.source_page, error.source_page);
+    assert_eq!(deserialized.target_page, error.target_page);
+    assert_eq!(deserialized.http_error_code, Some(404));
+    assert_eq!(deserialized.severity, Severity::Error);
+    assert_eq!(deserialized.get_message(), error.get_message());
+
+    assert!(serde_json::from_str::<SpiderErrorType>("\"no-such-rule\"").is_err());
+}
+
+#[test]
+fn test_deserialize_minimal_error() {
+    // Only the type is required, every other field is optional on input
+    for error_type in all::<SpiderErrorType>() {
+        let json = serde_json::json!({ "type": error_type.code() });
+        let error: SpiderError = serde_json::from_value(json).unwrap();
+        assert_eq!(error.error_type, error_type);
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.source_page, None);
+
+        // Messages of partial errors fall back to "unknown" instead of panicking
+        let message = error.get_message();
+        assert!(!message.is_empty());
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["message"], message);
+        let round_trip: SpiderError = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip.get_message(), message);
+    }
+
+    let error: SpiderError = serde_json::from_str(r#"{"type": "http-error"}"#).unwrap();
+    assert_eq!(
+        error.to_string(),
+        "SpiderError (http-error): HTTP GET request received status code unknown for page \"unknown\"!"
+    );
+}
+
+#[test]
+fn test_doc_urls() {
+    let rules_doc = include_str!("../docs/rules.md");
+    for error_type in all::<SpiderErrorType>() {
+        if matches!(
+            error_type,
+            SpiderErrorType::FailedCrawl | SpiderErrorType::ParseError
+        ) {
+            assert_eq!(error_type.doc_url(), None);
+            continue;
+        }
+
+        let rule_name = error_type.get_rule_name();
+        let doc_url = error_type.doc_url().unwrap();
+        assert!(
+            doc_url.ends_with(&format!("#{}", rule_name)),
+            "{} does not link to {}",
+            doc_url,
+            rule_name
+        );
+        assert!(
+            rules_doc.contains(&format!("\n## {}\n", rule_name)),
+            "{} is not documented in docs/rules.md",
+            rule_name
+        );
+    }
+}
+
+#[test]
+fn test_sort_errors() {
+    let error =
+        |error_type: SpiderErrorType, source_page: Option<&str>, target_page: &str| SpiderError {
+            error_type,
+            source_page: source_page.map(String::from),
+            target_page: Some(target_page.to_string()),
+            ..SpiderError::default()
+        };
+    let errors = [
+        error(
+            SpiderErrorType::InvalidURL,
+            Some("https://example.com/b.html"),
+            "a",
+        ),
+        error(
+            SpiderErrorType::HTTPError,
+            None,
+            "https://example.com/missing.html",
+        ),
+        error(
+            SpiderErrorType::InvalidURL,
+            Some("https://example.com/a.html"),
+            "b",
+        ),
+        error(
+            SpiderErrorType::EmptyAttribute,
+            Some("https://example.com/a.html"),
+            "c",
+        ),
+    ];
+
+    let mut forward: Vec<&SpiderError> = errors.iter().collect();
+    let mut backward: Vec<&SpiderError> = errors.iter().rev().collect();
+    sort_errors(&mut forward);
+    sort_errors(&mut backward);
+
+    let targets: Vec<&str> = forward
+        .iter()
+        .map(|e| e.target_page.as_deref().unwrap())
+        .collect();
+    assert_eq!(
+        targets,
+        vec!["https://example.com/missing.html", "c", "b", "a"]
+    );
+    assert!(forward
+        .iter()
+        .zip(&backward)
+        .all(|(a, b)| std::ptr::eq(*a, *b)));
+}
@@ -0,0 +1,117 @@
+//! Holds the `CrawlEvent` stream that reports the progress of a crawl while it is running
+
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::sync::mpsc::Receiver;
+use url::Url;
+
+use crate::error::SpiderError;
+
+/// Event sent while a crawl is running. Subscribe to events with `SpiderCrab::subscribe()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrawlEvent {
+    /// Page is about to be requested
+    PageStarted { url: Url },
+    /// Page has been requested and checked. Pages it links to may still be in progress.
+    PageFinished {
+        url: Url,
+        status_code: Option<u16>,
+        good: Option<bool>,
+        /// Value of the `Last-Modified` header of the response
+        last_modified: Option<String>,
+        /// Value of the `ETag` header of the response
+        etag: Option<String>,
+    },
+    /// Error or warning was recorded for a page
+    ErrorFound {
+        url: Url,
+        rule: &'static str,
+        warning: bool,
+        message: String,
+        /// URL of the documentation of the rule
+        doc_url: Option<&'static str>,
+    },
+    /// Crawl has finished and all checks have been run
+    CrawlFinished {
+        result: bool,
+        pages: usize,
+        links: usize,
+        errors: usize,
+        warnings: usize,
+    },
+}
+
+impl CrawlEvent {
+    /// Creates an `ErrorFound` event for `error`, which was recorded for the page at `url`
+    pub fn error_found(url: &Url, error: &SpiderError) -> Self {
+        CrawlEvent::ErrorFound {
+            url: url.clone(),
+            rule: error.error_type.get_rule_name(),
+            warning: error.is_warning(),
+            message: error.to_string(),
+            doc_url: error.error_type.doc_url(),
+        }
+    }
+
+    /// Returns this event as a JSON object. The `event` field holds the name of the event (ie. `page_started`).
+    pub fn to_json(&self) -> Value {
+        match self {
+            CrawlEvent::PageStarted { url } => json!({
+                "event": "page_started",
+                "url": url.as_str(),
+            }),
+            CrawlEvent::PageFinished {
+                url,
+                status_code,
+                good,
+                last_modified,
+                etag,
+            } => json!({
+                "event": "page_finished",
+                "url": url.as_str(),
+                "status_code": status_code,
+                "good": good,
+                "last_modified": last_modified,
+                "etag": etag,
+            }),
+            CrawlEvent::ErrorFound {
+                url,
+                rule,
+                warning,
+                message,
+                doc_url,
+            } => json!({
+                "event": "error_found",
+                "url": url.as_str(),
+                "rule": rule,
+                "warning": warning,
+                "message": message,
+                "doc_url": doc_url,
+            }),
+            CrawlEvent::CrawlFinished {
+                result,
+                pages,
+                links,
+                errors,
+                warnings,
+            } => json!({
+                "event": "crawl_finished",
+                "result": result,
+                "pages": pages,
+                "links": links,
+                "errors": errors,
+                "warnings": warnings,
+            }),
+        }
+    }
+}
+
+/// Writes every event received from `events` to `out` as newline-delimited JSON, until all senders are dropped.
+/// Each line is flushed as soon as it is written, so that the output can be followed live.
+pub fn write_ndjson<W: Write>(events: Receiver<CrawlEvent>, mut out: W) -> io::Result<()> {
+    for event in events {
+        writeln!(out, "{}", event.to_json())?;
+        out.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,674 @@
+//! Holds the `Fetcher` trait that the traversal algorithm uses to send HTTP requests,
+//! its default implementation using `reqwest`, and the `FileFetcher` for local files
+
+use futures::future::BoxFuture;
+use log::warn;
+use reqwest::header::{
+    HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER,
+};
+use reqwest::{redirect, Client, Method, Proxy, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use url::{Host, Url};
+
+use crate::dns::CachingResolver;
+use crate::url_helpers::is_same_registrable_domain;
+use crate::SpiderOptions;
+
+/// Future that resolves to the body of a response
+pub type BodyFuture = BoxFuture<'static, Result<String, FetchError>>;
+
+/// Semaphore that lets `limit` requests be in flight at once
+#[derive(Debug)]
+struct LimitState {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+/// Shared limit on the number of requests in flight at once, see `SpiderOptions::max_concurrency`.
+/// Clones of a limiter share the same limit.
+#[derive(Debug, Default, Clone)]
+pub struct RequestLimiter {
+    /// Created on the first request, and again whenever the limit changes
+    state: Arc<Mutex<Option<LimitState>>>,
+}
+
+impl RequestLimiter {
+    /// Waits until fewer than `max_concurrency` requests are in flight, then returns a permit that lets one request be sent.
+    /// The request counts as in flight until the permit is dropped.
+    /// Returns `None` right away if `max_concurrency` is 0, which means unlimited.
+    pub async fn acquire(&self, max_concurrency: usize) -> Option<OwnedSemaphorePermit> {
+        if max_concurrency == 0 {
+            return None;
+        }
+        let semaphore = {
+            let mut state = self.state.lock().unwrap();
+            match state.as_ref() {
+                Some(s) if s.limit == max_concurrency => s.semaphore.clone(),
+                _ => {
+                    let semaphore = Arc::new(Semaphore::new(max_concurrency));
+                    *state = Some(LimitState {
+                        limit: max_concurrency,
+                        semaphore: semaphore.clone(),
+                    });
+                    semaphore
+                }
+            }
+        };
+        // The semaphore is never closed
+        Some(semaphore.acquire_owned().await.unwrap())
+    }
+}
+
+/// Spaces out requests to the same host, see `SpiderOptions::request_delay`.
+/// Requests to different hosts are not delayed by each other. Clones of a throttle share the same hosts.
+#[derive(Debug, Default, Clone)]
+pub struct HostThrottle {
+    /// Earliest time that the next request to each host may be sent
+    next_request: Arc<Mutex<HashMap<Host<String>, Instant>>>,
+}
+
+impl HostThrottle {
+    /// Waits until a request to the host of `url` may be sent, `delay` after the previous request to the host.
+    /// Concurrent callers for the same host are given consecutive time slots.
+    /// Returns right away if `delay` is `None`, or if `url` does not have a host (ie. `file://` URLs).
+    pub async fn wait(&self, url: &Url, delay: Option<Duration>) {
+        let (Some(delay), Some(host)) = (delay, url.host()) else {
+            return;
+        };
+        let send_at = {
+            let mut next_request = self.next_request.lock().unwrap();
+            let now = Instant::now();
+            let send_at = next_request
+                .get(&host.to_owned())
+                .map_or(now, |next| (*next).max(now));
+            next_request.insert(host.to_owned(), send_at + delay);
+            send_at
+        };
+        tokio::time::sleep_until(send_at).await;
+
+        // Timers can fire late, the next request waits for the delay from when this one is actually sent
+        let mut next_request = self.next_request.lock().unwrap();
+        if let Some(next) = next_request.get_mut(&host.to_owned()) {
+            *next = (*next).max(Instant::now() + delay);
+        }
+    }
+}
+
+/// HTTP request that the traversal algorithm wants sent
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    /// HTTP method of the request
+    pub method: Method,
+    /// URL the request is sent to
+    pub url: Url,
+    /// User name and password that the request is authenticated with using HTTP Basic Auth
+    pub basic_auth: Option<(String, String)>,
+}
+
+impl FetchRequest {
+    /// Convenience constructor for an HTTP GET request
+    pub fn get(url: &Url) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    /// Convenience constructor for an HTTP HEAD request
+    pub fn head(url: &Url) -> Self {
+        Self::new(Method::HEAD, url)
+    }
+
+    /// Constructor for an HTTP request with any method
+    pub fn new(method: Method, url: &Url) -> Self {
+        Self {
+            method,
+            url: url.clone(),
+            basic_auth: None,
+        }
+    }
+}
+
+/// Response returned by a `Fetcher`
+pub struct FetchedResponse {
+    /// URL of the response, after any redirects were followed
+    pub url: Url,
+    /// HTTP status code of the response
+    pub status: StatusCode,
+    /// Headers of the response
+    pub headers: HeaderMap,
+    /// Body of the response. Only read if the traversal algorithm needs it.
+    pub body: BodyFuture,
+    /// `true` if the response was sent compressed (ie. gzip), `false` if it was not.
+    /// `None` if the fetcher can not tell.
+    pub compressed: Option<bool>,
+    /// URLs that the request was redirected through, starting with the requested URL and ending with `url`.
+    /// Empty if the request was not redirected, or if the fetcher only knows the final `url`.
+    pub redirect_chain: Vec<Url>,
+}
+
+impl FetchedResponse {
+    /// Returns how long to wait before retrying the request, if the server asked for a retry.
+    /// Only `503 Service Unavailable` and `429 Too Many Requests` responses with a valid `Retry-After` header are retried.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.status {
+            StatusCode::SERVICE_UNAVAILABLE | StatusCode::TOO_MANY_REQUESTS => {
+                parse_retry_after(&self.headers, SystemTime::now())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parses the `Retry-After` header, which is either a number of seconds or an HTTP-date.
+/// HTTP-dates are converted into the duration from `now` until the date, dates in the past result in a duration of zero.
+/// Returns `None` if the header is missing or invalid.
+pub fn parse_retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[test]
+fn test_parse_retry_after_seconds() {
+    let mut headers = HeaderMap::new();
+    headers.insert(RETRY_AFTER, "120".parse().unwrap());
+    assert_eq!(
+        parse_retry_after(&headers, SystemTime::now()),
+        Some(Duration::from_secs(120))
+    );
+}
+
+#[test]
+fn test_parse_retry_after_http_date() {
+    let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        RETRY_AFTER,
+        "Wed, 21 Oct 2015 07:28:30 GMT".parse().unwrap(),
+    );
+    assert_eq!(
+        parse_retry_after(&headers, now),
+        Some(Duration::from_secs(30))
+    );
+
+    // Dates in the past do not need to be waited for
+    headers.insert(
+        RETRY_AFTER,
+        "Wed, 21 Oct 2015 07:27:00 GMT".parse().unwrap(),
+    );
+    assert_eq!(parse_retry_after(&headers, now), Some(Duration::ZERO));
+}
+
+#[test]
+fn test_parse_retry_after_invalid() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(parse_retry_after(&headers, SystemTime::now()), None);
+    headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+    assert_eq!(parse_retry_after(&headers, SystemTime::now()), None);
+}
+
+/// Error returned by a `Fetcher` when no response could be retrieved
+#[derive(Debug)]
+pub struct FetchError {
+    /// HTTP status code associated with the error, if any
+    pub status: Option<StatusCode>,
+    /// Description of what went wrong
+    pub message: String,
+    /// `true` if the request was cancelled because it took longer than `SpiderOptions::timeout`
+    pub timed_out: bool,
+}
+
+impl std::error::Error for FetchError {}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        Self {
+            status: e.status(),
+            message: e.to_string(),
+            timed_out: e.is_timeout(),
+        }
+    }
+}
+
+/// Sends HTTP requests on behalf of the traversal algorithm.
+/// Implement this trait to use a different HTTP backend, or to supply canned responses in tests.
+pub trait Fetcher: Send + Sync {
+    /// Sends `request` and returns the response
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>>;
+}
+
+impl Fetcher for Client {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let mut builder = self.request(request.method, request.url);
+            if let Some((username, password)) = request.basic_auth {
+                builder = builder.basic_auth(username, Some(password));
+            }
+            let response = builder.send().await?;
+            let headers = response.headers().clone();
+            // reqwest removes the Content-Encoding and Content-Length headers when it decompresses a response,
+            // so a Content-Length means the response was not compressed.
+            let compressed = if headers.contains_key(CONTENT_ENCODING) {
+                Some(true)
+            } else if headers.contains_key(CONTENT_LENGTH) {
+                Some(false)
+            } else {
+                None
+            };
+            Ok(FetchedResponse {
+                url: response.url().clone(),
+                status: response.status(),
+                headers,
+                body: Box::pin(async move { Ok(response.text().await?) }),
+                compressed,
+                redirect_chain: vec![],
+            })
+        })
+    }
+}
+
+/// HTTP client built by `build_client()`, which also returns the redirect chain of each response
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    pub client: Client,
+    /// Redirect chains recorded by the redirect policy of the `client`
+    redirect_history: RedirectHistory,
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let url = request.url.clone();
+            let result = self.client.fetch(request).await;
+            // The chain is removed whether or not the request succeeded, so that no chain is kept once its request is done
+            let redirect_chain = self.redirect_history.take(&url);
+            result.map(|response| FetchedResponse {
+                redirect_chain,
+                ..response
+            })
+        })
+    }
+}
+
+/// `HttpFetcher` built with the default options when its first request is sent, so that creating it never fails.
+/// Used by `SpiderCrab::default()`, use `SpiderCrab::from_options()` to build the HTTP client up front.
+#[derive(Debug, Default)]
+pub struct LazyHttpFetcher {
+    /// Built on the first request. Holds why the HTTP client could not be built, if it could not
+    fetcher: OnceLock<Result<HttpFetcher, String>>,
+}
+
+impl Fetcher for LazyHttpFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        let fetcher = self
+            .fetcher
+            .get_or_init(|| build_client(&SpiderOptions::default()).map_err(|e| e.to_string()));
+        match fetcher {
+            Ok(fetcher) => fetcher.fetch(request),
+            Err(message) => {
+                let error = FetchError {
+                    status: None,
+                    message: format!("Failed to build the HTTP client: {}", message),
+                    timed_out: false,
+                };
+                Box::pin(async move { Err(error) })
+            }
+        }
+    }
+}
+
+/// Controls which redirects the HTTP client follows, see `SpiderOptions::redirect_policy`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Follow every redirect
+    #[default]
+    All,
+    /// Only follow redirects to the same registrable domain (ie. `example.com` to `www.example.com`)
+    SameDomain,
+    /// Only follow redirects to the same host and port
+    SameHost,
+}
+
+impl RedirectPolicy {
+    /// Returns `true` if a request for `url` may be redirected to `redirect_url`
+    pub fn allows(&self, url: &Url, redirect_url: &Url) -> bool {
+        match self {
+            RedirectPolicy::All => true,
+            RedirectPolicy::SameDomain => is_same_registrable_domain(url, redirect_url),
+            RedirectPolicy::SameHost => {
+                url.host() == redirect_url.host()
+                    && url.port_or_known_default() == redirect_url.port_or_known_default()
+            }
+        }
+    }
+}
+
+impl FromStr for RedirectPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(RedirectPolicy::All),
+            "same-domain" => Ok(RedirectPolicy::SameDomain),
+            "same-host" => Ok(RedirectPolicy::SameHost),
+            _ => Err(format!(
+                "Invalid redirect policy {:?}, expected all, same-domain or same-host",
+                s
+            )),
+        }
+    }
+}
+
+/// User agent that requests are sent with, unless `SpiderOptions::user_agent` is set
+pub const DEFAULT_USER_AGENT: &str = concat!("spider-crab/", env!("CARGO_PKG_VERSION"));
+
+/// Most redirects in a row that the HTTP client follows, like the `reqwest` default
+const MAX_REDIRECTS: usize = 10;
+
+/// Redirect chains of the requests sent by the HTTP client, recorded by its redirect policy (see `build_client()`).
+/// Chains are removed by `HttpFetcher` as soon as their request is done. Clones of a history share the same chains.
+#[derive(Debug, Default, Clone)]
+struct RedirectHistory {
+    /// URLs that each request was redirected through, by the URL that was requested
+    chains: Arc<Mutex<HashMap<Url, Vec<Url>>>>,
+}
+
+impl RedirectHistory {
+    /// Records that a request was redirected through `chain`, which starts with the requested URL
+    fn record(&self, chain: Vec<Url>) {
+        self.chains.lock().unwrap().insert(chain[0].clone(), chain);
+    }
+
+    /// Removes and returns the URLs that the request for `url` was redirected through, starting with `url` and ending with the URL of the response.
+    /// Returns an empty chain if the request was not redirected.
+    fn take(&self, url: &Url) -> Vec<Url> {
+        self.chains.lock().unwrap().remove(url).unwrap_or_default()
+    }
+}
+
+/// Builds the `reqwest` redirect policy for `policy`, which records the redirects that are followed in `history`.
+/// Redirects that `policy` does not allow are not followed, the redirect response is returned instead.
+fn build_redirect_policy(policy: RedirectPolicy, history: RedirectHistory) -> redirect::Policy {
+    redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let mut chain = attempt.previous().to_vec();
+        if policy.allows(&chain[0], attempt.url()) {
+            chain.push(attempt.url().clone());
+            history.record(chain);
+            attempt.follow()
+        } else {
+            // The response of the last URL of the chain is returned
+            if chain.len() > 1 {
+                history.record(chain);
+            }
+            attempt.stop()
+        }
+    })
+}
+
+/// Builds the HTTP client used to crawl with `options`. Hosts are looked up with a `CachingResolver` using `options.host_overrides`.
+/// Connections are tuned with `options.http2_prior_knowledge`, `options.pool_max_idle_per_host`, `options.pool_idle_timeout` and `options.tcp_keepalive`.
+/// Requests are sent with `options.user_agent` and cancelled after `options.timeout`, cookies are kept if `options.enable_cookies` is set, and redirects are followed according to `options.redirect_policy`.
+/// The redirects that are followed are returned as the `redirect_chain` of each response.
+/// Requests go through `options.proxy`, or the proxies of the environment if it is not set. Returns an error if `options.proxy` is not a valid URL.
+/// TLS certificates are verified unless `options.accept_invalid_certs` is set.
+pub fn build_client(options: &SpiderOptions) -> reqwest::Result<HttpFetcher> {
+    let mut builder = Client::builder()
+        .dns_resolver(Arc::new(CachingResolver::new(
+            options.host_overrides.clone(),
+        )))
+        .tcp_keepalive(options.tcp_keepalive)
+        .user_agent(options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+        .default_headers(options.headers.clone())
+        .cookie_store(options.enable_cookies);
+    if options.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(max_idle) = options.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = options.pool_idle_timeout {
+        builder = builder.pool_idle_timeout(idle_timeout);
+    }
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+    if options.accept_invalid_certs {
+        warn!("TLS certificates are not verified, responses may have been tampered with");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let redirect_history = RedirectHistory::default();
+    let client = builder
+        .redirect(build_redirect_policy(
+            options.redirect_policy,
+            redirect_history.clone(),
+        ))
+        .build()?;
+    Ok(HttpFetcher {
+        client,
+        redirect_history,
+    })
+}
+
+#[test]
+fn test_build_client_with_pool_settings() {
+    let options = SpiderOptions {
+        http2_prior_knowledge: true,
+        pool_max_idle_per_host: Some(4),
+        pool_idle_timeout: Some(Duration::from_secs(30)),
+        tcp_keepalive: Some(Duration::from_secs(60)),
+        ..SpiderOptions::default()
+    };
+    assert!(build_client(&options).is_ok());
+
+    let spider_crab = crate::SpiderCrab::from_options(options).unwrap();
+    assert_eq!(spider_crab.options.pool_max_idle_per_host, Some(4));
+    assert_eq!(
+        spider_crab.options.pool_idle_timeout,
+        Some(Duration::from_secs(30))
+    );
+}
+
+#[test]
+fn test_build_client_accepting_invalid_certs() {
+    let options = SpiderOptions {
+        accept_invalid_certs: true,
+        ..SpiderOptions::default()
+    };
+    assert!(build_client(&options).is_ok());
+
+    let spider_crab = crate::SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.options.accept_invalid_certs);
+}
+
+#[test]
+fn test_build_client_with_invalid_proxy() {
+    let options = SpiderOptions {
+        proxy: Some(String::from("http://proxy example:8080")),
+        ..SpiderOptions::default()
+    };
+    let error = crate::SpiderCrab::from_options(options).err().unwrap();
+    assert!(error.is_builder());
+    assert!(error.to_string().contains("invalid domain character"));
+
+    let options = SpiderOptions {
+        proxy: Some(String::from("http://proxy.example.com:8080")),
+        ..SpiderOptions::default()
+    };
+    assert!(build_client(&options).is_ok());
+}
+
+#[tokio::test]
+async fn test_lazy_http_fetcher() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/")
+        .with_status(200)
+        .create_async()
+        .await;
+
+    // The HTTP client is not built until the first request is sent
+    let fetcher = LazyHttpFetcher::default();
+    assert!(fetcher.fetcher.get().is_none());
+    let url = Url::parse(&server.url()).unwrap();
+    let response = fetcher.fetch(FetchRequest::get(&url)).await.unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(fetcher.fetcher.get().is_some_and(|f| f.is_ok()));
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_redirect_policy_same_host() {
+    let url = Url::parse("http://example.com/old.html").unwrap();
+    let allows = |redirect_url: &str| {
+        RedirectPolicy::SameHost.allows(&url, &Url::parse(redirect_url).unwrap())
+    };
+    assert!(allows("http://example.com/new.html"));
+    assert!(allows("http://example.com:80/new.html"));
+    assert!(!allows("http://example.com:8080/new.html"));
+    assert!(!allows("https://example.com/new.html"));
+    assert!(!allows("http://www.example.com/new.html"));
+}
+
+#[tokio::test]
+async fn test_redirect_chains_are_not_kept() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/old.html")
+        .with_status(301)
+        .with_header("location", "/new.html")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/new.html")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let fetcher = build_client(&SpiderOptions::default()).unwrap();
+    let old_url = Url::parse(&format!("{}/old.html", server.url())).unwrap();
+    let response = fetcher.fetch(FetchRequest::get(&old_url)).await.unwrap();
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.redirect_chain.len(), 2);
+    assert_eq!(response.redirect_chain[0], old_url);
+    assert_eq!(response.redirect_chain[1], response.url);
+    assert!(fetcher.redirect_history.chains.lock().unwrap().is_empty());
+}
+
+/// Fetcher that reads `file://` URLs from the local disk, used to check a static website before it is deployed.
+/// Missing files are returned as a `404 Not Found` response.
+#[derive(Debug, Clone)]
+pub struct FileFetcher {
+    /// Names of the files that are served for a URL of a directory (ie. `/docs/`), in order of preference
+    pub index_files: Vec<String>,
+}
+
+impl FileFetcher {
+    pub fn new(index_files: Vec<String>) -> Self {
+        Self { index_files }
+    }
+
+    /// Returns the file that `url` is served from. URLs of directories are served from the first of the `index_files` that exists.
+    /// Returns `None` if there is no such file.
+    pub fn resolve_path(&self, url: &Url) -> Option<PathBuf> {
+        let path = url.to_file_path().ok()?;
+        if path.is_dir() {
+            return self
+                .index_files
+                .iter()
+                .map(|f| path.join(f))
+                .find(|p| p.is_file());
+        }
+        path.is_file().then_some(path)
+    }
+}
+/// Returns `path` with each of its components spelled as they are on the disk, ignoring case.
+/// Components that exist with the exact casing are preferred. Returns `None` if no such path exists.
+pub fn find_path_ignoring_case(path: &Path) -> Option<PathBuf> {
+    let mut found = PathBuf::new();
+    for component in path.components() {
+        let std::path::Component::Normal(name) = component else {
+            found.push(component);
+            continue;
+        };
+        let name = name.to_str()?;
+        let entries: Vec<String> = fs::read_dir(&found)
+            .ok()?
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .collect();
+        let entry = entries
+            .iter()
+            .find(|e| *e == name)
+            .or_else(|| entries.iter().find(|e| e.eq_ignore_ascii_case(name)))?;
+        found.push(entry);
+    }
+    Some(found)
+}
+
+/// Returns the Content-Type of a file with the extension of `path`
+fn get_file_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+impl Fetcher for FileFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let mut headers = HeaderMap::new();
+            let (status, body) = match self.resolve_path(&request.url) {
+                Some(path) => {
+                    let body = fs::read(&path).map_err(|e| FetchError {
+                        status: None,
+                        message: format!("Failed to read {}: {}", path.display(), e),
+                        timed_out: false,
+                    })?;
+                    headers.insert(
+                        CONTENT_TYPE,
+                        HeaderValue::from_static(get_file_content_type(&path)),
+                    );
+                    headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    (StatusCode::OK, String::from_utf8_lossy(&body).into_owned())
+                }
+                None => (StatusCode::NOT_FOUND, String::new()),
+            };
+
+            Ok(FetchedResponse {
+                url: request.url,
+                status,
+                headers,
+                body: Box::pin(async move { Ok(body) }),
+                compressed: None,
+                redirect_chain: vec![],
+            })
+        })
+    }
+}
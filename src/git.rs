@@ -0,0 +1,102 @@
+//! Holds helpers for scoping reported errors to the files changed in a git repository
+
+use petgraph::Direction;
+use std::collections::HashSet;
+use std::io;
+use std::process::Command;
+use url::Url;
+
+use crate::error::SpiderError;
+use crate::SpiderCrab;
+
+/// Set of site files that changed, given as paths relative to the root of the site (ie. `blog/index.html`)
+#[derive(Debug, Default, Clone)]
+pub struct ChangedFiles {
+    paths: HashSet<String>,
+}
+
+impl ChangedFiles {
+    /// Creates a set of changed files from paths relative to the root of the site
+    pub fn from_paths<I, S>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            paths: paths
+                .into_iter()
+                .map(|p| p.as_ref().trim_start_matches('/').to_string())
+                .collect(),
+        }
+    }
+
+    /// Runs `git diff --name-only <since>` in `site_root` to find the site files changed since the git ref `since`.
+    /// `site_root` is the directory that the site is served from.
+    pub fn from_git(since: &str, site_root: &str) -> io::Result<Self> {
+        let output = Command::new("git")
+            .current_dir(site_root)
+            .args(["diff", "--name-only", "--relative", since, "--", "."])
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "git diff failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(Self::from_paths(
+            String::from_utf8_lossy(&output.stdout).lines(),
+        ))
+    }
+
+    /// Returns `true` if the page at `url` is served from one of the changed files.
+    /// URLs of directories (ie. `/blog/`) are served from the `index.html` file inside of them.
+    pub fn contains_url(&self, url: &Url) -> bool {
+        let mut path = url.path().trim_start_matches('/').to_string();
+        if path.is_empty() || path.ends_with('/') {
+            path.push_str("index.html");
+        }
+        self.paths.contains(&path)
+    }
+
+    /// Returns `true` if `error` was found on a changed page, or is about a link to a changed page
+    pub fn contains_error(&self, error: &SpiderError) -> bool {
+        [&error.source_page, &error.target_page]
+            .into_iter()
+            .flatten()
+            .filter_map(|u| Url::parse(u).ok())
+            .any(|u| self.contains_url(&u))
+    }
+}
+
+impl SpiderCrab {
+    /// Returns the errors found on pages in `changed`, or on pages that are linked to from a page in `changed`.
+    /// Errors with a source or target page in `changed` are also returned.
+    pub fn errors_in_changed_files(&self, changed: &ChangedFiles) -> Vec<&SpiderError> {
+        let mut errors = Vec::<&SpiderError>::new();
+        for node_index in self.graph.node_indices() {
+            let page = self.graph.node_weight(node_index).unwrap();
+            let linked_from_changed = changed.contains_url(&page.url)
+                || self
+                    .graph
+                    .neighbors_directed(node_index, Direction::Incoming)
+                    .any(|n| changed.contains_url(&self.graph.node_weight(n).unwrap().url));
+            errors.extend(
+                page.errors
+                    .iter()
+                    .filter(|e| linked_from_changed || changed.contains_error(e)),
+            );
+        }
+        errors
+    }
+}
+
+#[test]
+fn test_changed_files_contains_url() {
+    let changed = ChangedFiles::from_paths(["index.html", "blog/post.html", "/docs/index.html"]);
+    assert!(changed.contains_url(&Url::parse("https://example.com/").unwrap()));
+    assert!(changed.contains_url(&Url::parse("https://example.com/blog/post.html").unwrap()));
+    assert!(changed.contains_url(&Url::parse("https://example.com/docs/").unwrap()));
+    assert!(!changed.contains_url(&Url::parse("https://example.com/blog/").unwrap()));
+    assert!(!changed.contains_url(&Url::parse("https://example.com/about.html").unwrap()));
+}
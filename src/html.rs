@@ -0,0 +1,158 @@
+//! Holds functions to render the crawl results as a standalone HTML report
+use crate::error::SpiderError;
+use crate::{Page, SpiderCrab};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Styles of the HTML report, inlined so that the report is a single file
+const HTML_REPORT_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+th { background: #eee; cursor: pointer; }
+tr.bad td { background: #fdd; }
+li.warning { color: #864; }
+input { margin-bottom: 1em; padding: 0.3em; width: 30em; }";
+
+/// Scripts of the HTML report: sorting the pages by a column when its header is clicked, and filtering the errors by text
+const HTML_REPORT_SCRIPT: &str = "document.querySelectorAll('#pages th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const body = document.querySelector('#pages tbody');
+    const ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    const rows = Array.from(body.rows);
+    rows.sort((a, b) => {
+      const x = a.cells[column].textContent, y = b.cells[column].textContent;
+      const order = x.localeCompare(y, undefined, { numeric: true });
+      return ascending ? order : -order;
+    });
+    rows.forEach(row => body.appendChild(row));
+  });
+});
+document.querySelector('#error-filter').addEventListener('input', event => {
+  const text = event.target.value.toLowerCase();
+  document.querySelectorAll('section.rule').forEach(section => {
+    let shown = 0;
+    section.querySelectorAll('li').forEach(li => {
+      const match = li.textContent.toLowerCase().includes(text);
+      li.hidden = !match;
+      shown += match;
+    });
+    section.hidden = shown === 0;
+  });
+});";
+
+/// Returns `s` with the characters that have a meaning in HTML replaced by entities
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_escape_html() {
+    assert_eq!(escape_html("plain text"), "plain text");
+    assert_eq!(
+        escape_html("<a href=\"x\">Tom & Jerry's</a>"),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+}
+
+/// Returns the table row of `page` in the HTML report
+fn get_page_row(page: &Page) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    format!(
+        "<tr{}><td><a href=\"{url}\">{url}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        if page.good == Some(false) { " class=\"bad\"" } else { "" },
+        page.status_code.map(|s| s.as_u16().to_string()).unwrap_or_default(),
+        escape_html(page.content_type.as_deref().unwrap_or_default()),
+        page.good.map(yes_no).unwrap_or_default(),
+        yes_no(page.visited),
+        page.response_time.map(|t| t.as_millis().to_string()).unwrap_or_default(),
+        url = escape_html(page.url.as_str()),
+    )
+}
+
+impl SpiderCrab {
+    /// Returns a self-contained HTML page that summarizes the crawl, for sharing with people who do not use the command line.
+    /// Pages are listed in a table that can be sorted by clicking a column header, sorted by URL at first.
+    /// Errors are grouped into one section per rule, and can be filtered by text.
+    pub fn to_html_report(&self) -> String {
+        let report = self.report();
+        let mut pages: Vec<&Page> = self.graph.node_weights().collect();
+        pages.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        let mut errors_by_rule = BTreeMap::<&str, Vec<&SpiderError>>::new();
+        for e in report.sorted_errors() {
+            errors_by_rule
+                .entry(e.error_type.get_rule_name())
+                .or_default()
+                .push(e);
+        }
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>spider-crab report</title>\n");
+        writeln!(
+            html,
+            "<style>\n{}\n</style>\n</head>\n<body>",
+            HTML_REPORT_STYLE
+        )
+        .unwrap();
+        html.push_str("<h1>spider-crab report</h1>\n");
+        writeln!(
+            html,
+            "<p>{} pages, {} errors, {} warnings</p>",
+            pages.len(),
+            report.error_count(),
+            report.warning_count()
+        )
+        .unwrap();
+
+        html.push_str("<h2>Pages</h2>\n<table id=\"pages\">\n<thead><tr><th>URL</th><th>Status</th><th>Content-Type</th><th>Good</th><th>Visited</th><th>Response time (ms)</th></tr></thead>\n<tbody>\n");
+        for page in pages {
+            html.push_str(&get_page_row(page));
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        html.push_str("<h2>Errors</h2>\n<input id=\"error-filter\" type=\"search\" placeholder=\"Filter errors\">\n");
+        for (rule, errors) in errors_by_rule {
+            writeln!(
+                html,
+                "<section class=\"rule\" id=\"{rule}\">\n<h3>{rule} ({})</h3>",
+                errors.len(),
+                rule = rule
+            )
+            .unwrap();
+            if let Some(doc_url) = errors[0].error_type.doc_url() {
+                writeln!(html, "<p><a href=\"{}\">How to fix</a></p>", doc_url).unwrap();
+            }
+            html.push_str("<ul>\n");
+            for e in errors {
+                writeln!(
+                    html,
+                    "<li class=\"{}\">{}</li>",
+                    if e.is_warning() { "warning" } else { "error" },
+                    escape_html(&e.get_message())
+                )
+                .unwrap();
+            }
+            html.push_str("</ul>\n</section>\n");
+        }
+
+        writeln!(
+            html,
+            "<script>\n{}\n</script>\n</body>\n</html>",
+            HTML_REPORT_SCRIPT
+        )
+        .unwrap();
+        html
+    }
+}
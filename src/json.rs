@@ -0,0 +1,34 @@
+//! Holds functions to render the crawl results as a JSON report
+use crate::error::SpiderError;
+use crate::{Page, SpiderCrab};
+use reqwest::StatusCode;
+use serde::{Serialize, Serializer};
+
+/// Schema of the JSON report, see `SpiderCrab::to_json_report()`
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    pages: Vec<&'a Page>,
+    errors: Vec<&'a SpiderError>,
+}
+
+/// Serializes an HTTP status code as its number
+pub(crate) fn serialize_status_code<S: Serializer>(
+    status_code: &Option<StatusCode>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    status_code.map(|s| s.as_u16()).serialize(serializer)
+}
+
+impl SpiderCrab {
+    /// Returns every page and every error of the crawl as a JSON document.
+    /// Pages are sorted by URL, and errors in the same order as the summary.
+    pub fn to_json_report(&self) -> String {
+        let mut pages: Vec<&Page> = self.graph.node_weights().collect();
+        pages.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        let report = JsonReport {
+            pages,
+            errors: self.report().sorted_errors(),
+        };
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+}
@@ -1,19 +1,55 @@
-use error::{SpiderError, SpiderErrorType};
+use algo::CrawlContext;
+use error::{Severity, SpiderError, SpiderErrorType};
+use events::CrawlEvent;
+use fetch::{Fetcher, LazyHttpFetcher, RedirectPolicy};
+use json::serialize_status_code;
+use pause::PauseHandle;
+use report::CrawlReport;
 
 use log::info;
+use petgraph::algo::tarjan_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
-use reqwest::StatusCode;
+use petgraph::Direction;
+use policy::{DefaultGoodnessPolicy, GoodnessPolicy};
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
 use scraper::{selector::CssLocalName, Selector};
+use serde::Serialize;
 use std::io::{BufRead, BufReader};
+use std::net::IpAddr;
 use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Mutex;
-use std::{collections::HashMap, fs::File};
+use std::time::{Duration, Instant, SystemTime};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+};
 use url::{Host, Url};
+use url_helpers::{check_host, is_query_variation, matches_url_pattern, QueryParamFilter, Shard};
 
 pub mod algo;
+pub mod config;
+pub mod csv;
+pub mod dns;
 pub mod dot;
 pub mod error;
+pub mod events;
+pub mod fetch;
+pub mod git;
+pub mod html;
+pub mod json;
+pub mod logging;
+pub mod pause;
+pub mod policy;
+pub mod report;
+pub mod robots;
+pub mod sitemap;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod url_helpers;
+pub mod watch;
 
 #[cfg(test)]
 mod test_utils;
@@ -30,22 +66,70 @@ pub struct Link {
 }
 
 /// Representation of a document/page
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Page {
     /// Title of the page
+    #[serde(skip)]
     pub title: Option<String>,
     /// Content-Type that was given when this page was visited
     pub content_type: Option<String>,
-    /// True if the page was visited and a 2XX HTTP status code was returned, false otherwise
+    /// True if the page was visited and `SpiderOptions::goodness_policy` accepted it, false otherwise
     pub good: Option<bool>,
     /// True if this page was visited, false otherwise
     pub visited: bool,
     /// URL that this page is represented by. Does not include URL parameters or fragments
     pub url: Url,
     /// HTTP status code returned when this page was visited
+    #[serde(serialize_with = "serialize_status_code")]
     pub status_code: Option<StatusCode>,
     /// Vector of errors encountered while scraping this page
+    #[serde(skip)]
     pub errors: Vec<SpiderError>,
+    /// URLs declared as localized alternates of this page via `<link rel="alternate" hreflang="...">`
+    #[serde(skip)]
+    pub hreflang_alternates: Vec<Url>,
+    /// URLs of stylesheets and scripts that block this page from rendering, see `url_helpers::is_render_blocking()`.
+    /// Only recorded if `SpiderOptions::record_render_blocking` is set.
+    #[serde(skip)]
+    pub render_blocking_resources: Vec<Url>,
+    /// Value of the `Last-Modified` header returned when this page was visited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Value of the `ETag` header returned when this page was visited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Priority given to this page by the sitemap the crawl was seeded from, see `SpiderCrab::visit_sitemap()`
+    #[serde(skip)]
+    pub sitemap_priority: Option<f32>,
+    /// Change frequency (ie. `daily`) given to this page by the sitemap the crawl was seeded from
+    #[serde(skip)]
+    pub sitemap_changefreq: Option<String>,
+    /// Links that point at a fragment, as the linked page and the fragment (ie. `section` for `#section`).
+    /// Links to pages on the hosts are only recorded if `SpiderOptions::check_fragments` is set,
+    /// links to pages outside of the hosts if `SpiderOptions::check_external_fragments` is set.
+    #[serde(skip)]
+    pub linked_fragments: Vec<(Url, String)>,
+    /// Anchors of this page that a fragment can point to, see `SpiderOptions::check_fragments` and `SpiderOptions::check_external_fragments`.
+    /// Only recorded for HTML pages that one of the two options applies to.
+    #[serde(skip)]
+    pub anchors: Option<HashSet<String>>,
+    /// Time until the response headers were received when this page was visited
+    #[serde(skip)]
+    pub response_time: Option<Duration>,
+    /// Number of words in the visible text of this page, not counting scripts and stylesheets.
+    /// Only recorded if `SpiderOptions::record_word_count` or `SpiderOptions::min_word_count` is set.
+    #[serde(skip)]
+    pub word_count: Option<usize>,
+    /// URLs that the request for this page was redirected through, starting with the page URL and ending with the URL of the response.
+    /// Empty if the request was not redirected
+    #[serde(skip)]
+    pub redirect_chain: Vec<Url>,
+    /// True if this page was linked to as a resource of another page (ie. an image, script or stylesheet), see `url_helpers::is_subresource()`
+    #[serde(skip)]
+    pub subresource: bool,
+    /// True if every link to this page found so far is protocol-relative (ie. `//host/path`), see `SpiderOptions::protocol_relative_in_host`
+    #[serde(skip)]
+    pub protocol_relative: bool,
 }
 
 impl Page {
@@ -58,10 +142,59 @@ impl Page {
             url: url.clone(),
             status_code: None,
             errors: Vec::<SpiderError>::new(),
+            hreflang_alternates: Vec::<Url>::new(),
+            render_blocking_resources: Vec::<Url>::new(),
+            last_modified: None,
+            etag: None,
+            sitemap_priority: None,
+            sitemap_changefreq: None,
+            linked_fragments: Vec::<(Url, String)>::new(),
+            anchors: None,
+            response_time: None,
+            word_count: None,
+            redirect_chain: Vec::<Url>::new(),
+            subresource: false,
+            protocol_relative: false,
+        }
+    }
+
+    /// Records `error` for this page, unless an error with the same type, target page and attribute was already recorded.
+    /// Returns `true` if the error was recorded.
+    pub fn push_error(&mut self, error: SpiderError) -> bool {
+        let duplicate = self.errors.iter().any(|e| {
+            e.error_type == error.error_type
+                && e.target_page == error.target_page
+                && e.attribute == error.attribute
+        });
+        if duplicate {
+            return false;
         }
+        self.errors.push(error);
+        true
+    }
+
+    /// Discards everything but the URL, status and errors of this page once it has been checked, see `SpiderOptions::memory_bounded`.
+    /// Fixed-size fields such as `response_time` are kept for the summary of the crawl.
+    pub fn evict(&mut self) {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.shrink_to_fit();
+        *self = Self {
+            good: self.good,
+            visited: self.visited,
+            status_code: self.status_code,
+            errors,
+            sitemap_priority: self.sitemap_priority,
+            response_time: self.response_time,
+            word_count: self.word_count,
+            subresource: self.subresource,
+            ..Self::new(&self.url)
+        };
     }
 }
 
+/// Result of checking a single URL with `SpiderCrab::check_url()`
+pub type PageResult = Page;
+
 /// Helper type for the HashMap that maps Urls to Nodes in the graph
 pub type PageMap = HashMap<Url, NodeIndex>;
 
@@ -75,16 +208,204 @@ pub struct SpiderOptions {
     /// If set to `0`, then only visits the root node.
     /// Any positive value visits noes that are a distance `max_depth` away from the root node
     pub max_depth: i32,
+    /// Links that only change the query string of a page (ie. `?page=2`) lead to a page at the same depth, see `link_depth()`.
+    /// Useful for websites that paginate with a query parameter.
+    pub ignore_query_depth: bool,
     /// Scraper CSS Selector used for getting all elements we want to check
     pub element_selector: Box<Selector>,
     /// Scraper CSS Selector used for getting the <title> of a page
     pub title_selector: Box<Selector>,
+    /// Scraper CSS Selector used for getting all elements with a `srcset` attribute to validate
+    pub srcset_selector: Box<Selector>,
+    /// Scraper CSS Selector used for getting all JSON-LD structured data blocks
+    pub json_ld_selector: Box<Selector>,
     /// Name of the CSS class that marks elements to not check URLs for
     pub skip_class: CssLocalName,
+    /// Scraper CSS Selector for elements to not check URLs for.
+    /// Elements inside of a matching element are also not checked.
+    pub skip_selector: Option<Selector>,
     /// Vector of hosts (domain names and IP addresses) that Spider Crab will traverse
     pub hosts: Vec<Host<String>>,
     /// List of patterns that the user has specified to ignore
     pub ignore_patterns: HashMap<SpiderErrorType, Vec<String>>,
+    /// Treat protocol-relative URLs (ie. `//host/path`) as in-host when their host matches an entry of `hosts`.
+    /// If set to `false`, pages that are only linked with protocol-relative URLs are checked like external links, but never parsed.
+    /// Pages that any other link reaches before they are visited are still parsed.
+    pub protocol_relative_in_host: bool,
+    /// HTML pages with a body larger than this many bytes will be recorded with a `PageTooLarge` warning
+    /// If set to `None`, page size is not checked
+    pub max_page_bytes: Option<usize>,
+    /// Controls which query parameters are significant. Insignificant query parameters are stripped from discovered URLs.
+    pub query_params: QueryParamFilter,
+    /// Check that every page declared as an hreflang alternate declares the original page as an alternate in return
+    pub check_hreflang_reciprocity: bool,
+    /// Path of the site's 404 page (ie. `/404.html`). Links to this path on any of the `hosts` are recorded with a `LinkTo404Page` warning.
+    /// If set to `None`, links to the 404 page are not checked
+    pub not_found_page_path: Option<String>,
+    /// Decides if a visited page is good or bad.
+    /// Defaults to `DefaultGoodnessPolicy`, which treats pages with a 2XX HTTP status code as good.
+    pub goodness_policy: Box<dyn GoodnessPolicy>,
+    /// Check URLs found in JSON-LD structured data (ie. `image`, `url` and `logo` fields)
+    pub check_json_ld: bool,
+    /// Longest `Retry-After` delay to wait for before retrying a `503` or `429` response once.
+    /// Responses asking for a longer delay are not retried. If set to `None` (the default), responses are never retried.
+    pub max_retry_after: Option<Duration>,
+    /// Number of times to retry a request that failed to connect or received a `5XX` response, before recording an error
+    pub max_retries: u32,
+    /// Time to wait before the first retry of a failed request. The wait doubles with each retry.
+    pub retry_backoff: Duration,
+    /// Only keep the URL, status and errors of each page once it has been checked, see `Page::evict()`.
+    /// Links, page titles and the anchors of pages are discarded, which bounds memory usage on very large sites.
+    /// Reference counts, the Dot output and the checks that run once the crawl is done (ie. `check_fragments`) are not available in this mode.
+    pub memory_bounded: bool,
+    /// Check that every `<a>` element has text, or an accessible name (ie. an `aria-label`)
+    pub check_link_text: bool,
+    /// Check the links and images inside of `<noscript>` elements, as a browser with JavaScript disabled would load them.
+    /// If set to `false`, the fallback content of `<noscript>` elements is not checked.
+    pub check_noscript: bool,
+    /// Check that every id referenced by an attribute such as `aria-labelledby` belongs to an element on the same page
+    pub check_aria_references: bool,
+    /// Check that the `<html>` element of every page has a `lang` attribute with a well-formed language tag (ie. `en-US`)
+    pub check_lang: bool,
+    /// Check that the addresses of `mailto:` links are well-formed email addresses. Invalid addresses are recorded with an `InvalidURL` error.
+    /// Links with a `mailto:`, `tel:` or `javascript:` URL are never visited either way
+    pub validate_mailto: bool,
+    /// Record anchors that link to their own page with an absolute URL (ie. `https://example.com/about` on `/about`) with a `RedundantAbsoluteSelfLink` warning
+    pub check_absolute_self_links: bool,
+    /// Follow the `rel="next"` and `rel="prev"` URLs of the HTTP `Link` header, used by paginated APIs
+    pub follow_link_headers: bool,
+    /// Check the resources of pages (ie. images, scripts and stylesheets, see `Page::subresource`)
+    /// and assets (ie. images and fonts, see `url_helpers::is_asset_url()`) with a HEAD request, and never parse them.
+    /// Servers that do not support HEAD requests are sent a GET request instead.
+    pub head_for_assets: bool,
+    /// Pairs of URL patterns and the HTTP status code that URLs matching the pattern are expected to return.
+    /// A `*` in a pattern matches any number of characters. The first matching pattern is used.
+    /// Matching URLs that return the expected status code are good, even if it is not 2XX. Any other status code is an `UnexpectedStatus` error.
+    pub expected_statuses: Vec<(String, u16)>,
+    /// Pairs of URL patterns and the HTTP method that URLs matching the pattern are requested with (ie. `POST` for API endpoints).
+    /// A `*` in a pattern matches any number of characters. The first matching pattern is used. Other URLs are requested with `GET`.
+    pub request_methods: Vec<(String, Method)>,
+    /// In-host HTML, CSS and JavaScript responses larger than this many bytes that were sent uncompressed are recorded with an `UncompressedResource` warning.
+    /// If set to `None`, compression is not checked
+    pub max_uncompressed_bytes: Option<usize>,
+    /// Names of inline attributes (ie. `onclick` or `data-url`) to extract URLs from.
+    /// Extraction is a best-effort heuristic, see `url_helpers::get_urls_from_inline_attribute()`.
+    /// If empty, inline attributes are not checked
+    pub inline_url_attributes: Vec<String>,
+    /// Extra attributes to read URLs from, as the CSS selector of the elements and the name of the attribute (ie. `data-href` on `[data-href]`).
+    /// Lets framework-specific links (ie. `ng-href`) be checked like the `href` of anchors
+    pub extra_url_attributes: Vec<(Selector, String)>,
+    /// Most requests to have in flight at once. A request is in flight until the body of its response has been read.
+    /// If set to 0, the number of requests is not limited
+    pub max_concurrency: usize,
+    /// Time to wait after sending a request to a host before sending the next request to the same host.
+    /// If set to `None`, requests are sent without waiting
+    pub request_delay: Option<Duration>,
+    /// Do not visit pages that the `robots.txt` of their host disallows crawling, see `robots::get_robots_user_agent()`.
+    /// The pages that a crawl starts from are always visited, and only the `robots.txt` of the `hosts` is retrieved.
+    pub respect_robots: bool,
+    /// Record a `RobotsDisallowed` error for each link to a page that `robots.txt` disallows crawling
+    pub strict_robots: bool,
+    /// Only visit URLs on one of the `hosts` that match this regular expression.
+    /// Links to other URLs on the `hosts` are added to the page graph, but never visited.
+    /// URLs outside of the `hosts` are checked as usual. If set to `None`, all URLs are visited
+    pub url_match: Option<Regex>,
+    /// Only fetch URLs that belong to this shard, so that a crawl can be split between several machines.
+    /// Shards only find pages through the pages they fetch, so seed each shard with all pages (ie. from a sitemap).
+    /// If set to `None`, all URLs are fetched
+    pub shard: Option<Shard>,
+    /// Scheme and host (ie. `https://example.com`) that absolute links within the website should use.
+    /// Absolute links to one of the `hosts` or `stale_hosts` with a different scheme, host or port are recorded with a `WrongCanonicalHost` warning.
+    /// If set to `None`, absolute links are not checked
+    pub canonical_origin: Option<Url>,
+    /// Old hosts of the website (ie. after a domain migration). Links to these hosts are not followed,
+    /// but they are recorded with a `WrongCanonicalHost` warning when `canonical_origin` is set.
+    pub stale_hosts: Vec<Host<String>>,
+    /// Only check links outside of the `hosts` that are found on pages at most this many links away from the root page.
+    /// Pages outside of the `hosts` are only requested to check their status, they are never parsed for more links.
+    /// If set to `None`, links outside of the `hosts` are checked on every page
+    pub max_external_link_depth: Option<i32>,
+    /// Hosts outside of the website whose broken links are recorded as warnings instead of errors (ie. flaky third parties).
+    /// Links to other hosts outside of the website still fail the crawl
+    pub soft_hosts: Vec<Host<String>>,
+    /// Check links to pages outside of the `hosts` with a single request each. Their status code is recorded, but they are never parsed for more links.
+    /// If set to `false`, links outside of the `hosts` are added to the page graph, but never requested
+    pub check_external: bool,
+    /// Hosts outside of the website whose links are assumed to be good and are never requested (ie. `en.wikipedia.org`).
+    /// Useful for large, stable websites that would otherwise be requested on every crawl, or rate-limit the crawler
+    pub trusted_external_hosts: Vec<Host<String>>,
+    /// Record the render-blocking stylesheets and scripts of each page in `Page::render_blocking_resources`
+    pub record_render_blocking: bool,
+    /// Names of the files that are served for a URL of a directory (ie. `/docs/`), in order of preference.
+    /// Used by the `FileFetcher` when checking a website on the local disk.
+    pub index_files: Vec<String>,
+    /// Hosts that resolve to a fixed IP address instead of being looked up with DNS (like `/etc/hosts`).
+    /// Used when the HTTP client is built by `SpiderCrab::from_options()`.
+    pub host_overrides: HashMap<String, IpAddr>,
+    /// Heuristic: also scan the raw body of in-host pages for absolute `http(s)://` URLs and check them.
+    /// Text pages that are not HTML (ie. `text/plain` or XML) are scanned instead of being skipped.
+    /// Catches links that are dropped by the HTML parser, but may find strings that are not meant as links.
+    /// See `url_helpers::get_urls_from_text()`
+    pub lenient_html: bool,
+    /// When seeding a crawl from a sitemap, crawl the pages with the highest sitemap priority first.
+    /// Pages with a lower priority are not visited until all pages with a higher priority (and the pages they link to) were checked.
+    pub sitemap_priority_order: bool,
+    /// Record a `CaseMismatch` error for pages whose path only matches when its casing is ignored,
+    /// see `url_helpers::find_case_mismatch()`. These links break on case-sensitive servers.
+    pub check_path_case: bool,
+    /// Resolve relative URLs in the HTML of every page against this URL, instead of the URL of the page.
+    /// Useful when crawling a proxied or rewritten website whose pages were written for another base URL.
+    /// If set to `None`, relative URLs are resolved against the URL of the page
+    pub force_base: Option<Url>,
+    /// Links to URLs longer than this many characters are recorded with a `UrlTooLong` warning on the linking page.
+    /// If set to `None`, the length of URLs is not checked
+    pub max_url_length: Option<usize>,
+    /// Record the number of words in the visible text of each HTML page in `Page::word_count`
+    pub record_word_count: bool,
+    /// HTML pages with fewer words than this in their visible text are recorded with a `ThinContent` warning.
+    /// If set to `None`, the number of words is not checked
+    pub min_word_count: Option<usize>,
+    /// Send requests with HTTP/2 without negotiating it first. Only use this when every crawled server supports HTTP/2.
+    /// Used when the HTTP client is built by `SpiderCrab::from_options()`, like the connection settings below.
+    pub http2_prior_knowledge: bool,
+    /// Most idle connections to keep open to each host. If set to `None`, the `reqwest` default is used
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long idle connections are kept open. If set to `None`, the `reqwest` default is used
+    pub pool_idle_timeout: Option<Duration>,
+    /// Interval of TCP keepalive probes on open connections. If set to `None`, keepalive probes are not sent
+    pub tcp_keepalive: Option<Duration>,
+    /// Longest time to wait for a response, including reading its body. Pages whose requests time out are recorded with an `UnableToRetrieve` error.
+    /// If set to `None`, requests never time out
+    pub timeout: Option<Duration>,
+    /// Pages whose response headers take longer than this to arrive are recorded with a `SlowResponse` error.
+    /// If set to `None`, response times are not checked
+    pub max_response_time: Option<Duration>,
+    /// User agent to send requests with. If set to `None`, `fetch::DEFAULT_USER_AGENT` is sent
+    pub user_agent: Option<String>,
+    /// Extra headers sent with every request (ie. an API key, or a specific `Accept` header)
+    pub headers: HeaderMap,
+    /// User name and password to authenticate requests to the `hosts` with using HTTP Basic Auth.
+    /// Requests to other hosts are never sent the credentials
+    pub basic_auth: Option<(String, String)>,
+    /// Keep the cookies that responses set, and send them with later requests (ie. for websites that need a session cookie).
+    /// Off by default, so that every page is requested the same way no matter which pages were crawled before it
+    pub enable_cookies: bool,
+    /// URL of the proxy to send every request through (ie. `http://proxy.example.com:8080`).
+    /// If set to `None`, the proxies given by the `HTTP_PROXY` and `HTTPS_PROXY` environment variables are used
+    pub proxy: Option<String>,
+    /// Accept invalid TLS certificates (ie. self-signed certificates of a staging server).
+    /// Only use this for servers that you trust, the responses of any server can then be tampered with
+    pub accept_invalid_certs: bool,
+    /// Which redirects to follow. Redirects that are not followed are recorded with a `BlockedRedirect` error
+    pub redirect_policy: RedirectPolicy,
+    /// Record pages that redirect with a `RedirectChain` warning
+    pub report_redirects: bool,
+    /// Parse HTML pages outside of the hosts for their anchors, and check that links to them with a fragment (ie. `#section`) point to an anchor that exists.
+    /// Missing anchors are recorded with a `MissingFragment` error. The pages they link to are still not visited.
+    pub check_external_fragments: bool,
+    /// Check that links to pages on the hosts with a fragment (ie. `page.html#section`) point to an anchor that exists on the linked page.
+    /// Missing anchors are recorded with a `MissingFragment` error on the linking page.
+    pub check_fragments: bool,
 }
 
 impl SpiderOptions {
@@ -93,16 +414,85 @@ impl SpiderOptions {
         Self {
             hosts: target_urls
                 .iter()
-                .map(|s| Url::parse(s).unwrap().host().unwrap().to_owned())
+                .filter_map(|s| Url::parse(s).unwrap().host().map(|h| h.to_owned()))
                 .collect(),
             ..Default::default()
         }
     }
 
-    /// Add the host referenced by `url` to the `hosts` vector. This allows the spider crab algorithm to traverse the newly added host
+    /// Add the host referenced by `url` to the `hosts` vector. This allows the spider crab algorithm to traverse the newly added host.
+    /// Local `file://` URLs do not have a host, so nothing is added for them.
     pub fn add_host(&mut self, url: &str) {
-        self.hosts
-            .push(Url::parse(url).unwrap().host().unwrap().to_owned())
+        if let Some(host) = Url::parse(url).unwrap().host() {
+            self.hosts.push(host.to_owned())
+        }
+    }
+
+    /// Returns the HTTP status code that `url` is expected to return, if it matches any of the `expected_statuses` patterns
+    pub fn expected_status(&self, url: &Url) -> Option<u16> {
+        self.expected_statuses
+            .iter()
+            .find(|(pattern, _)| matches_url_pattern(pattern, url.as_str()))
+            .map(|(_, status)| *status)
+    }
+
+    /// Returns the HTTP method that `url` is requested with, if it matches any of the `request_methods` patterns
+    pub fn request_method(&self, url: &Url) -> Option<Method> {
+        self.request_methods
+            .iter()
+            .find(|(pattern, _)| matches_url_pattern(pattern, url.as_str()))
+            .map(|(_, method)| method.clone())
+    }
+
+    /// Returns `false` if `url` is on one of the `hosts`, but does not match `url_match`
+    pub fn is_url_matched(&self, url: &Url) -> bool {
+        match &self.url_match {
+            Some(url_match) => !check_host(&self.hosts, url) || url_match.is_match(url.as_str()),
+            None => true,
+        }
+    }
+
+    /// Returns the depth of the page at `next_url`, which is linked to from the page at `url` found at `current_depth`.
+    /// Linked pages are one deeper, unless `ignore_query_depth` is set and `next_url` only changes the query string of `url`.
+    pub fn link_depth(&self, url: &Url, next_url: &Url, current_depth: i32) -> i32 {
+        if self.ignore_query_depth && is_query_variation(url, next_url) {
+            current_depth
+        } else {
+            current_depth + 1
+        }
+    }
+
+    /// Returns `true` if the link from the page at `url`, found at `current_depth`, to `next_url` is followed within `max_depth`
+    pub fn is_within_max_depth(&self, url: &Url, next_url: &Url, current_depth: i32) -> bool {
+        self.link_depth(url, next_url, current_depth) == current_depth
+            || current_depth != self.max_depth
+    }
+
+    /// Returns `false` if `url` is outside of the `hosts`, and either `check_external` is not set or it was found on a page deeper than `max_external_link_depth`.
+    /// `depth` is the distance of the page that links to `url` from the root page.
+    pub fn is_external_link_checked(&self, url: &Url, depth: i32) -> bool {
+        if !self.check_external {
+            return check_host(&self.hosts, url);
+        }
+        match self.max_external_link_depth {
+            Some(max_depth) => depth <= max_depth || check_host(&self.hosts, url),
+            None => true,
+        }
+    }
+
+    /// Returns `true` if `url` is on one of the `trusted_external_hosts` that is not one of the `hosts`
+    pub fn is_trusted(&self, url: &Url) -> bool {
+        check_host(&self.trusted_external_hosts, url) && !check_host(&self.hosts, url)
+    }
+
+    /// Returns the severity of the errors recorded when `url` can not be retrieved or returns a bad status code.
+    /// This is `Severity::Warning` for URLs on one of the `soft_hosts` that is not one of the `hosts`.
+    pub fn retrieve_severity(&self, url: &Url) -> Severity {
+        if check_host(&self.soft_hosts, url) && !check_host(&self.hosts, url) {
+            Severity::Warning
+        } else {
+            Severity::Error
+        }
     }
 
     pub fn is_rule_enabled(&self, rule: SpiderErrorType, url: &Url) -> bool {
@@ -119,46 +509,85 @@ impl SpiderOptions {
         true
     }
 
-    pub fn read_ignore_list_from_file(&mut self, filepath: &str) {
-        let ignore_file = File::open(filepath).unwrap();
+    /// Parses the ignore file at `filepath` without applying it, see `read_ignore_list_from_file()`.
+    /// Returns the number of ignore rules in the file, or a `ParseError` for each line that could not be parsed.
+    pub fn validate_ignore_file(filepath: &str) -> Result<usize, Vec<SpiderError>> {
+        Ok(Self::parse_ignore_file(filepath)?.len())
+    }
+
+    /// Parses the ignore file at `filepath` into pairs of a rule and the URL it is ignored for.
+    /// Every line is parsed, so that all malformed lines are returned at once.
+    fn parse_ignore_file(
+        filepath: &str,
+    ) -> Result<Vec<(SpiderErrorType, String)>, Vec<SpiderError>> {
+        let parse_error = |line: Option<usize>, reason: String| SpiderError {
+            error_type: SpiderErrorType::ParseError,
+            source_page: Some(filepath.to_string()),
+            line,
+            reason: Some(reason),
+            ..SpiderError::default()
+        };
+
+        let ignore_file =
+            File::open(filepath).map_err(|e| vec![parse_error(None, e.to_string())])?;
         let reader = BufReader::new(ignore_file);
-        let mut count = 0;
+        let mut rules = Vec::<(SpiderErrorType, String)>::new();
+        let mut errors = Vec::<SpiderError>::new();
         for (line_num, line) in reader.lines().enumerate() {
-            if let Ok(line) = line {
-                let line = line.trim();
-                if line.starts_with('#') || line.is_empty() {
+            let line_num = line_num + 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    errors.push(parse_error(Some(line_num), e.to_string()));
                     continue;
                 }
-                let mut parts = line.split_whitespace();
-                let rule = parts.next().unwrap_or_else(|| {
-                    panic!(
-                        "Could not read ignore rule from line {} in the ignore file!",
-                        line_num
-                    )
-                });
-                let url = parts.next().unwrap_or_else(|| {
-                    panic!(
-                        "Could not read URL from line {} in the ignore file!",
-                        line_num
-                    )
-                });
-                let error_type = SpiderErrorType::from_str(rule).unwrap_or_else(|_| {
-                    panic!("Invalid ignore rule on line {} of ignore file!", line_num)
-                });
-
-                if !self.ignore_patterns.contains_key(&error_type) {
-                    self.ignore_patterns.insert(error_type.clone(), Vec::new());
-                }
-
-                // The get_mut().unwrap() _should_ never panic because we check to make sure that the map contains the key right before this
-                self.ignore_patterns
-                    .get_mut(&error_type)
-                    .unwrap()
-                    .push(url.to_string());
-                count += 1;
+            };
+            let line = line.trim();
+            if line.starts_with('#') || line.is_empty() {
+                continue;
             }
+            let mut parts = line.split_whitespace();
+            // Blank lines were skipped, so there is always a rule
+            let rule = parts.next().unwrap();
+            let Some(url) = parts.next() else {
+                errors.push(parse_error(
+                    Some(line_num),
+                    format!("missing URL after rule {:?}", rule),
+                ));
+                continue;
+            };
+            match SpiderErrorType::from_str(rule) {
+                Ok(error_type) => rules.push((error_type, url.to_string())),
+                Err(_) => errors.push(parse_error(
+                    Some(line_num),
+                    format!("unknown rule {:?}", rule),
+                )),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(rules)
+    }
+
+    /// Reads the ignore file at `filepath` and ignores each of its rules for its URL.
+    /// Each line holds a rule name and a URL separated by whitespace. Blank lines and lines starting with `#` are skipped.
+    /// Returns the number of ignore rules read, or a `ParseError` with the line number and reason for each line that could not be parsed.
+    /// No rules are ignored if any line could not be parsed.
+    pub fn read_ignore_list_from_file(
+        &mut self,
+        filepath: &str,
+    ) -> Result<usize, Vec<SpiderError>> {
+        let rules = Self::parse_ignore_file(filepath)?;
+        let count = rules.len();
+        for (error_type, url) in rules {
+            self.ignore_patterns
+                .entry(error_type)
+                .or_default()
+                .push(url);
         }
         info!("Parsed {} ignore rules from {}", count, filepath);
+        Ok(count)
     }
 }
 
@@ -166,24 +595,95 @@ impl Default for SpiderOptions {
     fn default() -> Self {
         Self {
             max_depth: -1,
+            ignore_query_depth: false,
             element_selector: Box::new(
-                Selector::parse("a,link,img,script").expect("Invalid selector!"),
+                Selector::parse("a,link,img,script,use").expect("Invalid selector!"),
             ),
             title_selector: Box::new(Selector::parse("title").expect("Invalid <title> selector!")),
+            srcset_selector: Box::new(
+                Selector::parse("img[srcset],source[srcset]").expect("Invalid srcset selector!"),
+            ),
+            json_ld_selector: Box::new(
+                Selector::parse("script[type=\"application/ld+json\"]")
+                    .expect("Invalid JSON-LD selector!"),
+            ),
             skip_class: CssLocalName::from("scrab-skip"),
+            skip_selector: None,
             hosts: vec![],
             ignore_patterns: HashMap::new(),
+            protocol_relative_in_host: true,
+            max_page_bytes: None,
+            query_params: QueryParamFilter::KeepAll,
+            check_hreflang_reciprocity: false,
+            not_found_page_path: None,
+            goodness_policy: Box::new(DefaultGoodnessPolicy),
+            check_json_ld: false,
+            max_retry_after: None,
+            max_retries: 0,
+            retry_backoff: Duration::from_millis(500),
+            memory_bounded: false,
+            check_link_text: false,
+            check_noscript: false,
+            check_aria_references: false,
+            check_lang: false,
+            validate_mailto: false,
+            check_absolute_self_links: false,
+            follow_link_headers: false,
+            head_for_assets: false,
+            expected_statuses: vec![],
+            request_methods: vec![],
+            max_uncompressed_bytes: None,
+            inline_url_attributes: vec![],
+            extra_url_attributes: vec![],
+            max_concurrency: 0,
+            request_delay: None,
+            respect_robots: true,
+            strict_robots: false,
+            url_match: None,
+            shard: None,
+            canonical_origin: None,
+            stale_hosts: vec![],
+            max_external_link_depth: None,
+            soft_hosts: vec![],
+            check_external: true,
+            trusted_external_hosts: vec![],
+            record_render_blocking: false,
+            index_files: vec![String::from("index.html")],
+            host_overrides: HashMap::new(),
+            lenient_html: false,
+            sitemap_priority_order: false,
+            check_path_case: false,
+            force_base: None,
+            max_url_length: None,
+            record_word_count: false,
+            min_word_count: None,
+            http2_prior_knowledge: false,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            timeout: None,
+            max_response_time: None,
+            user_agent: None,
+            headers: HeaderMap::new(),
+            basic_auth: None,
+            enable_cookies: false,
+            proxy: None,
+            accept_invalid_certs: false,
+            redirect_policy: RedirectPolicy::All,
+            report_redirects: false,
+            check_external_fragments: false,
+            check_fragments: false,
         }
     }
 }
 
-#[derive(Default)]
 pub struct SpiderCrab {
     /// Options controlling behavior of the traversal algorithm
     pub options: SpiderOptions,
 
-    /// HTTP client that requests will be sent out with
-    pub client: reqwest::Client,
+    /// Fetcher that HTTP requests will be sent out with
+    /// Defaults to a `LazyHttpFetcher`, `from_options()` uses the `HttpFetcher` built by `fetch::build_client()`
+    pub fetcher: Box<dyn Fetcher>,
 
     /// Graph of all pages discovered
     /// Not all discovered pages have been visited
@@ -193,9 +693,59 @@ pub struct SpiderCrab {
     /// HashMap of pages that have already been visited
     /// Includes pages that are visited and return an HTTP error code
     pub map: PageMap,
+
+    /// Nodes of the pages that the crawls started from, see `roots()`
+    pub root_nodes: Vec<NodeIndex>,
+
+    /// Time the last crawl started at
+    pub start_time: Option<SystemTime>,
+
+    /// Time the last crawl ended at
+    pub end_time: Option<SystemTime>,
+
+    /// Wall-clock duration of the last crawl
+    pub duration: Option<Duration>,
+
+    /// URLs listed by the sitemap that the last crawl was seeded from or checked against, see `CrawlReport::coverage()`
+    pub sitemap_urls: Vec<Url>,
+
+    /// Pauses and resumes the crawls of this `SpiderCrab`, see `pause_handle()`
+    pub pause_handle: PauseHandle,
+
+    /// Progress of the crawls is sent through this channel while they are running.
+    /// Use `subscribe()` to set this.
+    pub event_sender: Option<Sender<CrawlEvent>>,
+}
+
+impl Default for SpiderCrab {
+    fn default() -> Self {
+        Self {
+            options: SpiderOptions::default(),
+            fetcher: Box::new(LazyHttpFetcher::default()),
+            graph: PageGraph::default(),
+            map: PageMap::default(),
+            root_nodes: vec![],
+            start_time: None,
+            end_time: None,
+            duration: None,
+            sitemap_urls: vec![],
+            pause_handle: PauseHandle::default(),
+            event_sender: None,
+        }
+    }
 }
 
 impl SpiderCrab {
+    /// Create a new `SpiderCrab` struct that crawls with `options`.
+    /// The HTTP client is built from the options, so that `options.host_overrides` and the connection settings are used.
+    pub fn from_options(options: SpiderOptions) -> reqwest::Result<Self> {
+        Ok(Self {
+            fetcher: Box::new(fetch::build_client(&options)?),
+            options,
+            ..Default::default()
+        })
+    }
+
     /// Create a new `SpiderCrab` struct with the list of `domain_names` as valid domains to include while traversing links
     pub fn new(domain_names: &[&str]) -> Self {
         Self {
@@ -209,22 +759,180 @@ impl SpiderCrab {
     /// Returns `false` if errors were found.
     pub async fn visit_website(&mut self, url: &str) -> bool {
         let url = Url::parse(url).unwrap();
+        let timer = self.start_timer();
+        // Every crawl starts with a new context, so that it does not keep the state of the crawl before it
+        let context = CrawlContext::new(
+            self.fetcher.as_ref(),
+            &self.options,
+            self.pause_handle.clone(),
+            self.event_sender.clone(),
+        );
+        let map_mutex = Mutex::<&mut PageMap>::new(&mut self.map);
+        let graph_mutex = Mutex::<&mut PageGraph>::new(&mut self.graph);
+        let result = algo::visit_root_page(&url, &context, &graph_mutex, &map_mutex).await;
+        self.record_roots(&[url]);
+        let result = self.check_pages() && result;
+        self.stop_timer(timer);
+        self.send_crawl_finished(result);
+        result
+    }
+
+    /// Begins crawling from each of the pages given by `urls`
+    /// Use `options.max_depth` to control how far away from these pages the crawl goes.
+    /// Returns `true` if no errors were found.
+    /// Returns `false` if errors were found.
+    pub async fn visit_pages(&mut self, urls: &[&str]) -> bool {
+        let urls: Vec<Url> = urls.iter().map(|u| Url::parse(u).unwrap()).collect();
+        let timer = self.start_timer();
+        let context = CrawlContext::new(
+            self.fetcher.as_ref(),
+            &self.options,
+            self.pause_handle.clone(),
+            self.event_sender.clone(),
+        );
         let map_mutex = Mutex::<&mut PageMap>::new(&mut self.map);
         let graph_mutex = Mutex::<&mut PageGraph>::new(&mut self.graph);
-        algo::visit_root_page(&url, &self.client, &self.options, &graph_mutex, &map_mutex).await
+        let result = algo::visit_root_pages(&urls, &context, &graph_mutex, &map_mutex).await;
+        self.record_roots(&urls);
+        let result = self.check_pages() && result;
+        self.stop_timer(timer);
+        self.send_crawl_finished(result);
+        result
+    }
+
+    /// Returns the pages that the crawls started from (ie. the URL given to `visit_website()`), in the order they were given
+    pub fn roots(&self) -> Vec<&Page> {
+        self.root_nodes
+            .iter()
+            .map(|i| self.graph.node_weight(*i).unwrap())
+            .collect()
+    }
+
+    /// Records the pages of `urls` as roots of the crawl, unless they already are
+    fn record_roots(&mut self, urls: &[Url]) {
+        for url in urls {
+            if let Some(node_index) = self.map.get(url) {
+                if !self.root_nodes.contains(node_index) {
+                    self.root_nodes.push(*node_index);
+                }
+            }
+        }
+    }
+
+    /// Returns a channel that receives a `CrawlEvent` as each page is visited during the following crawls.
+    /// Only the latest subscriber receives events.
+    pub fn subscribe(&mut self) -> Receiver<CrawlEvent> {
+        let (sender, receiver) = channel();
+        self.event_sender = Some(sender);
+        receiver
+    }
+
+    /// Sends the `CrawlFinished` event for a crawl that finished with `result`
+    fn send_crawl_finished(&self, result: bool) {
+        let Some(sender) = &self.event_sender else {
+            return;
+        };
+        let report = self.report();
+        // Nobody listening for events is not a problem for the crawl
+        let _ = sender.send(CrawlEvent::CrawlFinished {
+            result,
+            pages: self.page_count(),
+            links: self.link_count(),
+            errors: report.error_count(),
+            warnings: report.warning_count(),
+        });
+    }
+
+    /// Records the start time of a crawl, returns the `Instant` used to measure the duration of the crawl
+    fn start_timer(&mut self) -> Instant {
+        self.start_time = Some(SystemTime::now());
+        self.end_time = None;
+        self.duration = None;
+        Instant::now()
+    }
+
+    /// Records the end time and duration of a crawl that was started at `timer`
+    fn stop_timer(&mut self, timer: Instant) {
+        self.duration = Some(timer.elapsed());
+        self.end_time = Some(SystemTime::now());
+    }
+
+    /// Returns a report summarizing the results of the last crawl
+    pub fn report(&self) -> CrawlReport<'_> {
+        CrawlReport::new(self)
+    }
+
+    /// Runs the checks that need the whole page graph, after the crawl has finished
+    /// Returns `true` if no errors were found.
+    fn check_pages(&mut self) -> bool {
+        let mut result = true;
+        if self.options.check_hreflang_reciprocity {
+            result &= algo::check_hreflang_reciprocity(&mut self.graph, &self.map, &self.options);
+        }
+        if self.options.check_fragments || self.options.check_external_fragments {
+            result &= algo::check_fragments(&mut self.graph, &self.map, &self.options);
+        }
+        result
+    }
+
+    /// Checks the single URL given by `url` without following any of the links on the page
+    /// Does not modify the page graph.
+    pub async fn check_url(&self, url: &str) -> PageResult {
+        let url = Url::parse(url).unwrap();
+        let context = CrawlContext::new(
+            self.fetcher.as_ref(),
+            &self.options,
+            self.pause_handle.clone(),
+            self.event_sender.clone(),
+        );
+        algo::check_page(&url, &context).await
+    }
+
+    /// Checks each of the URLs given by `urls` independently, without following any of the links on the pages.
+    /// URLs are checked concurrently. Results are returned in the same order as `urls`.
+    /// A URL that is given more than once is only checked once, each of its results is a copy of the same result.
+    /// Does not modify the page graph.
+    pub async fn check_urls(&self, urls: &[&str]) -> Vec<PageResult> {
+        // The URLs are checked as a single crawl, so that they share its limits
+        let context = CrawlContext::new(
+            self.fetcher.as_ref(),
+            &self.options,
+            self.pause_handle.clone(),
+            self.event_sender.clone(),
+        );
+        let urls: Vec<Url> = urls.iter().map(|u| Url::parse(u).unwrap()).collect();
+        // Concurrent checks of the same URL would share its redirect history, so each URL is only checked once
+        let mut unique_urls: Vec<&Url> = Vec::new();
+        for url in &urls {
+            if !unique_urls.contains(&url) {
+                unique_urls.push(url);
+            }
+        }
+        let results = futures::future::join_all(
+            unique_urls
+                .iter()
+                .map(|url| algo::check_page(url, &context)),
+        )
+        .await;
+        urls.iter()
+            .map(|url| {
+                let index = unique_urls.iter().position(|u| *u == url).unwrap();
+                results[index].clone()
+            })
+            .collect()
     }
 
     /// Returns the `Page` in the page map given by `url`
     pub fn get_page(&self, url: &Url) -> &Page {
         let node_id = *self.map.get(url).unwrap();
-        return self.graph.node_weight(node_id).unwrap();
+        self.graph.node_weight(node_id).unwrap()
     }
 
     /// Returns the `Page` in the page map given by `url`
     pub fn get_page_by_str(&self, url: &str) -> &Page {
         let url = Url::parse(url).unwrap();
         let node_id = *self.map.get(&url).unwrap();
-        return self.graph.node_weight(node_id).unwrap();
+        self.graph.node_weight(node_id).unwrap()
     }
 
     /// Returns `true` if the page map contains the page given by `url`
@@ -258,10 +966,90 @@ impl SpiderCrab {
         self.graph.edge_count()
     }
 
+    /// Returns the number of links in the page graph that point to the page given by `url` (ie. the in-degree of the page)
+    pub fn reference_count(&self, url: &Url) -> usize {
+        let node_id = *self.map.get(url).unwrap();
+        self.graph
+            .edges_directed(node_id, Direction::Incoming)
+            .count()
+    }
+
+    /// Returns every page in the page graph paired with its reference count.
+    /// Sorted from most referenced to least referenced, ties are sorted by URL.
+    pub fn pages_by_reference_count(&self) -> Vec<(&Page, usize)> {
+        let mut pages: Vec<(&Page, usize)> = self
+            .graph
+            .node_indices()
+            .map(|i| {
+                (
+                    self.graph.node_weight(i).unwrap(),
+                    self.graph.edges_directed(i, Direction::Incoming).count(),
+                )
+            })
+            .collect();
+        pages.sort_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| a.url.cmp(&b.url))
+        });
+        pages
+    }
+
+    /// Returns groups of pages that link to each other in a cycle (ie. strongly connected components with more than one page).
+    /// If `include_self_loops` is `true`, pages that link to themselves are also returned as a group of one page.
+    /// Pages in each group are sorted by URL, and groups are sorted by their first URL.
+    pub fn find_cycles(&self, include_self_loops: bool) -> Vec<Vec<&Page>> {
+        let mut cycles: Vec<Vec<&Page>> = tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|scc| {
+                scc.len() > 1 || (include_self_loops && self.graph.contains_edge(scc[0], scc[0]))
+            })
+            .map(|scc| {
+                let mut pages: Vec<&Page> = scc
+                    .into_iter()
+                    .map(|i| self.graph.node_weight(i).unwrap())
+                    .collect();
+                pages.sort_by(|a, b| a.url.cmp(&b.url));
+                pages
+            })
+            .collect();
+        cycles.sort_by(|a, b| a[0].url.cmp(&b[0].url));
+        cycles
+    }
+
     /// Returns an iterator over all errors found in the page graph.
     pub fn errors(&self) -> impl Iterator<Item = &SpiderError> {
         self.graph
             .node_weights()
             .flat_map(|node: &Page| node.errors.iter())
     }
+
+    /// Returns `Ok(())` if the last crawl found no errors. Warnings are allowed.
+    /// Otherwise, returns the summary of the crawl followed by every error and warning, grouped by the page they were found on.
+    /// Meant for integration tests of a website, ie. `spider_crab.assert_no_errors().unwrap()`
+    pub fn assert_no_errors(&self) -> Result<(), String> {
+        let report = self.report();
+        if report.error_count() == 0 {
+            return Ok(());
+        }
+
+        let mut pages: Vec<&Page> = self
+            .graph
+            .node_weights()
+            .filter(|p| !p.errors.is_empty())
+            .collect();
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let mut message = report.summary();
+        for page in pages {
+            message.push_str(&format!("\n{}:", page.url));
+            for error in &page.errors {
+                let severity = if error.is_warning() {
+                    "warning"
+                } else {
+                    "error"
+                };
+                message.push_str(&format!("\n  {}: {}", severity, error));
+            }
+        }
+        Err(message)
+    }
 }
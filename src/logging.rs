@@ -0,0 +1,188 @@
+//! Holds the logger used by spider crab, which writes to stderr and can optionally tee all output into a log file
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::sync::{LazyLock, Mutex};
+use stderrlog::StdErrLog;
+
+/// Logger that writes to stderr (unless quiet) and optionally to a log file
+pub struct SpiderLogger {
+    /// Logger used for writing to stderr
+    stderr: StdErrLog,
+    /// Log file that all log output is written to
+    file: Option<Mutex<LineWriter<File>>>,
+    /// Maximum level of log messages that will be written to the log file
+    level: LevelFilter,
+    /// Only log messages from this module (and its submodules) will be written to the log file
+    module: String,
+    /// How many times each warning was logged, by its message with the URLs replaced, see `dedup()`
+    repeated_warnings: Option<Mutex<BTreeMap<String, usize>>>,
+}
+
+/// Absolute URLs in log messages. Quotes, brackets and trailing punctuation around a URL are not part of it.
+static URL_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"[A-Za-z][A-Za-z0-9+.-]*://[^\s"'<>()\[\]{}]*[^\s"'<>()\[\]{}.,;:!?]"#).unwrap()
+});
+
+/// Returns `message` with every URL replaced by `<url>`, so that warnings about different pages compare equal
+fn collapse_urls(message: &str) -> String {
+    URL_PATTERN.replace_all(message, "<url>").into_owned()
+}
+
+#[test]
+fn test_collapse_urls() {
+    assert_eq!(
+        collapse_urls("Page at https://example.com/a.html does not have a title!"),
+        "Page at <url> does not have a title!"
+    );
+    assert_eq!(
+        collapse_urls("Redirect from \"https://example.com/old\" to (https://example.com/new?a=1), then http://example.org."),
+        "Redirect from \"<url>\" to (<url>), then <url>."
+    );
+    assert_eq!(
+        collapse_urls("Page https://example.com/ links to protocol-relative URL //cdn, was a relative path intended?"),
+        "Page <url> links to protocol-relative URL //cdn, was a relative path intended?"
+    );
+    assert_eq!(collapse_urls("All links good!"), "All links good!");
+}
+
+/// Converts a verbosity count into a log level, following the same convention as `stderrlog`
+fn verbosity_to_level(verbosity: usize) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Error,
+        1 => LevelFilter::Warn,
+        2 => LevelFilter::Info,
+        3 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+impl SpiderLogger {
+    /// Create a new logger that logs messages from `module` to stderr
+    /// If `quiet` is `true`, then nothing will be written to stderr
+    pub fn new(module: &str, verbosity: usize, quiet: bool) -> Self {
+        let mut stderr = stderrlog::new();
+        stderr.module(module).quiet(quiet).verbosity(verbosity);
+        Self {
+            stderr,
+            file: None,
+            level: verbosity_to_level(verbosity),
+            module: module.to_string(),
+            repeated_warnings: None,
+        }
+    }
+
+    /// Only log the first of the warnings that are the same apart from their URLs.
+    /// The number of times each of them was logged is written when the logger is flushed (ie. `Page at <url> does not have a title! (x143)`).
+    pub fn dedup(mut self) -> Self {
+        self.repeated_warnings = Some(Mutex::new(BTreeMap::new()));
+        self
+    }
+
+    /// Also write all log output into the file at `filepath`. The file is truncated if it already exists.
+    pub fn log_file(mut self, filepath: &str) -> std::io::Result<Self> {
+        let f = File::create(filepath)?;
+        self.file = Some(Mutex::new(LineWriter::new(f)));
+        Ok(self)
+    }
+
+    /// Install this logger as the global logger
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(self.level);
+        log::set_boxed_logger(Box::new(self))
+    }
+
+    /// Writes `record` to stderr and to the log file
+    fn write(&self, record: &Record) {
+        self.stderr.log(record);
+
+        if self.file_enabled(record.metadata()) {
+            let mut f = self.file.as_ref().unwrap().lock().unwrap();
+            let _ = writeln!(f, "{} - {}", record.level(), record.args());
+        }
+    }
+
+    fn file_enabled(&self, metadata: &Metadata) -> bool {
+        self.file.is_some()
+            && metadata.level() <= self.level
+            && (metadata.target() == self.module
+                || metadata
+                    .target()
+                    .starts_with(format!("{}::", self.module).as_str()))
+    }
+}
+
+impl Log for SpiderLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata) || self.file_enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(repeated_warnings) = &self.repeated_warnings {
+            if record.level() == Level::Warn && self.enabled(record.metadata()) {
+                let mut repeated_warnings = repeated_warnings.lock().unwrap();
+                let count = repeated_warnings
+                    .entry(collapse_urls(&record.args().to_string()))
+                    .or_insert(0);
+                *count += 1;
+                if *count > 1 {
+                    return;
+                }
+            }
+        }
+
+        self.write(record);
+    }
+
+    /// Also writes how many times each repeated warning was logged, when `dedup()` is used
+    fn flush(&self) {
+        if let Some(repeated_warnings) = &self.repeated_warnings {
+            let repeated_warnings = std::mem::take(&mut *repeated_warnings.lock().unwrap());
+            for (message, count) in repeated_warnings.into_iter().filter(|(_, c)| *c > 1) {
+                self.write(
+                    &Record::builder()
+                        .level(Level::Warn)
+                        .target(&self.module)
+                        .args(format_args!("{} (x{})", message, count))
+                        .build(),
+                );
+            }
+        }
+        self.stderr.flush();
+        if let Some(f) = &self.file {
+            let _ = f.lock().unwrap().flush();
+        }
+    }
+}
+
+#[test]
+fn test_dedup_warnings() {
+    let log_path = std::env::temp_dir().join("spider-crab-test-dedup.log");
+    let logger = SpiderLogger::new("spider_crab", 1, true)
+        .log_file(log_path.to_str().unwrap())
+        .expect("Failed to create log file!")
+        .dedup();
+    for i in 1..=3 {
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("spider_crab::algo")
+                .args(format_args!(
+                    "Page at https://example.com/{}.html does not have a title!",
+                    i
+                ))
+                .build(),
+        );
+    }
+    logger.flush();
+
+    let contents = std::fs::read_to_string(&log_path).expect("Failed to read log file!");
+    assert_eq!(
+        contents,
+        "WARN - Page at https://example.com/1.html does not have a title!\n\
+        WARN - Page at <url> does not have a title! (x3)\n"
+    );
+}
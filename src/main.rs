@@ -1,30 +1,179 @@
-use log::{error, info};
+use log::{error, info, warn};
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
+use clap::parser::ValueSource;
 use clap::{Arg, ArgAction, Command};
-use spider_crab::error::SpiderError;
-use spider_crab::SpiderCrab;
+use regex::Regex;
+use scraper::Selector;
+use spider_crab::config::{
+    parse_basic_auth, parse_expected_status, parse_header, parse_host_override,
+    parse_request_method, parse_url_attribute, SpiderConfig, DEFAULT_CONFIG_FILE,
+};
+use spider_crab::dot::ClusterBy;
+use spider_crab::error::{
+    exit_code_for_errors, sort_errors, SpiderError, EXIT_FAILED_CRAWL, EXIT_SUCCESS,
+};
+use spider_crab::events::write_ndjson;
+use spider_crab::fetch::{build_client, FileFetcher, RedirectPolicy};
+use spider_crab::git::ChangedFiles;
+use spider_crab::logging::SpiderLogger;
+use spider_crab::url_helpers::{QueryParamFilter, Shard};
+use spider_crab::watch::CrawlDiff;
+use spider_crab::{SpiderCrab, SpiderOptions};
+use url::{Host, Url};
 
 fn save_graph_file(
     spider_crab: &SpiderCrab,
     filename: &str,
+    cluster_by: Option<ClusterBy>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut f = File::create(filename)?;
-    f.write_all(spider_crab.get_dot_format().as_bytes())?;
+    let dot = match cluster_by {
+        Some(cluster_by) => spider_crab.get_clustered_dot_format(cluster_by),
+        None => spider_crab.get_dot_format(),
+    };
+    f.write_all(dot.as_bytes())?;
     Ok(())
 }
 
+fn save_csv_file(
+    spider_crab: &SpiderCrab,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut f = File::create(filename)?;
+    f.write_all(spider_crab.to_csv().as_bytes())?;
+    Ok(())
+}
+
+fn save_json_file(
+    spider_crab: &SpiderCrab,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut f = File::create(filename)?;
+    f.write_all(spider_crab.to_json_report().as_bytes())?;
+    Ok(())
+}
+
+/// Number of pages listed in each section of the reference report
+const REFERENCE_REPORT_LENGTH: usize = 10;
+
+/// Value of `--max-url-length` when it is given without one, same as `DEFAULT_MAX_URL_LENGTH`
+const DEFAULT_MAX_URL_LENGTH_ARG: &str = "2000";
+
+/// Value of `--check-inline-urls` when it is given without one, same as `DEFAULT_INLINE_URL_ATTRIBUTES`
+const DEFAULT_INLINE_URL_ATTRIBUTES_ARG: &str = "onclick,data-url,data-href,data-src";
+
+fn print_reference_report(spider_crab: &SpiderCrab) {
+    let pages = spider_crab.pages_by_reference_count();
+
+    println!("Most referenced pages:");
+    for (page, count) in pages.iter().take(REFERENCE_REPORT_LENGTH) {
+        println!("{:>6} {}", count, page.url);
+    }
+
+    println!("Least referenced pages:");
+    for (page, count) in pages.iter().rev().take(REFERENCE_REPORT_LENGTH) {
+        println!("{:>6} {}", count, page.url);
+    }
+}
+
+fn print_cycle_report(spider_crab: &SpiderCrab) {
+    let cycles = spider_crab.find_cycles(false);
+
+    println!("Link cycles: {}", cycles.len());
+    for (i, cycle) in cycles.iter().enumerate() {
+        println!("Cycle {} ({} pages):", i + 1, cycle.len());
+        for page in cycle {
+            println!("    {}", page.url);
+        }
+    }
+}
+
+fn print_render_blocking_report(spider_crab: &SpiderCrab) {
+    println!("Render-blocking resources:");
+    for page in spider_crab.graph.node_weights() {
+        if page.render_blocking_resources.is_empty() {
+            continue;
+        }
+        println!("{:>6} {}", page.render_blocking_resources.len(), page.url);
+        for url in &page.render_blocking_resources {
+            println!("           {}", url);
+        }
+    }
+}
+
+fn print_host_report(spider_crab: &SpiderCrab) {
+    let mut summaries: Vec<_> = spider_crab
+        .report()
+        .per_host_summary()
+        .into_iter()
+        .collect();
+    // Hosts with the most errors first
+    summaries.sort_by(|(a_host, a), (b_host, b)| {
+        b.error_count()
+            .cmp(&a.error_count())
+            .then_with(|| a_host.to_string().cmp(&b_host.to_string()))
+    });
+
+    println!("Hosts:");
+    for (host, summary) in summaries {
+        let response_time = match summary.average_response_time {
+            Some(t) => format!("{:.3} seconds", t.as_secs_f64()),
+            None => String::from("-"),
+        };
+        println!(
+            "{}: {} pages visited, {} errors, average response time {}",
+            host,
+            summary.pages_visited,
+            summary.error_count(),
+            response_time
+        );
+        let mut errors_by_type: Vec<_> = summary
+            .errors_by_type
+            .iter()
+            .map(|(error_type, count)| (format!("{:?}", error_type), count))
+            .collect();
+        errors_by_type.sort();
+        for (rule, count) in errors_by_type {
+            println!("{:>6} {}", count, rule);
+        }
+    }
+}
+
+fn print_watch_summary(spider_crab: &SpiderCrab, result: bool, diff: &CrawlDiff) {
+    for line in spider_crab.report().summary().lines() {
+        info!("{}", line);
+    }
+    for error in &diff.new_errors {
+        warn!("New: {}", error);
+    }
+    for error in &diff.fixed_errors {
+        info!("Fixed: {}", error);
+    }
+    if diff.is_empty() {
+        info!("No changes since the last crawl.");
+    }
+    if result {
+        info!("All links good!");
+    } else {
+        error!("Found a problem while crawling the target webpage!");
+    }
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
-    let matches = Command::new("Spider Crab")
+    let command = Command::new("Spider Crab")
         .about("Checks links and images in a webpage.")
         .author("Tyler Sengia")
         .arg(
             Arg::new("url")
                 .action(ArgAction::Set)
-                .required(true)
-                .help("URL of the webpage to check."),
+                .required_unless_present("check-ignore-file")
+                .help("URL of the webpage to check. Use a file:// URL to check a website on the local disk."),
         )
         .arg(
             Arg::new("depth")
@@ -35,6 +184,25 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 .value_parser(clap::value_parser!(i32))
                 .help("Depth of links to check. Default is -1 which is unlimited."),
         )
+        .arg(
+            Arg::new("ignore-query-depth")
+                .long("ignore-query-depth")
+                .action(ArgAction::SetTrue)
+                .help("Links that only change the query string of a page (ie. ?page=2) do not count towards the depth."),
+        )
+        .arg(
+            Arg::new("protocol-relative-external")
+                .long("protocol-relative-external")
+                .action(ArgAction::SetTrue)
+                .help("Only check pages that are linked with protocol-relative links (ie. //example.com/page), like external pages, instead of crawling them."),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .action(ArgAction::Set)
+                .help("Read options from this TOML file. Defaults to spidercrab.toml if it exists. Command line flags override the file."),
+        )
         .arg(
             Arg::new("quiet")
                 .short('q')
@@ -54,7 +222,568 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 .action(ArgAction::Set)
                 .help("Save output to file in graphiz Dot format."),
         )
-        .get_matches();
+        .arg(
+            Arg::new("cluster-by")
+                .long("cluster-by")
+                .action(ArgAction::Set)
+                .requires("dot")
+                .value_parser(["host", "path"])
+                .help("Group pages in the Dot output by host, or by host and first path segment."),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::Set)
+                .help("Save every page and error of the crawl to file as a JSON report."),
+        )
+        .arg(
+            Arg::new("csv")
+                .long("csv")
+                .action(ArgAction::Set)
+                .help("Save one row per error of the crawl to file in CSV format."),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .action(ArgAction::Set)
+                .value_parser(["text", "ndjson", "plain", "github", "html"])
+                .default_value("text")
+                .help("Output format. ndjson writes one JSON event per line to stdout while crawling. plain writes one sorted line per error to stdout after crawling, which can be diffed between runs. github writes a GitHub Actions ::error or ::warning workflow command per error to stdout after crawling. html writes a standalone HTML report with a sortable table of pages and the errors of each rule to stdout after crawling."),
+        )
+        .arg(
+            Arg::new("max-allowed-errors")
+                .long("max-allowed-errors")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Only fail the crawl if more than this many errors are found. Warnings are not counted."),
+        )
+        .arg(
+            Arg::new("report-references")
+                .long("report-references")
+                .action(ArgAction::SetTrue)
+                .help("Print the most and least referenced pages."),
+        )
+        .arg(
+            Arg::new("report-cycles")
+                .long("report-cycles")
+                .action(ArgAction::SetTrue)
+                .help("Print groups of pages that link to each other in a cycle."),
+        )
+        .arg(
+            Arg::new("report-render-blocking")
+                .long("report-render-blocking")
+                .action(ArgAction::SetTrue)
+                .help("Print the stylesheets and scripts that block each page from rendering."),
+        )
+        .arg(
+            Arg::new("report-by-host")
+                .long("report-by-host")
+                .action(ArgAction::SetTrue)
+                .help("Print the pages visited, errors by type and average response time of each host."),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .action(ArgAction::Set)
+                .help("Also write all log output to this file."),
+        )
+        .arg(
+            Arg::new("dedup-logs")
+                .long("dedup-logs")
+                .action(ArgAction::SetTrue)
+                .help("Only log the first of the warnings that are the same apart from their URLs, and how many there were at the end."),
+        )
+        .arg(
+            Arg::new("max-page-bytes")
+                .long("max-page-bytes")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Warn about HTML pages larger than this many bytes."),
+        )
+        .arg(
+            Arg::new("max-url-length")
+                .long("max-url-length")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(DEFAULT_MAX_URL_LENGTH_ARG)
+                .help(format!("Warn about links to URLs longer than this many characters. Defaults to {}.", DEFAULT_MAX_URL_LENGTH_ARG)),
+        )
+        .arg(
+            Arg::new("check-ignore-file")
+                .long("check-ignore-file")
+                .action(ArgAction::Set)
+                .value_name("file")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(".spidercrab-ignore")
+                .help("Check that this ignore file is valid and exit without crawling. Defaults to .spidercrab-ignore."),
+        )
+        .arg(
+            Arg::new("min-word-count")
+                .long("min-word-count")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Warn about HTML pages with fewer than this many words of visible text."),
+        )
+        .arg(
+            Arg::new("max-uncompressed-bytes")
+                .long("max-uncompressed-bytes")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Warn about HTML, CSS and JavaScript files larger than this many bytes that are not served compressed."),
+        )
+        .arg(
+            Arg::new("keep-query-param")
+                .long("keep-query-param")
+                .action(ArgAction::Append)
+                .conflicts_with("strip-query-param")
+                .help("Only follow URLs with this query parameter as distinct pages, strip all other query parameters. Can be repeated."),
+        )
+        .arg(
+            Arg::new("strip-query-param")
+                .long("strip-query-param")
+                .action(ArgAction::Append)
+                .help("Strip this query parameter from URLs before visiting them. Can be repeated."),
+        )
+        .arg(
+            Arg::new("changed-files")
+                .long("changed-files")
+                .action(ArgAction::Append)
+                .num_args(1..)
+                .help("Only check the links in these pages, given as paths relative to the URL. Implies a depth of 1 unless --depth is given."),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .action(ArgAction::Set)
+                .value_name("ref")
+                .help("Only report errors on pages changed since this git ref, or on links to those pages."),
+        )
+        .arg(
+            Arg::new("site-root")
+                .long("site-root")
+                .action(ArgAction::Set)
+                .requires("since")
+                .default_value(".")
+                .help("Directory in the git repository that the site is served from. Used with --since."),
+        )
+        .arg(
+            Arg::new("match")
+                .long("match")
+                .action(ArgAction::Set)
+                .value_name("regex")
+                .help("Only visit URLs of the website that match this regular expression. Links to other URLs of the website are not checked."),
+        )
+        .arg(
+            Arg::new("shard")
+                .long("shard")
+                .action(ArgAction::Set)
+                .value_name("index/count")
+                .help("Split the crawl into count shards, and only fetch the URLs of shard index (ie. 1/4). Seed each shard with every page, ie. with --sitemap."),
+        )
+        .arg(
+            Arg::new("canonical-origin")
+                .long("canonical-origin")
+                .action(ArgAction::Set)
+                .value_name("url")
+                .help("Warn about absolute links within the website that do not use this scheme and host (ie. https://example.com)."),
+        )
+        .arg(
+            Arg::new("stale-host")
+                .long("stale-host")
+                .action(ArgAction::Append)
+                .requires("canonical-origin")
+                .help("Old host of the website (ie. old.example.com). Links to it are warned about, but not followed. Can be repeated."),
+        )
+        .arg(
+            Arg::new("max-external-depth")
+                .long("max-external-depth")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(i32))
+                .help("Only check links to other websites on pages at most this many links away from the URL."),
+        )
+        .arg(
+            Arg::new("skip-external")
+                .long("skip-external")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("max-external-depth")
+                .help("Do not check links to other websites. By default they are checked with a single request each, without crawling them."),
+        )
+        .arg(
+            Arg::new("soft-host")
+                .long("soft-host")
+                .action(ArgAction::Append)
+                .help("Host of another website (ie. flaky.example.com) whose broken links are reported as warnings instead of errors. Can be repeated."),
+        )
+        .arg(
+            Arg::new("trusted-host")
+                .long("trusted-host")
+                .action(ArgAction::Append)
+                .help("Host of another website (ie. en.wikipedia.org) whose links are assumed to be good and are not requested. Can be repeated."),
+        )
+        .arg(
+            Arg::new("index-file")
+                .long("index-file")
+                .action(ArgAction::Append)
+                .help("Name of the file served for a directory URL of a website on the local disk (ie. index.htm). Can be repeated. Defaults to index.html."),
+        )
+        .arg(
+            Arg::new("resolve")
+                .long("resolve")
+                .action(ArgAction::Append)
+                .help("Resolve a host to a fixed IP address instead of looking it up with DNS, given as host=ip (ie. staging.example.com=127.0.0.1). Can be repeated."),
+        )
+        .arg(
+            Arg::new("sitemap")
+                .long("sitemap")
+                .action(ArgAction::SetTrue)
+                .help("URL is an XML sitemap, start crawling from each of the pages it lists."),
+        )
+        .arg(
+            Arg::new("check-sitemap")
+                .long("check-sitemap")
+                .action(ArgAction::Set)
+                .value_name("sitemap")
+                .conflicts_with("sitemap")
+                .help("After crawling, warn about pages of this XML sitemap that were not found, and crawled pages missing from it."),
+        )
+        .arg(
+            Arg::new("sitemap-priority-order")
+                .long("sitemap-priority-order")
+                .action(ArgAction::SetTrue)
+                .requires("sitemap")
+                .help("Crawl the pages with the highest sitemap priority first."),
+        )
+        .arg(
+            Arg::new("ignore-robots")
+                .long("ignore-robots")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("strict-robots")
+                .help("Visit pages even if robots.txt disallows crawling them."),
+        )
+        .arg(
+            Arg::new("strict-robots")
+                .long("strict-robots")
+                .action(ArgAction::SetTrue)
+                .help("Report links to pages that robots.txt disallows crawling as errors."),
+        )
+        .arg(
+            Arg::new("delay")
+                .long("delay")
+                .action(ArgAction::Set)
+                .value_name("ms")
+                .value_parser(clap::value_parser!(u64))
+                .help("Milliseconds to wait between requests to the same host."),
+        )
+        .arg(
+            Arg::new("user-agent")
+                .long("user-agent")
+                .action(ArgAction::Set)
+                .help("User agent to send requests with, defaults to spider-crab/<version>. Its product token (ie. MyBot for MyBot/1.0) is looked for in robots.txt."),
+        )
+        .arg(
+            Arg::new("auth")
+                .long("auth")
+                .action(ArgAction::Set)
+                .value_name("username:password")
+                .help("Authenticate requests to the website with HTTP Basic Auth. Links to other websites are not sent the credentials."),
+        )
+        .arg(
+            Arg::new("cookies")
+                .long("cookies")
+                .action(ArgAction::SetTrue)
+                .help("Keep the cookies set by responses and send them with later requests, for websites that need a session cookie."),
+        )
+        .arg(
+            Arg::new("proxy")
+                .long("proxy")
+                .action(ArgAction::Set)
+                .value_name("url")
+                .help("Send every request through this proxy (ie. http://proxy.example.com:8080). By default the HTTP_PROXY and HTTPS_PROXY environment variables are used."),
+        )
+        .arg(
+            Arg::new("insecure")
+                .long("insecure")
+                .action(ArgAction::SetTrue)
+                .help("Do not verify TLS certificates (ie. for a staging server with a self-signed certificate). Only use this for servers that you trust."),
+        )
+        .arg(
+            Arg::new("header")
+                .long("header")
+                .action(ArgAction::Append)
+                .value_name("name: value")
+                .help("Send this header with every request (ie. \"Accept: application/json\"). Can be repeated."),
+        )
+        .arg(
+            Arg::new("redirect-policy")
+                .long("redirect-policy")
+                .action(ArgAction::Set)
+                .value_parser(["all", "same-domain", "same-host"])
+                .help("Which redirects to follow, defaults to all. same-domain follows redirects within the registrable domain of the page (ie. example.com to www.example.com), same-host only follows redirects to the same host."),
+        )
+        .arg(
+            Arg::new("report-redirects")
+                .long("report-redirects")
+                .action(ArgAction::SetTrue)
+                .help("Warn about pages that redirect, so links to them can point to the final URL instead."),
+        )
+        .arg(
+            Arg::new("retries")
+                .long("retries")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u32))
+                .help("Retry requests that fail to connect or receive a 5XX response this many times before reporting an error."),
+        )
+        .arg(
+            Arg::new("max-retry-after")
+                .long("max-retry-after")
+                .action(ArgAction::Set)
+                .value_name("seconds")
+                .value_parser(clap::value_parser!(u64))
+                .help("Retry 503 and 429 responses once if their Retry-After header asks to wait at most this many seconds. By default they are not retried."),
+        )
+        .arg(
+            Arg::new("retry-backoff")
+                .long("retry-backoff")
+                .action(ArgAction::Set)
+                .value_name("ms")
+                .value_parser(clap::value_parser!(u64))
+                .help("Milliseconds to wait before the first retry of a failed request. The wait doubles with each retry. Defaults to 500."),
+        )
+        .arg(
+            Arg::new("max-concurrency")
+                .long("max-concurrency")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Most requests to have in flight at once. Use 0 for no limit, which is the default."),
+        )
+        .arg(
+            Arg::new("http2-prior-knowledge")
+                .long("http2-prior-knowledge")
+                .action(ArgAction::SetTrue)
+                .help("Send requests with HTTP/2 without negotiating it first. Only use this if every crawled server supports HTTP/2."),
+        )
+        .arg(
+            Arg::new("pool-max-idle")
+                .long("pool-max-idle")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Most idle connections to keep open to each host."),
+        )
+        .arg(
+            Arg::new("pool-idle-timeout")
+                .long("pool-idle-timeout")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .help("Close idle connections after this many seconds."),
+        )
+        .arg(
+            Arg::new("tcp-keepalive")
+                .long("tcp-keepalive")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .help("Send TCP keepalive probes on open connections every this many seconds."),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .action(ArgAction::Set)
+                .value_name("seconds")
+                .value_parser(clap::value_parser!(u64))
+                .help("Give up on a page if its response takes longer than this many seconds."),
+        )
+        .arg(
+            Arg::new("max-response-time")
+                .long("max-response-time")
+                .action(ArgAction::Set)
+                .value_name("ms")
+                .value_parser(clap::value_parser!(u64))
+                .help("Fail on pages whose response takes longer than this many milliseconds."),
+        )
+        .arg(
+            Arg::new("force-base")
+                .long("force-base")
+                .action(ArgAction::Set)
+                .help("Resolve relative links on every page against this URL, instead of the URL of the page. Useful for proxied or rewritten websites."),
+        )
+        .arg(
+            Arg::new("check-external-fragments")
+                .long("check-external-fragments")
+                .action(ArgAction::SetTrue)
+                .help("Parse pages on other websites, and check that links to them with a #fragment point to an anchor that exists."),
+        )
+        .arg(
+            Arg::new("check-fragments")
+                .long("check-fragments")
+                .action(ArgAction::SetTrue)
+                .help("Check that links to pages of the website with a #fragment point to an anchor that exists."),
+        )
+        .arg(
+            Arg::new("check-path-case")
+                .long("check-path-case")
+                .action(ArgAction::SetTrue)
+                .help("Check that the casing of each path matches the page on the server, or the file on the disk."),
+        )
+        .arg(
+            Arg::new("lenient-html")
+                .long("lenient-html")
+                .action(ArgAction::SetTrue)
+                .help("Heuristic: also scan the raw text of pages for http(s):// URLs, including text pages that are not HTML such as text/plain."),
+        )
+        .arg(
+            Arg::new("check-hreflang")
+                .long("check-hreflang")
+                .action(ArgAction::SetTrue)
+                .help("Check that hreflang alternate pages link back to each other."),
+        )
+        .arg(
+            Arg::new("skip-select")
+                .long("skip-select")
+                .action(ArgAction::Set)
+                .help("Do not check elements matching this CSS selector, or elements inside of them."),
+        )
+        .arg(
+            Arg::new("not-found-page")
+                .long("not-found-page")
+                .action(ArgAction::Set)
+                .help("Path of the site's 404 page (ie. /404.html). Warn about links directly to it."),
+        )
+        .arg(
+            Arg::new("check-json-ld")
+                .long("check-json-ld")
+                .action(ArgAction::SetTrue)
+                .help("Check URLs found in JSON-LD structured data."),
+        )
+        .arg(
+            Arg::new("check-link-text")
+                .long("check-link-text")
+                .action(ArgAction::SetTrue)
+                .help("Check that every link has text, or an accessible name such as an aria-label."),
+        )
+        .arg(
+            Arg::new("check-noscript")
+                .long("check-noscript")
+                .action(ArgAction::SetTrue)
+                .help("Check links and images inside of <noscript> elements, which are only loaded with JavaScript disabled."),
+        )
+        .arg(
+            Arg::new("check-aria-references")
+                .long("check-aria-references")
+                .action(ArgAction::SetTrue)
+                .help("Check that every id referenced by aria-labelledby, aria-describedby and similar attributes exists on the page."),
+        )
+        .arg(
+            Arg::new("check-lang")
+                .long("check-lang")
+                .action(ArgAction::SetTrue)
+                .help("Check that the <html> element of every page has a lang attribute with a valid language tag (ie. en-US)."),
+        )
+        .arg(
+            Arg::new("validate-mailto")
+                .long("validate-mailto")
+                .action(ArgAction::SetTrue)
+                .help("Check that the addresses of mailto: links are valid email addresses."),
+        )
+        .arg(
+            Arg::new("check-absolute-self-links")
+                .long("check-absolute-self-links")
+                .action(ArgAction::SetTrue)
+                .help("Warn about links to the page they are on that use an absolute URL, where a relative URL would be clearer."),
+        )
+        .arg(
+            Arg::new("check-inline-urls")
+                .long("check-inline-urls")
+                .action(ArgAction::Set)
+                .value_name("attributes")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(DEFAULT_INLINE_URL_ATTRIBUTES_ARG)
+                .help(format!("Heuristic: also check URLs found in these comma separated inline attributes, such as onclick handlers. Defaults to {}.", DEFAULT_INLINE_URL_ATTRIBUTES_ARG)),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .action(ArgAction::Set)
+                .value_name("seconds")
+                .value_parser(clap::value_parser!(u64))
+                .conflicts_with("changed-files")
+                .help("Re-crawl the website every this many seconds and print what changed. Runs until stopped."),
+        )
+        .arg(
+            Arg::new("head-for-assets")
+                .long("head-for-assets")
+                .action(ArgAction::SetTrue)
+                .help("Check the images, scripts and stylesheets that pages load, and links to images, fonts, media and other assets, with a HEAD request, without downloading them."),
+        )
+        .arg(
+            Arg::new("follow-link-headers")
+                .long("follow-link-headers")
+                .action(ArgAction::SetTrue)
+                .help("Follow the next and prev URLs of HTTP Link headers, used by paginated APIs."),
+        )
+        .arg(
+            Arg::new("expect-status")
+                .long("expect-status")
+                .action(ArgAction::Append)
+                .value_name("pattern=status")
+                .help("Expect URLs matching the pattern to return this HTTP status code (ie. */old/*=410). A * matches anything. Can be repeated."),
+        )
+        .arg(
+            Arg::new("request-method")
+                .long("request-method")
+                .action(ArgAction::Append)
+                .value_name("pattern=method")
+                .help("Request URLs matching the pattern with this HTTP method instead of GET (ie. */api/*=POST). A * matches anything. Can be repeated."),
+        )
+        .arg(
+            Arg::new("url-attribute")
+                .long("url-attribute")
+                .action(ArgAction::Append)
+                .value_name("selector=attribute")
+                .help("Also check URLs in this attribute of elements matching the CSS selector, such as framework-specific links (ie. [data-href]=data-href). Can be repeated."),
+        )
+        .arg(
+            Arg::new("memory-bounded")
+                .long("memory-bounded")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "dot",
+                    "report-references",
+                    "report-cycles",
+                    "report-render-blocking",
+                    "check-fragments",
+                    "check-external-fragments",
+                    "check-hreflang",
+                ])
+                .help("Only keep the URL, status and errors of a page once it is checked to save memory on very large sites."),
+        );
+
+    #[cfg(feature = "sqlite")]
+    let command = command.arg(
+        Arg::new("sqlite")
+            .long("sqlite")
+            .action(ArgAction::Set)
+            .help("Save pages, links and errors into this SQLite database."),
+    );
+
+    let matches = command.get_matches();
+
+    if let Some(ignore_file) = matches.get_one::<String>("check-ignore-file") {
+        match SpiderOptions::validate_ignore_file(ignore_file) {
+            Ok(count) => {
+                println!("{} is valid, it has {} ignore rules", ignore_file, count);
+                return Ok(());
+            }
+            Err(errors) => {
+                for e in errors.iter() {
+                    eprintln!("{}", e);
+                }
+                std::process::exit(errors[0].error_type.exit_code());
+            }
+        }
+    }
 
     let url_str = matches
         .get_one::<String>("url")
@@ -66,25 +795,309 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let verbose = matches.get_count("verbosity");
 
     let dot_output_file = matches.get_one::<String>("dot");
+    let json_output_file = matches.get_one::<String>("json");
+    let csv_output_file = matches.get_one::<String>("csv");
+    let cluster_by = matches
+        .get_one::<String>("cluster-by")
+        .map(|c| ClusterBy::from_str(c))
+        .transpose()?;
 
-    stderrlog::new()
-        .module(module_path!())
-        .quiet(matches.get_flag("quiet"))
-        .verbosity(verbose as usize)
-        .init()
-        .unwrap();
+    let mut logger = SpiderLogger::new(module_path!(), verbose as usize, matches.get_flag("quiet"));
+    if let Some(log_file) = matches.get_one::<String>("log-file") {
+        logger = logger.log_file(log_file)?;
+    }
+    if matches.get_flag("dedup-logs") {
+        logger = logger.dedup();
+    }
+    logger.init().unwrap();
 
     let mut spider_crab = SpiderCrab::default();
     spider_crab.options.add_host(url_str);
 
-    spider_crab.options.max_depth = depth;
+    let config_file = match matches.get_one::<String>("config") {
+        Some(config_file) => Some(config_file.as_str()),
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => Some(DEFAULT_CONFIG_FILE),
+        None => None,
+    };
+    let mut config_depth = None;
+    if let Some(config_file) = config_file {
+        info!("Reading options from {}", config_file);
+        let config = SpiderConfig::from_file(config_file)
+            .map_err(|e| format!("Failed to read config file {}: {}", config_file, e))?;
+        config
+            .apply(&mut spider_crab.options)
+            .map_err(|e| format!("Invalid config file {}: {}", config_file, e))?;
+        config_depth = config.depth;
+    }
+
+    // Command line flags override the config file
+    if matches.value_source("depth") != Some(ValueSource::DefaultValue) || config_depth.is_none() {
+        spider_crab.options.max_depth = depth;
+    }
+    if let Some(max_page_bytes) = matches.get_one::<usize>("max-page-bytes") {
+        spider_crab.options.max_page_bytes = Some(*max_page_bytes);
+    }
+    if let Some(max_url_length) = matches.get_one::<usize>("max-url-length") {
+        spider_crab.options.max_url_length = Some(*max_url_length);
+    }
+    if let Some(min_word_count) = matches.get_one::<usize>("min-word-count") {
+        spider_crab.options.min_word_count = Some(*min_word_count);
+    }
+    if let Some(max_uncompressed_bytes) = matches.get_one::<usize>("max-uncompressed-bytes") {
+        spider_crab.options.max_uncompressed_bytes = Some(*max_uncompressed_bytes);
+    }
+    if let Some(max_external_depth) = matches.get_one::<i32>("max-external-depth") {
+        spider_crab.options.max_external_link_depth = Some(*max_external_depth);
+    }
+    if matches.get_flag("skip-external") {
+        spider_crab.options.check_external = false;
+    }
+    if let Some(soft_hosts) = matches.get_many::<String>("soft-host") {
+        for soft_host in soft_hosts {
+            spider_crab.options.soft_hosts.push(Host::parse(soft_host)?);
+        }
+    }
+    if let Some(trusted_hosts) = matches.get_many::<String>("trusted-host") {
+        for trusted_host in trusted_hosts {
+            spider_crab
+                .options
+                .trusted_external_hosts
+                .push(Host::parse(trusted_host)?);
+        }
+    }
+    if matches.get_flag("report-render-blocking") {
+        spider_crab.options.record_render_blocking = true;
+    }
+    if matches.get_flag("sitemap-priority-order") {
+        spider_crab.options.sitemap_priority_order = true;
+    }
+    if let Some(force_base) = matches.get_one::<String>("force-base") {
+        spider_crab.options.force_base = Some(
+            Url::parse(force_base)
+                .map_err(|e| format!("Invalid --force-base URL {}: {}", force_base, e))?,
+        );
+    }
+    if matches.get_flag("check-external-fragments") {
+        spider_crab.options.check_external_fragments = true;
+    }
+    if matches.get_flag("check-fragments") {
+        spider_crab.options.check_fragments = true;
+    }
+    if matches.get_flag("check-path-case") {
+        spider_crab.options.check_path_case = true;
+    }
+    if matches.get_flag("lenient-html") {
+        spider_crab.options.lenient_html = true;
+    }
+    if matches.get_flag("check-hreflang") {
+        spider_crab.options.check_hreflang_reciprocity = true;
+    }
+    if matches.get_flag("check-json-ld") {
+        spider_crab.options.check_json_ld = true;
+    }
+    if matches.get_flag("memory-bounded") {
+        spider_crab.options.memory_bounded = true;
+    }
+    if matches.get_flag("check-link-text") {
+        spider_crab.options.check_link_text = true;
+    }
+    if matches.get_flag("check-noscript") {
+        spider_crab.options.check_noscript = true;
+    }
+    if matches.get_flag("check-aria-references") {
+        spider_crab.options.check_aria_references = true;
+    }
+    if matches.get_flag("check-lang") {
+        spider_crab.options.check_lang = true;
+    }
+    if matches.get_flag("validate-mailto") {
+        spider_crab.options.validate_mailto = true;
+    }
+    if matches.get_flag("check-absolute-self-links") {
+        spider_crab.options.check_absolute_self_links = true;
+    }
+    if matches.get_flag("head-for-assets") {
+        spider_crab.options.head_for_assets = true;
+    }
+    if matches.get_flag("follow-link-headers") {
+        spider_crab.options.follow_link_headers = true;
+    }
+    if let Some(attributes) = matches.get_one::<String>("check-inline-urls") {
+        spider_crab.options.inline_url_attributes = attributes
+            .split(',')
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+    }
+    if let Some(not_found_page) = matches.get_one::<String>("not-found-page") {
+        spider_crab.options.not_found_page_path = Some(not_found_page.clone());
+    }
+    if let Some(index_files) = matches.get_many::<String>("index-file") {
+        spider_crab.options.index_files = index_files.cloned().collect();
+    }
+    if matches.get_flag("ignore-query-depth") {
+        spider_crab.options.ignore_query_depth = true;
+    }
+    if matches.get_flag("protocol-relative-external") {
+        spider_crab.options.protocol_relative_in_host = false;
+    }
+    if matches.get_flag("ignore-robots") {
+        spider_crab.options.respect_robots = false;
+    }
+    if matches.get_flag("strict-robots") {
+        spider_crab.options.strict_robots = true;
+    }
+    if let Some(ms) = matches.get_one::<u64>("delay") {
+        spider_crab.options.request_delay = Some(Duration::from_millis(*ms));
+    }
+    if let Some(user_agent) = matches.get_one::<String>("user-agent") {
+        spider_crab.options.user_agent = Some(user_agent.clone());
+    }
+    if let Some(auth) = matches.get_one::<String>("auth") {
+        let basic_auth =
+            parse_basic_auth(auth).ok_or("Invalid --auth, expected username:password")?;
+        spider_crab.options.basic_auth = Some(basic_auth);
+    }
+    if matches.get_flag("cookies") {
+        spider_crab.options.enable_cookies = true;
+    }
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        spider_crab.options.proxy = Some(proxy.clone());
+    }
+    if matches.get_flag("insecure") {
+        spider_crab.options.accept_invalid_certs = true;
+    }
+    if let Some(headers) = matches.get_many::<String>("header") {
+        for h in headers {
+            let (name, value) = parse_header(h).map_err(|e| format!("Invalid --header {}", e))?;
+            spider_crab.options.headers.append(name, value);
+        }
+    }
+    if let Some(policy) = matches.get_one::<String>("redirect-policy") {
+        spider_crab.options.redirect_policy = RedirectPolicy::from_str(policy)?;
+    }
+    if matches.get_flag("report-redirects") {
+        spider_crab.options.report_redirects = true;
+    }
+    if let Some(retries) = matches.get_one::<u32>("retries") {
+        spider_crab.options.max_retries = *retries;
+    }
+    if let Some(seconds) = matches.get_one::<u64>("max-retry-after") {
+        spider_crab.options.max_retry_after = Some(Duration::from_secs(*seconds));
+    }
+    if let Some(ms) = matches.get_one::<u64>("retry-backoff") {
+        spider_crab.options.retry_backoff = Duration::from_millis(*ms);
+    }
+    if let Some(max_concurrency) = matches.get_one::<usize>("max-concurrency") {
+        spider_crab.options.max_concurrency = *max_concurrency;
+    }
+    if matches.get_flag("http2-prior-knowledge") {
+        spider_crab.options.http2_prior_knowledge = true;
+    }
+    if let Some(max_idle) = matches.get_one::<usize>("pool-max-idle") {
+        spider_crab.options.pool_max_idle_per_host = Some(*max_idle);
+    }
+    if let Some(seconds) = matches.get_one::<u64>("pool-idle-timeout") {
+        spider_crab.options.pool_idle_timeout = Some(Duration::from_secs(*seconds));
+    }
+    if let Some(seconds) = matches.get_one::<u64>("tcp-keepalive") {
+        spider_crab.options.tcp_keepalive = Some(Duration::from_secs(*seconds));
+    }
+    if let Some(seconds) = matches.get_one::<u64>("timeout") {
+        spider_crab.options.timeout = Some(Duration::from_secs(*seconds));
+    }
+    if let Some(ms) = matches.get_one::<u64>("max-response-time") {
+        spider_crab.options.max_response_time = Some(Duration::from_millis(*ms));
+    }
+    if let Some(host_overrides) = matches.get_many::<String>("resolve") {
+        for r in host_overrides {
+            let (host, ip) = parse_host_override(r)
+                .ok_or_else(|| format!("Invalid --resolve {}, expected host=ip", r))?;
+            spider_crab.options.host_overrides.insert(host, ip);
+        }
+    }
+    if Url::parse(url_str)?.scheme() == "file" {
+        // Website is on the local disk
+        spider_crab.fetcher = Box::new(FileFetcher::new(spider_crab.options.index_files.clone()));
+    } else {
+        // Host overrides, connection settings and the timeout are applied when the HTTP client is built
+        let client = build_client(&spider_crab.options)
+            .map_err(|e| format!("Failed to build the HTTP client: {}", e))?;
+        spider_crab.fetcher = Box::new(client);
+    }
+
+    if let Some(skip_select) = matches.get_one::<String>("skip-select") {
+        let selector = Selector::parse(skip_select)
+            .map_err(|e| format!("Invalid --skip-select selector {}: {:?}", skip_select, e))?;
+        spider_crab.options.skip_selector = Some(selector);
+    }
+
+    if let Some(canonical_origin) = matches.get_one::<String>("canonical-origin") {
+        spider_crab.options.canonical_origin = Some(Url::parse(canonical_origin)?);
+    }
+
+    if let Some(stale_hosts) = matches.get_many::<String>("stale-host") {
+        for stale_host in stale_hosts {
+            spider_crab
+                .options
+                .stale_hosts
+                .push(Host::parse(stale_host)?);
+        }
+    }
+
+    if let Some(url_match) = matches.get_one::<String>("match") {
+        let regex = Regex::new(url_match)
+            .map_err(|e| format!("Invalid --match regular expression {}: {}", url_match, e))?;
+        spider_crab.options.url_match = Some(regex);
+    }
+    if let Some(shard) = matches.get_one::<String>("shard") {
+        spider_crab.options.shard = Some(Shard::from_str(shard)?);
+    }
+
+    if let Some(url_attributes) = matches.get_many::<String>("url-attribute") {
+        for a in url_attributes {
+            let url_attribute = parse_url_attribute(a)
+                .map_err(|e| format!("Invalid --url-attribute {}: {}", a, e))?;
+            spider_crab.options.extra_url_attributes.push(url_attribute);
+        }
+    }
+    if let Some(expected_statuses) = matches.get_many::<String>("expect-status") {
+        for e in expected_statuses {
+            let expected_status = parse_expected_status(e)
+                .ok_or_else(|| format!("Invalid --expect-status {}, expected pattern=status", e))?;
+            spider_crab.options.expected_statuses.push(expected_status);
+        }
+    }
+
+    if let Some(request_methods) = matches.get_many::<String>("request-method") {
+        for m in request_methods {
+            let request_method = parse_request_method(m).ok_or_else(|| {
+                format!("Invalid --request-method {}, expected pattern=method", m)
+            })?;
+            spider_crab.options.request_methods.push(request_method);
+        }
+    }
+
+    if let Some(names) = matches.get_many::<String>("keep-query-param") {
+        spider_crab.options.query_params = QueryParamFilter::Allow(names.cloned().collect());
+    } else if let Some(names) = matches.get_many::<String>("strip-query-param") {
+        spider_crab.options.query_params = QueryParamFilter::Deny(names.cloned().collect());
+    }
 
     let f = File::open(".spidercrab-ignore");
     if f.is_ok() {
         info!("Found .spidercrab-ignore file! Parsing rules.");
-        spider_crab
+        if let Err(errors) = spider_crab
             .options
-            .read_ignore_list_from_file(".spidercrab-ignore");
+            .read_ignore_list_from_file(".spidercrab-ignore")
+        {
+            for e in errors.iter() {
+                error!("{}", e);
+            }
+            error!("Fix the ignore file, or check it with --check-ignore-file");
+            log::logger().flush();
+            std::process::exit(errors[0].error_type.exit_code());
+        }
     } else {
         info!("Did not find .spidercrab-ignore file.")
     }
@@ -94,42 +1107,182 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     spider_crab.graph.reserve_nodes(EXPECTED_PAGES);
     spider_crab.map.reserve(EXPECTED_PAGES);
 
-    let result = spider_crab.visit_website(url_str).await;
+    let ndjson_writer = if matches.get_one::<String>("format").unwrap() == "ndjson" {
+        let events = spider_crab.subscribe();
+        Some(std::thread::spawn(move || {
+            write_ndjson(events, std::io::stdout().lock())
+        }))
+    } else {
+        None
+    };
+
+    if let Some(seconds) = matches.get_one::<u64>("watch") {
+        spider_crab
+            .watch(
+                url_str,
+                Duration::from_secs(*seconds),
+                |spider_crab, result, diff| {
+                    print_watch_summary(spider_crab, result, diff);
+                    true
+                },
+            )
+            .await;
+        return Ok(());
+    }
+
+    let since_changes = match matches.get_one::<String>("since") {
+        Some(since) => {
+            let site_root = matches.get_one::<String>("site-root").unwrap();
+            let changed = ChangedFiles::from_git(since, site_root)?;
+            info!("Only reporting errors for pages changed since {}", since);
+            Some(changed)
+        }
+        None => None,
+    };
+
+    let mut result = if let Some(changed_files) = matches.get_many::<String>("changed-files") {
+        if matches.value_source("depth") == Some(ValueSource::DefaultValue)
+            && config_depth.is_none()
+        {
+            spider_crab.options.max_depth = 1;
+        }
+        let base_url = Url::parse(url_str)?;
+        let mut urls = Vec::<String>::new();
+        for f in changed_files {
+            urls.push(base_url.join(f)?.to_string());
+        }
+        let urls: Vec<&str> = urls.iter().map(|u| u.as_str()).collect();
+        spider_crab.visit_pages(&urls).await
+    } else if matches.get_flag("sitemap") {
+        spider_crab.visit_sitemap(url_str).await
+    } else {
+        spider_crab.visit_website(url_str).await
+    };
+
+    if let Some(sitemap_url) = matches.get_one::<String>("check-sitemap") {
+        Url::parse(sitemap_url)
+            .map_err(|e| format!("Invalid --check-sitemap URL {}: {}", sitemap_url, e))?;
+        result &= spider_crab.check_sitemap(sitemap_url).await;
+    }
+
+    if let Some(ndjson_writer) = ndjson_writer {
+        // Dropping the sender ends the event stream
+        spider_crab.event_sender = None;
+        ndjson_writer.join().unwrap()?;
+    }
+
+    for line in spider_crab.report().summary().lines() {
+        info!("{}", line);
+    }
+
+    if matches.get_flag("report-references") {
+        print_reference_report(&spider_crab);
+    }
+
+    if matches.get_flag("report-cycles") {
+        print_cycle_report(&spider_crab);
+    }
+
+    if matches.get_flag("report-render-blocking") {
+        print_render_blocking_report(&spider_crab);
+    }
+
+    if matches.get_flag("report-by-host") {
+        print_host_report(&spider_crab);
+    }
+
+    match matches.get_one::<String>("format").unwrap().as_str() {
+        "plain" => print!("{}", spider_crab.report().plain()),
+        "github" => print!("{}", spider_crab.report().github_annotations()),
+        "html" => print!("{}", spider_crab.to_html_report()),
+        _ => {}
+    }
+
+    let errors: Vec<&SpiderError> = match &since_changes {
+        Some(changed) => {
+            let mut errors = spider_crab.errors_in_changed_files(changed);
+            sort_errors(&mut errors);
+            // Errors on unchanged pages do not fail the crawl
+            result = !errors.iter().any(|e| !e.is_warning());
+            errors
+        }
+        None => spider_crab.report().sorted_errors(),
+    };
+
+    if let Some(max_allowed_errors) = matches.get_one::<usize>("max-allowed-errors") {
+        let error_count = errors.iter().filter(|e| !e.is_warning()).count();
+        if !result && error_count <= *max_allowed_errors {
+            info!(
+                "Found {} errors, which is within --max-allowed-errors {}",
+                error_count, max_allowed_errors
+            );
+            result = true;
+        }
+    }
+
+    let mut exit_code = exit_code_for_errors(errors.iter().copied());
+    if !result && exit_code == EXIT_SUCCESS {
+        exit_code = EXIT_FAILED_CRAWL;
+    }
+
+    for error in errors {
+        if error.is_warning() {
+            warn!("{} Hint: {}.", error, error.suggestion());
+        } else {
+            error!("{} Hint: {}.", error, error.suggestion());
+        }
+    }
+    // Writes the counts of the warnings collapsed by --dedup-logs
+    log::logger().flush();
 
-    info!("Discovered {} pages", spider_crab.page_count());
-    info!("Visited {} pages", spider_crab.map.len());
-    info!("Discovered {} links", spider_crab.link_count());
+    #[cfg(feature = "sqlite")]
+    if let Some(sqlite_file) = matches.get_one::<String>("sqlite") {
+        if let Err(e) = spider_crab.save_sqlite(sqlite_file) {
+            error!("Save to SQLite database {} failed!", sqlite_file);
+            error!("Error: {:?}", e);
+            if result {
+                return Err(e.into());
+            }
+        }
+    }
 
     if result {
         info!("All links good!");
-        if dot_output_file.is_some() {
-            let save_result = save_graph_file(&spider_crab, dot_output_file.unwrap());
+        if let Some(dot_output_file) = dot_output_file {
+            let save_result = save_graph_file(&spider_crab, dot_output_file, cluster_by);
             if save_result.is_err() {
                 return Err(save_result.err().unwrap());
             }
         }
+        if let Some(json_output_file) = json_output_file {
+            save_json_file(&spider_crab, json_output_file)?;
+        }
+        if let Some(csv_output_file) = csv_output_file {
+            save_csv_file(&spider_crab, csv_output_file)?;
+        }
         return Ok(());
     } else {
-        for page in spider_crab.graph.node_weights() {
-            for error in &page.errors {
-                error!("{}", error);
-            }
-        }
-
-        let e = Box::new(SpiderError {
-            error_type: spider_crab::error::SpiderErrorType::FailedCrawl,
-            ..Default::default()
-        }) as Box<dyn std::error::Error>;
-        if dot_output_file.is_some() {
-            let save_result = save_graph_file(&spider_crab, dot_output_file.unwrap());
+        if let Some(dot_output_file) = dot_output_file {
+            let save_result = save_graph_file(&spider_crab, dot_output_file, cluster_by);
             if save_result.is_err() {
-                error!(
-                    "Save to Dot output file {} failed!",
-                    dot_output_file.unwrap()
-                );
+                error!("Save to Dot output file {} failed!", dot_output_file);
                 error!("Error: {:?}", save_result.err().unwrap());
             }
         }
-        return Err(e);
+        if let Some(json_output_file) = json_output_file {
+            if let Err(e) = save_json_file(&spider_crab, json_output_file) {
+                error!("Save to JSON output file {} failed!", json_output_file);
+                error!("Error: {:?}", e);
+            }
+        }
+        if let Some(csv_output_file) = csv_output_file {
+            if let Err(e) = save_csv_file(&spider_crab, csv_output_file) {
+                error!("Save to CSV output file {} failed!", csv_output_file);
+                error!("Error: {:?}", e);
+            }
+        }
+        error!("Crawl failed with exit code {}", exit_code);
+        log::logger().flush();
+        std::process::exit(exit_code);
     }
 }
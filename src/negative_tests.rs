@@ -1,7 +1,17 @@
 //! Tests that are for the "negative case", errors, missing pages, etc.
-use crate::error::SpiderErrorType;
+use crate::error::{Severity, SpiderError, SpiderErrorType};
+use crate::fetch::{FileFetcher, RedirectPolicy};
+use crate::git::ChangedFiles;
+use crate::logging::SpiderLogger;
+use crate::test_utils::InMemoryFetcher;
 use crate::test_utils::SpiderTestPageBuilder;
 use crate::test_utils::SpiderTestServer;
+use crate::SpiderCrab;
+use crate::SpiderOptions;
+use mockito::Server;
+use scraper::Selector;
+use std::time::Duration;
+use url::{Host, Url};
 
 #[tokio::test]
 async fn test_missing_page() {
@@ -25,6 +35,9 @@ async fn test_missing_page() {
 
     // Make sure there is an HTTP Error recorded
     test_server.assert_contains_single_error_of_type(SpiderErrorType::HTTPError);
+
+    // Broken links fail with their own exit code
+    assert_eq!(test_server.spider_crab.report().exit_code(), 2);
 }
 
 #[tokio::test]
@@ -319,3 +332,1733 @@ async fn test_missing_script_and_missing_img() {
     // Make sure there is an HTTP Error recorded
     test_server.assert_contains_multiple_errors_of_type(2, SpiderErrorType::HTTPError);
 }
+
+#[tokio::test]
+async fn test_invalid_srcset() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><img src=\"image.png\" srcset=\"image.png 1x, image-2x.png 1x\" /></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_image = SpiderTestPageBuilder::default()
+        .url("/image.png")
+        .content_type(Some("image/png"))
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_image);
+
+    // Invalid srcset is only a warning, so the traversal should still succeed
+    assert!(test_server.run_test().await);
+
+    // Make sure that the page graph contains two pages
+    test_server.assert_page_count(2);
+
+    // Make sure there is one link in the page graph
+    test_server.assert_link_count(1);
+
+    // Make sure there is an InvalidSrcset warning recorded
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::InvalidSrcset);
+    assert!(test_server.spider_crab.errors().all(|e| e.is_warning()));
+}
+
+#[tokio::test]
+async fn test_log_file() {
+    let log_path = std::env::temp_dir().join("spider-crab-test-log-file.log");
+    SpiderLogger::new("spider_crab", 1, true)
+        .log_file(log_path.to_str().unwrap())
+        .expect("Failed to create log file!")
+        .init()
+        .expect("Failed to install logger!");
+
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+      .url("/")
+      .content("<!DOCTYPE html><html><title>Test Page</title><body><a>This link doesn't have an href attribute!</a></body></html>")
+      .title("Test Page")
+      .build()
+      .unwrap();
+
+    test_server.add_page(&mut test_page);
+    assert!(!test_server.run_test().await);
+    log::logger().flush();
+
+    // Make sure the error logged during the crawl was written to the log file
+    let contents = std::fs::read_to_string(&log_path).expect("Failed to read log file!");
+    assert!(
+        contents.contains("ERROR - Failed to get URL from element"),
+        "Log file does not contain the expected error! Contents:\n{}",
+        contents
+    );
+}
+
+#[tokio::test]
+async fn test_check_url_missing_page() {
+    let mut server = Server::new();
+
+    let mock = server
+        .mock("GET", "/missing.html")
+        .with_status(404)
+        .create();
+
+    let url = format!("{}/missing.html", server.url());
+    let spider_crab = SpiderCrab::new(&[server.url().as_str()]);
+
+    let result = spider_crab.check_url(url.as_str()).await;
+
+    // Make sure the HTTP request was made
+    mock.assert();
+
+    assert!(result.visited);
+    assert_eq!(result.good, Some(false));
+    assert_eq!(result.status_code.unwrap(), 404);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].error_type, SpiderErrorType::HTTPError);
+
+    // Make sure checking a URL does not modify the page graph
+    assert_eq!(spider_crab.page_count(), 0);
+}
+
+#[tokio::test]
+async fn test_page_too_large() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.max_page_bytes = Some(128);
+
+    let content = format!(
+        "<!DOCTYPE html><html><title>Test Page</title><body><p>{}</p></body></html>",
+        "Lorem ipsum ".repeat(100)
+    );
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content(content.as_str())
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    test_server.add_page(&mut test_page);
+
+    // Large pages are only a warning, so the traversal should still succeed
+    assert!(test_server.run_test().await);
+
+    // Make sure there is a PageTooLarge warning recorded with the actual size
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::PageTooLarge);
+    let error = test_server.spider_crab.errors().next().unwrap();
+    assert!(error.is_warning());
+    assert_eq!(error.size, Some(content.len()));
+}
+
+#[tokio::test]
+async fn test_missing_hreflang_alternate() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><head><title>Test Page</title><link rel=\"alternate\" hreflang=\"fr\" href=\"fr/index.html\" /></head><body></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_fr = SpiderTestPageBuilder::default()
+        .url("/fr/index.html")
+        .status_code(404)
+        .content_type(None)
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_fr);
+    assert!(!test_server.run_test().await);
+
+    // Make sure that the page graph contains two pages
+    test_server.assert_page_count(2);
+
+    // Make sure there is one link in the page graph
+    test_server.assert_link_count(1);
+
+    // Make sure there is an HTTP Error recorded
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::HTTPError);
+}
+
+#[tokio::test]
+async fn test_hreflang_mismatch() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.check_hreflang_reciprocity = true;
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><head><title>Test Page</title><link rel=\"alternate\" hreflang=\"fr\" href=\"fr/index.html\" /></head><body></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_fr = SpiderTestPageBuilder::default()
+        .url("/fr/index.html")
+        .content(
+            "<!DOCTYPE html><html><head><title>Page de test</title></head><body></body></html>",
+        )
+        .title("Page de test")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_fr);
+    assert!(!test_server.run_test().await);
+
+    // Make sure there is a HreflangMismatch error recorded
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::HreflangMismatch);
+}
+
+#[tokio::test]
+async fn test_link_to_404_page() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.not_found_page_path = Some("/404.html".to_string());
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"404.html\">Oops</a></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_404 = SpiderTestPageBuilder::default()
+        .url("/404.html")
+        .content("<!DOCTYPE html><html><title>Not Found</title><body></body></html>")
+        .title("Not Found")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_404);
+
+    // Linking to the 404 page is only a warning, so the traversal should still succeed
+    assert!(test_server.run_test().await);
+
+    // Make sure there is a LinkTo404Page warning recorded
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::LinkTo404Page);
+}
+
+/// Relative link with more `..` segments than directories above the page
+#[tokio::test]
+async fn test_path_traversal() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"../../etc/page.html\">Oops</a></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    // The link is clamped to the root of the host, so this page is still visited
+    let mut test_page_clamped = SpiderTestPageBuilder::default()
+        .url("/etc/page.html")
+        .content("<!DOCTYPE html><html><title>Clamped</title><body></body></html>")
+        .title("Clamped")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_clamped);
+
+    // Path traversal is only a warning, so the traversal should still succeed
+    assert!(test_server.run_test().await);
+
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::PathTraversal);
+}
+
+/// Broken image URL inside of JSON-LD structured data
+#[tokio::test]
+async fn test_json_ld_broken_image() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.check_json_ld = true;
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><head><title>Test Page</title><script type=\"application/ld+json\">{\"@context\": \"https://schema.org\", \"@type\": \"Article\", \"image\": \"/missing.png\"}</script></head><body></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+    let mut missing_image = SpiderTestPageBuilder::default()
+        .url("/missing.png")
+        .status_code(404)
+        .content_type(None)
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut missing_image);
+    assert!(!test_server.run_test().await);
+
+    test_server.assert_page_count(2);
+    test_server.assert_link_count(1);
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::HTTPError);
+}
+
+/// In-memory site of `pages` pages linked as a binary tree, every tenth page links to a missing page
+fn large_synthetic_site(pages: usize) -> InMemoryFetcher {
+    let mut fetcher = InMemoryFetcher::default();
+    for i in 0..pages {
+        let mut body = format!("<!DOCTYPE html><html><title>Page {}</title><body>", i);
+        for child in [2 * i + 1, 2 * i + 2].iter().filter(|c| **c < pages) {
+            body.push_str(&format!("<a href=\"/page{}.html\">Child</a>", child));
+        }
+        if i % 10 == 0 {
+            body.push_str(&format!("<a href=\"/missing{}.html\">Missing</a>", i));
+        }
+        body.push_str("</body></html>");
+        fetcher.add_page(
+            format!("https://example.com/page{}.html", i).as_str(),
+            body.leak(),
+        );
+    }
+    fetcher
+}
+
+/// Memory-bounded mode should report the same errors as a normal crawl, without keeping links or the contents of checked pages
+#[tokio::test]
+async fn test_memory_bounded_reports_all_errors() {
+    const PAGES: usize = 500;
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(large_synthetic_site(PAGES));
+    assert!(
+        !spider_crab
+            .visit_website("https://example.com/page0.html")
+            .await
+    );
+
+    let mut bounded_spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    bounded_spider_crab.fetcher = Box::new(large_synthetic_site(PAGES));
+    bounded_spider_crab.options.memory_bounded = true;
+    assert!(
+        !bounded_spider_crab
+            .visit_website("https://example.com/page0.html")
+            .await
+    );
+
+    let mut errors: Vec<String> = spider_crab.errors().map(|e| e.to_string()).collect();
+    let mut bounded_errors: Vec<String> = bounded_spider_crab
+        .errors()
+        .map(|e| e.to_string())
+        .collect();
+    errors.sort();
+    bounded_errors.sort();
+
+    assert_eq!(bounded_errors.len(), PAGES / 10);
+    assert_eq!(errors, bounded_errors);
+    assert_eq!(bounded_spider_crab.page_count(), spider_crab.page_count());
+    assert_eq!(spider_crab.link_count(), PAGES - 1 + PAGES / 10);
+    assert_eq!(bounded_spider_crab.link_count(), 0);
+    assert_eq!(bounded_spider_crab.graph.edge_count(), 0);
+
+    // Every checked page was evicted down to its URL, status and errors
+    let titled = |s: &SpiderCrab| s.graph.node_weights().filter(|p| p.title.is_some()).count();
+    assert_eq!(titled(&spider_crab), PAGES);
+    assert_eq!(titled(&bounded_spider_crab), 0);
+    for page in bounded_spider_crab.graph.node_weights() {
+        assert!(page.visited);
+        assert!(page.status_code.is_some());
+        assert!(page.content_type.is_none());
+        assert!(page.errors.capacity() == page.errors.len());
+    }
+}
+
+/// Icon-only link without an accessible name
+#[tokio::test]
+async fn test_empty_link_text() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.check_link_text = true;
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"/\"><i class=\"icon-home\"></i></a></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    test_server.add_page(&mut test_page);
+    assert!(!test_server.run_test().await);
+
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::EmptyLinkText);
+}
+
+/// Only errors on changed pages, or on links to changed pages, are reported
+#[tokio::test]
+async fn test_errors_in_changed_files() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"old.html\">Old</a><a href=\"new.html\">New</a><a href=\"moved.html\">Moved</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/old.html",
+            "<!DOCTYPE html><html><title>Old</title><body><a href=\"legacy-missing.html\">Missing</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/new.html",
+            "<!DOCTYPE html><html><title>New</title><body><a href=\"new-missing.html\">Missing</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 3);
+
+    let changed = ChangedFiles::from_paths(["new.html", "moved.html"]);
+    let mut targets: Vec<&str> = spider_crab
+        .errors_in_changed_files(&changed)
+        .iter()
+        .map(|e| e.target_page.as_deref().unwrap())
+        .collect();
+    targets.sort();
+    assert_eq!(
+        targets,
+        vec![
+            "https://example.com/moved.html",
+            "https://example.com/new-missing.html"
+        ]
+    );
+}
+
+/// Page that is expected to be gone, but still returns a 200
+#[tokio::test]
+async fn test_unexpected_status() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"old/gone.html\">Gone</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/old/gone.html",
+            "<!DOCTYPE html><html><title>Not Gone</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab
+        .options
+        .expected_statuses
+        .push(("*/old/*".to_string(), 410));
+
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::UnexpectedStatus);
+    assert_eq!(errors[0].http_error_code, Some(200));
+    assert_eq!(errors[0].expected_status_code, Some(410));
+}
+
+/// Page that links twice to the same invalid URL only records one error
+#[tokio::test]
+async fn test_duplicate_errors() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"http://[invalid\">Once</a><a href=\"http://[invalid\">Twice</a></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    test_server.add_page(&mut test_page);
+    assert!(!test_server.run_test().await);
+
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::InvalidURL);
+}
+
+/// Large HTML page that is served without compression
+#[tokio::test]
+async fn test_uncompressed_resource() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.max_uncompressed_bytes = Some(1024);
+
+    let content = format!(
+        "<!DOCTYPE html><html><title>Test Page</title><body>{}</body></html>",
+        "<p>Lorem ipsum dolor sit amet</p>".repeat(100)
+    );
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content(content.as_str())
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    test_server.add_page(&mut test_page);
+
+    // Uncompressed resources are only a warning, so the traversal should still succeed
+    assert!(test_server.run_test().await);
+
+    test_server.assert_contains_single_error_of_type(SpiderErrorType::UncompressedResource);
+}
+
+/// Broken link hidden in a `data-url` attribute is only found when inline URL checks are enabled
+#[tokio::test]
+async fn test_inline_url_attribute() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><div data-url=\"/missing.html\">Card</div><button onclick=\"location.href='ok.html'\">Go</button></body></html>",
+        )
+        .add_page(
+            "https://example.com/ok.html",
+            "<!DOCTYPE html><html><title>OK</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.page_count(), 1);
+
+    spider_crab.reset();
+    spider_crab.options.inline_url_attributes = vec!["data-url".to_string(), "onclick".to_string()];
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.page_count(), 3);
+    assert_eq!(spider_crab.link_count(), 2);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/missing.html")
+    );
+}
+
+/// Absolute internal links with the old scheme or host are warned about
+#[tokio::test]
+async fn test_wrong_canonical_host() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"http://example.com/about.html\">About</a><a href=\"https://example.com/contact.html\">Contact</a><a href=\"contact.html\">Contact</a></body></html>",
+        )
+        .add_page(
+            "http://example.com/about.html",
+            "<!DOCTYPE html><html><title>About</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/contact.html",
+            "<!DOCTYPE html><html><title>Contact</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.canonical_origin = Some(Url::parse("https://example.com").unwrap());
+
+    // Warnings do not fail the crawl
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::WrongCanonicalHost);
+    assert!(errors[0].is_warning());
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("http://example.com/about.html")
+    );
+    assert_eq!(
+        errors[0].suggestion(),
+        "link to https://example.com/about.html instead"
+    );
+}
+
+/// External links are checked on the root page, but not on pages deeper than `max_external_link_depth`.
+/// External pages are never parsed for more links.
+#[tokio::test]
+async fn test_max_external_link_depth() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"https://other.org/missing.html\">Missing</a><a href=\"https://other.org/\">Other</a><a href=\"a.html\">A</a></body></html>",
+        )
+        .add_page(
+            "https://other.org/",
+            "<!DOCTYPE html><html><title>Other</title><body><a href=\"https://other.org/also-missing.html\">Missing</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"https://other.org/deep-missing.html\">Missing</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.max_external_link_depth = Some(0);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    // Links of the external page are not added to the graph
+    assert_eq!(spider_crab.page_count(), 6);
+    assert!(!spider_crab.contains_page_by_str("https://other.org/also-missing.html"));
+
+    // The deep external link is added to the graph, but never checked
+    assert!(
+        !spider_crab
+            .get_page_by_str("https://other.org/deep-missing.html")
+            .visited
+    );
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://other.org/missing.html")
+    );
+}
+
+#[tokio::test]
+async fn test_check_urls() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a></body></html>",
+        )
+        .add_page(
+            "https://other.org/about.html",
+            "<!DOCTYPE html><html><title>About</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+
+    let results = spider_crab
+        .check_urls(&[
+            "https://example.com/missing.html",
+            "https://example.com/",
+            "https://other.org/about.html",
+        ])
+        .await;
+
+    let statuses: Vec<Option<u16>> = results
+        .iter()
+        .map(|r| r.status_code.map(|s| s.as_u16()))
+        .collect();
+    assert_eq!(statuses, vec![Some(404), Some(200), Some(200)]);
+    assert_eq!(results[0].good, Some(false));
+    assert_eq!(results[0].errors.len(), 1);
+    assert_eq!(results[0].errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(results[1].good, Some(true));
+    assert_eq!(results[2].good, Some(true));
+
+    // Links on the checked pages are not followed
+    assert_eq!(spider_crab.page_count(), 0);
+}
+
+#[tokio::test]
+async fn test_max_allowed_errors() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a><a href=\"c.html\">C</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let report = spider_crab.report();
+    assert_eq!(report.error_count(), 3);
+    assert!(!report.is_within_error_threshold(2));
+    assert!(report.is_within_error_threshold(3));
+    assert!(report.is_within_error_threshold(5));
+}
+
+/// Module scripts and stylesheets with a media query are checked like any other resource
+#[tokio::test]
+async fn test_missing_module_script() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><head><title>Root</title><link rel=\"stylesheet\" href=\"site.css\"><link rel=\"stylesheet\" media=\"print\" href=\"print.css\"><script type=\"module\" src=\"app.js\"></script><script nomodule src=\"legacy.js\"></script></head><body></body></html>",
+        )
+        .add_response("https://example.com/site.css", 200, Some("text/css"), "body {}", None)
+        .add_response("https://example.com/print.css", 200, Some("text/css"), "body {}", None)
+        .add_response(
+            "https://example.com/legacy.js",
+            200,
+            Some("text/javascript"),
+            "",
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.record_render_blocking = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    assert_eq!(spider_crab.page_count(), 5);
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/app.js")
+    );
+
+    let render_blocking: Vec<&str> = spider_crab
+        .get_page_by_str("https://example.com/")
+        .render_blocking_resources
+        .iter()
+        .map(|u| u.as_str())
+        .collect();
+    assert_eq!(
+        render_blocking,
+        vec![
+            "https://example.com/site.css",
+            "https://example.com/legacy.js"
+        ]
+    );
+}
+
+/// Broken URL in a text/plain page is only found in lenient mode
+#[tokio::test]
+async fn test_lenient_html_text_page() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"notes.txt\">Notes</a></body></html>",
+        )
+        .add_response(
+            "https://example.com/notes.txt",
+            200,
+            Some("text/plain"),
+            "Release notes are at https://example.com/missing.html.\nThe home page is https://example.com/",
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.page_count(), 2);
+
+    spider_crab.reset();
+    spider_crab.options.lenient_html = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.page_count(), 3);
+    // Root page links to notes.txt only once, notes.txt links to the missing page and back to the root page
+    assert_eq!(spider_crab.link_count(), 3);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/missing.html")
+    );
+}
+
+/// Link to /About.html works on case-insensitive file systems, but the file on the disk is about.html
+#[tokio::test]
+async fn test_case_mismatch() {
+    let root = std::env::temp_dir().join(format!("spidercrab-case-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(
+        root.join("index.html"),
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"About.html\">About</a></body></html>",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("about.html"),
+        "<!DOCTYPE html><html><title>About</title><body></body></html>",
+    )
+    .unwrap();
+
+    let root_url = Url::from_file_path(root.join("index.html")).unwrap();
+    let mut spider_crab = SpiderCrab::new(&[root_url.as_str()]);
+    spider_crab.options.check_path_case = true;
+    spider_crab.fetcher = Box::new(FileFetcher::new(spider_crab.options.index_files.clone()));
+    let result = spider_crab.visit_website(root_url.as_str()).await;
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(!result);
+    let errors: Vec<&SpiderError> = spider_crab
+        .errors()
+        .filter(|e| e.error_type == SpiderErrorType::CaseMismatch)
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some(
+            Url::from_file_path(root.join("About.html"))
+                .unwrap()
+                .as_str()
+        )
+    );
+    assert_eq!(
+        errors[0].canonical_url.as_deref(),
+        Some(
+            Url::from_file_path(root.join("about.html"))
+                .unwrap()
+                .as_str()
+        )
+    );
+}
+
+/// Link to a URL longer than the maximum URL length is warned about
+#[tokio::test]
+async fn test_url_too_long() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"short.html\">Short</a><a href=\"campaign.html?utm_source=newsletter&utm_medium=email&utm_campaign=spring\">Campaign</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/short.html",
+            "<!DOCTYPE html><html><title>Short</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/campaign.html?utm_source=newsletter&utm_medium=email&utm_campaign=spring",
+            "<!DOCTYPE html><html><title>Campaign</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.max_url_length = Some(40);
+    spider_crab.fetcher = Box::new(fetcher);
+    // Long URLs are only warned about
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::UrlTooLong);
+    assert!(errors[0].is_warning());
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/")
+    );
+    assert_eq!(errors[0].count, Some(92));
+}
+
+/// Page with almost no visible text is warned about, scripts and stylesheets are not counted as words
+#[tokio::test]
+async fn test_thin_content() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><p>Our company builds tools for checking websites.</p><a href=\"empty.html\">Coming soon</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/empty.html",
+            "<!DOCTYPE html><html><title>Empty</title><style>p { color: red; }</style><body><script>var words = 'not counted at all';</script><p>Coming soon</p></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.min_word_count = Some(5);
+    spider_crab.fetcher = Box::new(fetcher);
+    // Thin pages are only warned about
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let root = Url::parse("https://example.com/").unwrap();
+    assert_eq!(
+        spider_crab.graph[spider_crab.map[&root]].word_count,
+        Some(9)
+    );
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::ThinContent);
+    assert!(errors[0].is_warning());
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/empty.html")
+    );
+    assert_eq!(errors[0].count, Some(2));
+}
+
+/// Page without a title opts out of the title check with a meta element, other pages are still checked
+#[tokio::test]
+async fn test_skip_checks_meta() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"embed.html\">Embed</a><a href=\"untitled.html\">Untitled</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/embed.html",
+            "<!DOCTYPE html><html><head><meta name=\"spider-crab-skip-checks\" content=\"missing-title, empty-script\"></head><body><script></script></body></html>",
+        )
+        .add_page(
+            "https://example.com/untitled.html",
+            "<!DOCTYPE html><html><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::MissingTitle);
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/untitled.html")
+    );
+}
+
+/// Failure message of assert_no_errors() lists every error, grouped by page
+#[tokio::test]
+async fn test_assert_no_errors() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a><a href=\"untitled.html\">Untitled</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/untitled.html",
+            "<!DOCTYPE html><html><body><a href=\"\">Empty</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.assert_no_errors().is_ok());
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let message = spider_crab.assert_no_errors().unwrap_err();
+    assert!(message.starts_with(&spider_crab.report().summary()));
+    for error in spider_crab.errors() {
+        assert!(message.contains(&error.to_string()), "{}", message);
+    }
+
+    let lines: Vec<&str> = message.lines().collect();
+    let untitled = lines
+        .iter()
+        .position(|l| *l == "https://example.com/untitled.html:")
+        .unwrap();
+    assert!(lines[untitled + 1].contains("missing-title"));
+    assert!(lines[untitled + 2].contains("empty-attribute"));
+    assert!(lines.contains(&"https://example.com/missing.html:"));
+}
+
+/// Link into the docs of another website points at a section that does not exist
+#[tokio::test]
+async fn test_external_fragment() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"https://docs.example.org/guide.html#section\">Section</a><a href=\"https://docs.example.org/guide.html#intro\">Intro</a><a href=\"https://docs.example.org/guide.html#top\">Top</a></body></html>",
+        )
+        .add_page(
+            "https://docs.example.org/guide.html",
+            "<!DOCTYPE html><html><title>Guide</title><body><h2 id=\"intro\">Intro</h2><a href=\"https://docs.example.org/other.html\">Other</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.check_external_fragments = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    // The external page is only requested, the pages it links to are not
+    assert_eq!(spider_crab.page_count(), 2);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::MissingFragment);
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/")
+    );
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://docs.example.org/guide.html#section")
+    );
+}
+
+/// Sitemap lists a page that is not linked to, and misses a page that is
+#[tokio::test]
+async fn test_sitemap_mismatch() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"new.html\">New</a><a href=\"logo.png\">Logo</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/new.html",
+            "<!DOCTYPE html><html><title>New</title><body></body></html>",
+        )
+        .add_response(
+            "https://example.com/logo.png",
+            200,
+            Some("image/png"),
+            "",
+            None,
+        )
+        .add_response(
+            "https://example.com/sitemap.xml",
+            200,
+            Some("application/xml"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc></url>
+  <url><loc>https://example.com/orphan.html</loc></url>
+</urlset>"#,
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert!(
+        spider_crab
+            .check_sitemap("https://example.com/sitemap.xml")
+            .await
+    );
+
+    let errors = spider_crab.report().sorted_errors();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| e.error_type == SpiderErrorType::SitemapMismatch && e.is_warning()));
+
+    // Crawled page that is missing from the sitemap
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/new.html")
+    );
+    assert_eq!(errors[0].target_page, None);
+
+    // Page listed in the sitemap that was not found by crawling
+    assert_eq!(
+        errors[1].source_page.as_deref(),
+        Some("https://example.com/sitemap.xml")
+    );
+    assert_eq!(
+        errors[1].target_page.as_deref(),
+        Some("https://example.com/orphan.html")
+    );
+
+    assert!(
+        !spider_crab
+            .check_sitemap("https://example.com/missing-sitemap.xml")
+            .await
+    );
+}
+
+/// Link with non-ASCII characters is percent-encoded and followed, link with an invalid host is reported
+#[tokio::test]
+async fn test_unicode_and_invalid_urls() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"café menu.html\">Menu</a><a href=\"https://exa mple.com/\">Broken</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/caf%C3%A9%20menu.html",
+            "<!DOCTYPE html><html><title>Menu</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let menu = Url::parse("https://example.com/caf%C3%A9%20menu.html").unwrap();
+    assert_eq!(spider_crab.graph[spider_crab.map[&menu]].good, Some(true));
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::InvalidURL);
+}
+
+/// Unknown rule names in an ignore file are each reported with their line number
+#[test]
+fn test_validate_ignore_file_unknown_rule() {
+    let path =
+        std::env::temp_dir().join(format!("spidercrab-ignore-invalid-{}", std::process::id()));
+    std::fs::write(
+        &path,
+        "# Typos on lines 3 and 4\nmissing-title https://example.com/old.html\nmissing-titel https://example.com/new.html\nhttp-eror https://example.org/\n",
+    )
+    .unwrap();
+
+    let errors = SpiderOptions::validate_ignore_file(path.to_str().unwrap()).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|error| error.error_type == SpiderErrorType::ParseError));
+    assert_eq!(errors[0].line, Some(3));
+    assert_eq!(
+        errors[0].reason.as_deref(),
+        Some("unknown rule \"missing-titel\"")
+    );
+    assert_eq!(errors[1].line, Some(4));
+    assert_eq!(
+        errors[1].reason.as_deref(),
+        Some("unknown rule \"http-eror\"")
+    );
+}
+
+/// Missing ignore file is reported instead of panicking
+#[test]
+fn test_read_missing_ignore_file() {
+    let mut options = SpiderOptions::default();
+    let errors = options
+        .read_ignore_list_from_file("no-such-dir/.spidercrab-ignore")
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::ParseError);
+    assert_eq!(errors[0].line, None);
+    assert!(options.ignore_patterns.is_empty());
+}
+
+/// Lines without a URL are each reported with their line number, and none of the rules are ignored
+#[test]
+fn test_read_malformed_ignore_file() {
+    let path = std::env::temp_dir().join(format!(
+        "spidercrab-ignore-malformed-{}",
+        std::process::id()
+    ));
+    std::fs::write(
+        &path,
+        "missing-title https://example.com/old.html\n\n# No URL on lines 4 and 5\nhttp-error\nmissing-title\n",
+    )
+    .unwrap();
+
+    let mut options = SpiderOptions::default();
+    let errors = options
+        .read_ignore_list_from_file(path.to_str().unwrap())
+        .unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].line, Some(4));
+    assert_eq!(
+        errors[0].reason.as_deref(),
+        Some("missing URL after rule \"http-error\"")
+    );
+    assert_eq!(errors[1].line, Some(5));
+    assert_eq!(
+        errors[1].reason.as_deref(),
+        Some("missing URL after rule \"missing-title\"")
+    );
+    assert!(options.ignore_patterns.is_empty());
+}
+
+/// Dangling aria-labelledby reference is reported, a valid one is not
+#[tokio::test]
+async fn test_broken_aria_reference() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><h2 id=\"signup\">Sign up</h2><form aria-labelledby=\"signup\"></form><dialog aria-labelledby=\"dialog-title\"><p>Saved</p></dialog></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    // References are only checked when asked to
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.check_aria_references = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::BrokenAriaReference);
+    assert_eq!(errors[0].attribute.as_deref(), Some("aria-labelledby"));
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/#dialog-title")
+    );
+}
+
+/// Links to pages disallowed by robots.txt are errors in strict mode
+#[tokio::test]
+async fn test_strict_robots() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_response(
+            "https://example.com/robots.txt",
+            200,
+            Some("text/plain"),
+            "User-agent: spider-crab\nDisallow: /drafts/\n",
+            None,
+        )
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"drafts/post.html\">Post</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.strict_robots = true;
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::RobotsDisallowed);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/drafts/post.html")
+    );
+}
+
+/// Pages whose server does not respond in time are recorded as unable to be retrieved
+#[tokio::test]
+async fn test_request_timeout() {
+    // Accepts connections but never responds to them
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+
+    let options = SpiderOptions {
+        timeout: Some(Duration::from_millis(200)),
+        ..SpiderOptions::new(&[&url])
+    };
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(!spider_crab.visit_website(&url).await);
+
+    let page = spider_crab.get_page_by_str(&url);
+    assert_eq!(page.good, Some(false));
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::UnableToRetrieve);
+    assert_eq!(errors[0].reason.as_deref(), Some("the request timed out"));
+    assert!(errors[0].to_string().contains("timed out"));
+}
+
+/// Every error is written as a GitHub Actions `::error` workflow command
+#[tokio::test]
+async fn test_github_annotations() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"blog/\">Blog</a><a href=\"missing.html\">Missing</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/blog/",
+            "<!DOCTYPE html><html><title>Blog</title><body><a href=\"gone.html\">Gone</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let annotations = spider_crab.report().github_annotations();
+    let lines: Vec<&str> = annotations.lines().collect();
+    assert_eq!(lines.len(), spider_crab.errors().count());
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|l| l.starts_with("::error ")));
+    assert!(lines[0].starts_with("::error file=blog/gone.html,title=http-error::"));
+    assert!(lines[1].starts_with("::error file=missing.html,title=http-error::"));
+}
+
+/// Redirects to a different registrable domain are not followed with the same-domain redirect policy
+#[tokio::test]
+async fn test_redirect_other_domain() {
+    let mut server = Server::new_async().await;
+    let port = server.socket_address().port();
+    let url = format!("http://spidercrab.invalid:{}/", port);
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"moved.html\">Moved</a></body></html>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/moved.html")
+        .with_status(301)
+        .with_header(
+            "location",
+            &format!("http://other.invalid:{}/new.html", port),
+        )
+        .create_async()
+        .await;
+    let new_mock = server
+        .mock("GET", "/new.html")
+        .with_status(200)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.redirect_policy = RedirectPolicy::SameDomain;
+    for host in ["spidercrab.invalid", "other.invalid"] {
+        options
+            .host_overrides
+            .insert(String::from(host), server.socket_address().ip());
+    }
+
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(!spider_crab.visit_website(url.as_str()).await);
+    new_mock.assert_async().await;
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::BlockedRedirect);
+    assert_eq!(errors[0].http_error_code, Some(301));
+    assert_eq!(
+        errors[0].target_page,
+        Some(format!("http://other.invalid:{}/new.html", port))
+    );
+}
+
+/// URLs in configured attributes of custom components are checked like links
+#[tokio::test]
+async fn test_extra_url_attribute() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><nav-item data-href=\"missing.html\">Missing</nav-item></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    // The attribute is only read when it is configured
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.extra_url_attributes.push((
+        Selector::parse("nav-item[data-href]").unwrap(),
+        String::from("data-href"),
+    ));
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert!(!spider_crab.is_page_good_by_str("https://example.com/missing.html"));
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(errors[0].http_error_code, Some(404));
+}
+
+/// The JSON report lists every page and the broken links of the crawl
+#[tokio::test]
+async fn test_json_report() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let report: serde_json::Value = serde_json::from_str(&spider_crab.to_json_report()).unwrap();
+    let pages = report["pages"].as_array().unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[0]["url"], "https://example.com/");
+    assert_eq!(pages[0]["status_code"], 200);
+    assert_eq!(pages[0]["good"], true);
+    assert_eq!(pages[0]["visited"], true);
+    assert!(pages[0].get("last_modified").is_none());
+    assert!(pages[0].get("etag").is_none());
+    assert_eq!(pages[1]["url"], "https://example.com/missing.html");
+    assert_eq!(pages[1]["status_code"], 404);
+
+    let errors = report["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["type"], "http-error");
+    assert_eq!(errors[0]["target_page"], "https://example.com/missing.html");
+    assert_eq!(errors[0]["http_error_code"], 404);
+    assert_eq!(errors[0]["severity"], "error");
+}
+
+/// The CSV output has one row per broken link
+#[tokio::test]
+async fn test_csv_output() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a><a>No href</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let csv = spider_crab.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], crate::csv::CSV_HEADER);
+    assert!(lines.contains(&",https://example.com/missing.html,http-error,404,"));
+    assert!(lines.contains(&"https://example.com/,,missing-attribute,,href"));
+}
+
+/// Pages that take longer than the maximum response time to respond are recorded as slow
+#[tokio::test]
+async fn test_slow_response() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"slow.html\">Slow</a></body></html>",
+        )
+        .add_response(
+            "https://example.com/slow.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>Slow</title><body></body></html>",
+            Some(Duration::from_millis(300)),
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.max_response_time = Some(Duration::from_millis(100));
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::SlowResponse);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/slow.html")
+    );
+    assert!(errors[0].duration_ms.unwrap() >= 300);
+    assert_eq!(errors[0].size, None);
+}
+
+/// Links to pages of the website with a fragment that names no anchor are recorded once fragments are checked
+#[tokio::test]
+async fn test_missing_fragment() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"guide.html#section-3\">Section 3</a><a href=\"#nowhere\">Nowhere</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/guide.html",
+            "<!DOCTYPE html><html><title>Guide</title><body><h2 id=\"section-1\">Section 1</h2></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.check_fragments = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let mut targets: Vec<&str> = spider_crab
+        .errors()
+        .inspect(|e| {
+            assert_eq!(e.error_type, SpiderErrorType::MissingFragment);
+            assert_eq!(e.source_page.as_deref(), Some("https://example.com/"));
+        })
+        .map(|e| e.target_page.as_deref().unwrap())
+        .collect();
+    targets.sort();
+    assert_eq!(
+        targets,
+        vec![
+            "https://example.com/#nowhere",
+            "https://example.com/guide.html#section-3"
+        ]
+    );
+}
+
+/// Links to other websites are checked with one request each, and their pages are not crawled
+#[tokio::test]
+async fn test_external_link_checked() {
+    let mut server = Server::new_async().await;
+    let port = server.socket_address().port();
+    let url = format!("http://spidercrab.invalid:{}/", port);
+    server
+        .mock("GET", "/")
+        .match_header("host", format!("spidercrab.invalid:{}", port).as_str())
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(format!(
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"http://other.invalid:{0}/missing\">Missing</a><a href=\"http://other.invalid:{0}/guide\">Guide</a></body></html>",
+            port
+        ))
+        .create_async()
+        .await;
+    let missing_mock = server
+        .mock("GET", "/missing")
+        .with_status(404)
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/guide")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Guide</title><body><a href=\"deep\">Deep</a></body></html>")
+        .create_async()
+        .await;
+    let deep_mock = server
+        .mock("GET", "/deep")
+        .with_status(200)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.check_external = false;
+    for host in ["spidercrab.invalid", "other.invalid"] {
+        options
+            .host_overrides
+            .insert(String::from(host), server.socket_address().ip());
+    }
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    // Skipped external links are in the page graph, but are never requested
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    let missing_url = format!("http://other.invalid:{}/missing", port);
+    assert!(!spider_crab.get_page_by_str(&missing_url).visited);
+
+    spider_crab.reset();
+    spider_crab.options.check_external = true;
+    assert!(!spider_crab.visit_website(url.as_str()).await);
+    missing_mock.assert_async().await;
+    deep_mock.assert_async().await;
+
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(errors[0].http_error_code, Some(404));
+    assert_eq!(errors[0].target_page, Some(missing_url));
+
+    let guide = spider_crab.get_page_by_str(&format!("http://other.invalid:{}/guide", port));
+    assert_eq!(guide.status_code, Some(reqwest::StatusCode::OK));
+    assert!(!spider_crab.contains_page_by_str(&format!("http://other.invalid:{}/deep", port)));
+}
+
+/// Pages without a lang attribute on the html element are recorded when languages are checked
+#[tokio::test]
+async fn test_missing_lang() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.check_lang = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::MissingLang);
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/")
+    );
+}
+
+/// Pages whose lang attribute is not a language tag are recorded when languages are checked
+#[tokio::test]
+async fn test_invalid_lang() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html lang=\"english\"><title>Root</title><body></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.check_lang = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::InvalidLang);
+    assert_eq!(errors[0].html.as_deref(), Some("<html lang=\"english\">"));
+}
+
+/// Invalid email addresses in mailto: links are recorded when they are validated
+#[tokio::test]
+async fn test_invalid_mailto() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"mailto:foo@bar\">Email</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    spider_crab.reset();
+    spider_crab.options.validate_mailto = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    let errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, SpiderErrorType::InvalidURL);
+    assert_eq!(errors[0].target_page.as_deref(), Some("mailto:foo@bar"));
+    assert_eq!(spider_crab.page_count(), 1);
+}
+
+/// The HTML report has a row per page and a section per rule with errors
+#[tokio::test]
+async fn test_html_report() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"about.html\">About</a><a href=\"missing.html\">Missing</a><img src=\"\"></body></html>",
+        )
+        .add_page(
+            "https://example.com/about.html",
+            "<!DOCTYPE html><html><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let html = spider_crab.to_html_report();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert_eq!(
+        html.matches("<tr><td>").count() + html.matches("<tr class=\"bad\">").count(),
+        3
+    );
+    for url in [
+        "https://example.com/",
+        "https://example.com/about.html",
+        "https://example.com/missing.html",
+    ] {
+        assert!(html.contains(&format!("<td><a href=\"{0}\">{0}</a></td>", url)));
+    }
+    for rule in ["empty-attribute", "http-error", "missing-title"] {
+        assert!(html.contains(&format!("<section class=\"rule\" id=\"{}\">", rule)));
+    }
+    assert_eq!(html.matches("<section ").count(), 3);
+    assert!(html.contains("<tr class=\"bad\"><td><a href=\"https://example.com/missing.html\">"));
+}
+
+/// Broken links to soft hosts are warnings, broken links to other hosts are still errors
+#[tokio::test]
+async fn test_soft_host() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"https://flaky.example.org/missing\">Soft</a><a href=\"https://example.net/missing\">Hard</a></body></html>",
+        )
+        .add_response("https://flaky.example.org/missing", 404, None, "", None)
+        .add_response("https://example.net/missing", 404, None, "", None);
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab
+        .options
+        .soft_hosts
+        .push(Host::parse("flaky.example.org").unwrap());
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let mut errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    errors.sort_by_key(|e| e.target_page.clone());
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.net/missing")
+    );
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(errors[0].severity, Severity::Error);
+    assert_eq!(
+        errors[1].target_page.as_deref(),
+        Some("https://flaky.example.org/missing")
+    );
+    assert_eq!(errors[1].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(errors[1].severity, Severity::Warning);
+}
+
+/// SVG `<use>` elements that point at a missing sprite or a missing symbol of a sprite are recorded
+#[tokio::test]
+async fn test_broken_svg_use() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><svg><use href=\"missing.svg#home\"></use></svg><svg><use xlink:href=\"sprite.svg#search\"></use></svg><svg><use href=\"sprite.svg#home\"></use></svg></body></html>",
+        )
+        .add_response(
+            "https://example.com/sprite.svg",
+            200,
+            Some("image/svg+xml"),
+            "<?xml version=\"1.0\"?><svg xmlns=\"http://www.w3.org/2000/svg\"><symbol id=\"home\"><path d=\"M0 0\"/></symbol></svg>",
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.check_fragments = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let mut errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    errors.sort_by_key(|e| e.target_page.clone());
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].error_type, SpiderErrorType::HTTPError);
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/missing.svg")
+    );
+    assert_eq!(errors[1].error_type, SpiderErrorType::MissingFragment);
+    assert_eq!(
+        errors[1].target_page.as_deref(),
+        Some("https://example.com/sprite.svg#search")
+    );
+}
+
+/// Broken links and images inside of <noscript> are reported when `check_noscript` is set, unless the <noscript> is skipped
+#[tokio::test]
+async fn test_check_noscript() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><head><title>Root</title><noscript><link rel=\"stylesheet\" href=\"missing.css\"></noscript></head><body><noscript><img src=\"missing.png\" alt=\"Tracking pixel\"></noscript><noscript class=\"scrab-skip\"><a href=\"skipped.html\">Skipped</a></noscript></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.check_noscript = true;
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let mut errors: Vec<&SpiderError> = spider_crab.errors().collect();
+    errors.sort_by_key(|e| e.target_page.clone());
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| e.error_type == SpiderErrorType::HTTPError));
+    assert_eq!(
+        errors[0].target_page.as_deref(),
+        Some("https://example.com/missing.css")
+    );
+    assert_eq!(
+        errors[1].target_page.as_deref(),
+        Some("https://example.com/missing.png")
+    );
+    assert!(!spider_crab.contains_page_by_str("https://example.com/skipped.html"));
+}
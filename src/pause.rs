@@ -0,0 +1,67 @@
+//! Holds the `PauseHandle` used to pause and resume a running crawl
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+use crate::SpiderCrab;
+
+#[derive(Debug, Default)]
+struct PauseState {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+/// Shared flag that pauses a crawl. Clones of a handle control the same crawl.
+/// While paused, no new requests are sent, but requests that were already sent are completed.
+#[derive(Debug, Default, Clone)]
+pub struct PauseHandle {
+    state: Arc<PauseState>,
+}
+
+impl PauseHandle {
+    /// Stops new requests from being sent until `resume()` is called
+    pub fn pause(&self) {
+        self.state.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets the crawl send requests again
+    pub fn resume(&self) {
+        self.state.paused.store(false, Ordering::SeqCst);
+        self.state.resumed.notify_waiters();
+    }
+
+    /// Returns `true` if the crawl is paused
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the crawl is resumed. Returns immediately if the crawl is not paused.
+    pub async fn wait_while_paused(&self) {
+        loop {
+            // Create the future before checking the flag, so that a resume in between is not missed
+            let resumed = self.state.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+}
+
+impl SpiderCrab {
+    /// Returns a handle that can pause and resume crawls of this `SpiderCrab` while they are running
+    pub fn pause_handle(&self) -> PauseHandle {
+        self.pause_handle.clone()
+    }
+
+    /// Pauses the crawl, see `PauseHandle::pause()`
+    pub fn pause(&self) {
+        self.pause_handle.pause();
+    }
+
+    /// Resumes the crawl, see `PauseHandle::resume()`
+    pub fn resume(&self) {
+        self.pause_handle.resume();
+    }
+}
@@ -0,0 +1,30 @@
+//! Holds the `GoodnessPolicy` trait that decides if a visited page is "good",
+//! and the default policy
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use url::Url;
+
+/// Decides if a visited page is good or bad.
+/// The traversal algorithm uses the result to set `Page::good`.
+pub trait GoodnessPolicy: Send + Sync {
+    /// Returns `true` if the response for `url` is good, based on its HTTP status code and headers.
+    /// Pages with a bad response are not parsed.
+    fn is_good_response(&self, url: &Url, status: StatusCode, headers: &HeaderMap) -> bool;
+
+    /// Returns `true` if the contents of the HTML page at `url` are good.
+    /// Only called for pages that are parsed. By default every page is good.
+    fn is_good_content(&self, _url: &Url, _contents: &str) -> bool {
+        true
+    }
+}
+
+/// Default policy: a page is good if a 2XX HTTP status code was returned
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultGoodnessPolicy;
+
+impl GoodnessPolicy for DefaultGoodnessPolicy {
+    fn is_good_response(&self, _url: &Url, status: StatusCode, _headers: &HeaderMap) -> bool {
+        status.is_success()
+    }
+}
@@ -1,11 +1,32 @@
-///! Tests that are for the "positive" case (ie. no errors, normal execution)
+//! Tests that are for the "positive" case (ie. no errors, normal execution)
+use futures::future::BoxFuture;
 use mockito::Server;
-use url::Url;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use scraper::Selector;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::{Host, Url};
 
+use crate::dot::ClusterBy;
+use crate::error::SpiderErrorType;
+use crate::events::write_ndjson;
+use crate::fetch::{
+    FetchError, FetchRequest, FetchedResponse, Fetcher, FileFetcher, RedirectPolicy,
+    DEFAULT_USER_AGENT,
+};
+use crate::pause::PauseHandle;
+use crate::policy::GoodnessPolicy;
+use crate::test_utils::InMemoryFetcher;
 use crate::test_utils::SpiderTestPageBuilder;
 use crate::test_utils::SpiderTestServer;
+use crate::url_helpers::Shard;
+use crate::watch::CrawlDiff;
 use crate::Page;
 use crate::SpiderCrab;
+use crate::SpiderOptions;
 
 /// Single page, reference to example.com
 #[tokio::test]
@@ -133,3 +154,2388 @@ async fn test_skip_link_class() {
     // Links with the skip class will be excluded from the page graph
     test_server.assert_link_count(0);
 }
+
+/// Protocol-relative link to a page on the same host should be traversed
+#[tokio::test]
+async fn test_protocol_relative_in_host() {
+    let mut test_server = SpiderTestServer::default();
+
+    let host = Url::parse(test_server.url().as_str()).unwrap();
+    let content = format!(
+        "<!DOCTYPE html><html><title>Test Page</title><body><a href=\"//{}:{}/pageB.html\">Link to page B</a></body></html>",
+        host.host_str().unwrap(),
+        host.port().unwrap()
+    );
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content(content.as_str())
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_b = SpiderTestPageBuilder::default()
+        .url("/pageB.html")
+        .content("<!DOCTYPE html><html><title>Page B</title><body><a href=\"pageC.html\">Link to page C</a></body></html>")
+        .title("Page B")
+        .build()
+        .unwrap();
+
+    let mut test_page_c = SpiderTestPageBuilder::default()
+        .url("/pageC.html")
+        .content("<!DOCTYPE html><html><title>Page C</title><body></body></html>")
+        .title("Page C")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_b)
+        .add_page(&mut test_page_c);
+    assert!(test_server.run_test().await);
+
+    // Make sure that the page graph contains three pages
+    test_server.assert_page_count(3);
+
+    // Make sure there are two links in the page graph
+    test_server.assert_link_count(2);
+}
+
+/// Page that is only linked with a protocol-relative URL is checked, but not parsed when `protocol_relative_in_host` is unset
+#[tokio::test]
+async fn test_protocol_relative_external() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"//example.com/b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"c.html\">C</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.protocol_relative_in_host = false;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let page_b = spider_crab.get_page_by_str("https://example.com/b.html");
+    assert!(page_b.visited);
+    assert_eq!(page_b.title, None);
+    assert!(!spider_crab.contains_page_by_str("https://example.com/c.html"));
+}
+
+/// Page that is also linked without a protocol-relative URL is parsed, whichever link comes first
+#[tokio::test]
+async fn test_protocol_relative_external_also_linked() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"//example.com/b.html\">B</a><a href=\"b.html\">B again</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"c.html\">C</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/c.html",
+            "<!DOCTYPE html><html><title>C</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.protocol_relative_in_host = false;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let page_b = spider_crab.get_page_by_str("https://example.com/b.html");
+    assert_eq!(page_b.title.as_deref(), Some("B"));
+    assert!(
+        spider_crab
+            .get_page_by_str("https://example.com/c.html")
+            .visited
+    );
+}
+
+/// Protocol-relative link to a page on another host should be checked, but not traversed
+#[tokio::test]
+async fn test_protocol_relative_external_host() {
+    let mut test_server = SpiderTestServer::default();
+
+    let host = Url::parse(test_server.url().as_str()).unwrap();
+    let content = format!(
+        "<!DOCTYPE html><html><title>Test Page</title><body><a href=\"//localhost:{}/pageB.html\">Link to page B</a></body></html>",
+        host.port().unwrap()
+    );
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content(content.as_str())
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_b = SpiderTestPageBuilder::default()
+        .url("/pageB.html")
+        .host("localhost")
+        .content("<!DOCTYPE html><html><title>Page B</title><body><a href=\"pageC.html\">Link to page C</a></body></html>")
+        .build()
+        .unwrap();
+
+    let mut test_page_c = SpiderTestPageBuilder::default()
+        .url("/pageC.html")
+        .expect_visited(false)
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_b)
+        .add_page(&mut test_page_c);
+    assert!(test_server.run_test().await);
+
+    // Make sure that the page graph contains two pages
+    test_server.assert_page_count(2);
+
+    // Make sure there is one link in the page graph
+    test_server.assert_link_count(1);
+}
+
+/// Page D is linked from three other pages, so it should have a reference count of 3
+#[tokio::test]
+async fn test_reference_count() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Root</title><body><a href=\"pageA.html\">A</a><a href=\"pageB.html\">B</a><a href=\"pageC.html\">C</a></body></html>")
+        .title("Root")
+        .build()
+        .unwrap();
+
+    let mut test_page_a = SpiderTestPageBuilder::default()
+        .url("/pageA.html")
+        .content(
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"pageD.html\">D</a></body></html>",
+        )
+        .title("A")
+        .build()
+        .unwrap();
+
+    let mut test_page_b = SpiderTestPageBuilder::default()
+        .url("/pageB.html")
+        .content(
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"pageD.html\">D</a></body></html>",
+        )
+        .title("B")
+        .build()
+        .unwrap();
+
+    let mut test_page_c = SpiderTestPageBuilder::default()
+        .url("/pageC.html")
+        .content(
+            "<!DOCTYPE html><html><title>C</title><body><a href=\"pageD.html\">D</a></body></html>",
+        )
+        .title("C")
+        .build()
+        .unwrap();
+
+    let mut test_page_d = SpiderTestPageBuilder::default()
+        .url("/pageD.html")
+        .content("<!DOCTYPE html><html><title>D</title><body></body></html>")
+        .title("D")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_a)
+        .add_page(&mut test_page_b)
+        .add_page(&mut test_page_c)
+        .add_page(&mut test_page_d);
+    assert!(test_server.run_test().await);
+
+    let url = Url::parse(test_server.url().as_str()).unwrap();
+    let page_d = url.join("pageD.html").unwrap();
+    assert_eq!(test_server.spider_crab.reference_count(&page_d), 3);
+    assert_eq!(test_server.spider_crab.reference_count(&url), 0);
+
+    // Make sure the most referenced page is listed first, and the root page last
+    let pages = test_server.spider_crab.pages_by_reference_count();
+    assert_eq!(pages.first().unwrap().0.url, page_d);
+    assert_eq!(pages.first().unwrap().1, 3);
+    assert_eq!(pages.last().unwrap().0.url, url);
+}
+
+/// Well-formed srcset should not produce any warnings
+#[tokio::test]
+async fn test_valid_srcset() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><img src=\"image.png\" srcset=\"image.png 1x, image-2x.png 2x\" /></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_image = SpiderTestPageBuilder::default()
+        .url("/image.png")
+        .content_type(Some("image/png"))
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_image);
+    assert!(test_server.run_test().await);
+
+    assert_eq!(test_server.spider_crab.errors().count(), 0);
+}
+
+/// Only the seeded page and the pages it links to should be fetched
+#[tokio::test]
+async fn test_visit_seeded_pages() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.max_depth = 1;
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Root</title><body><a href=\"pageA.html\">A</a><a href=\"pageB.html\">B</a></body></html>")
+        .expect_visited(false)
+        .build()
+        .unwrap();
+
+    let mut test_page_a = SpiderTestPageBuilder::default()
+        .url("/pageA.html")
+        .content(
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"pageC.html\">C</a></body></html>",
+        )
+        .title("A")
+        .build()
+        .unwrap();
+
+    let mut test_page_b = SpiderTestPageBuilder::default()
+        .url("/pageB.html")
+        .expect_visited(false)
+        .build()
+        .unwrap();
+
+    let mut test_page_c = SpiderTestPageBuilder::default()
+        .url("/pageC.html")
+        .content(
+            "<!DOCTYPE html><html><title>C</title><body><a href=\"pageD.html\">D</a></body></html>",
+        )
+        .title("C")
+        .build()
+        .unwrap();
+
+    let mut test_page_d = SpiderTestPageBuilder::default()
+        .url("/pageD.html")
+        .expect_visited(false)
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_a)
+        .add_page(&mut test_page_b)
+        .add_page(&mut test_page_c)
+        .add_page(&mut test_page_d);
+    assert!(test_server.run_test_from_pages(&["/pageA.html"]).await);
+
+    // Page D is discovered, but not visited
+    test_server.assert_page_count(3);
+    test_server.assert_link_count(2);
+}
+
+/// Crawl a known graph served by an in-memory fetcher, no HTTP requests are sent
+#[tokio::test]
+async fn test_in_memory_fetcher() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_response(
+            "https://example.com/a.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"b.html\">B</a></body></html>",
+            Some(Duration::from_millis(20)),
+        )
+        .add_response(
+            "https://example.com/b.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"/\">Root</a></body></html>",
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    assert_eq!(spider_crab.page_count(), 3);
+    assert_eq!(spider_crab.link_count(), 4);
+    assert_eq!(
+        spider_crab
+            .get_page_by_str("https://example.com/a.html")
+            .title
+            .as_deref(),
+        Some("A")
+    );
+    assert!(spider_crab.is_page_good_by_str("https://example.com/b.html"));
+}
+
+/// Crawl start and end times, and the duration of the crawl should be recorded
+#[tokio::test]
+async fn test_crawl_timestamps() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_response(
+        "https://example.com/",
+        200,
+        Some("text/html"),
+        "<!DOCTYPE html><html><title>Root</title><body></body></html>",
+        Some(Duration::from_millis(10)),
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+
+    // No crawl has been performed yet
+    assert!(spider_crab.report().duration().is_none());
+
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let report = spider_crab.report();
+    assert!(report.duration().unwrap() > Duration::ZERO);
+    assert!(report.end_time().unwrap() >= report.start_time().unwrap());
+    assert!(report.summary().contains("Crawl started at"));
+}
+
+/// Links inside of an element matching the skip selector should not be checked
+#[tokio::test]
+async fn test_skip_selector() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.skip_selector = Some(Selector::parse("footer").unwrap());
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"pageB.html\">Page B</a><footer><p><a href=\"pageC.html\">Page C</a></p></footer></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    let mut test_page_b = SpiderTestPageBuilder::default()
+        .url("/pageB.html")
+        .content("<!DOCTYPE html><html><title>Page B</title><body></body></html>")
+        .title("Page B")
+        .build()
+        .unwrap();
+
+    let mut test_page_c = SpiderTestPageBuilder::default()
+        .url("/pageC.html")
+        .expect_visited(false)
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page)
+        .add_page(&mut test_page_b)
+        .add_page(&mut test_page_c);
+    assert!(test_server.run_test().await);
+
+    // Links inside the footer are excluded from the page graph
+    test_server.assert_page_count(2);
+    test_server.assert_link_count(1);
+}
+
+/// Row counts of the SQLite database should match the page graph
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn test_save_sqlite() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"missing.html\">Missing</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"/\">Root</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let db_path = std::env::temp_dir().join("spider-crab-test-save-sqlite.db");
+    let _ = std::fs::remove_file(&db_path);
+    spider_crab.save_sqlite(db_path.to_str().unwrap()).unwrap();
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let count = |table: &str| -> usize {
+        conn.query_row(
+            format!("SELECT COUNT(*) FROM {}", table).as_str(),
+            [],
+            |r| r.get(0),
+        )
+        .unwrap()
+    };
+    assert_eq!(count("crawls"), 1);
+    assert_eq!(count("pages"), spider_crab.page_count());
+    assert_eq!(count("links"), spider_crab.link_count());
+    assert_eq!(count("errors"), spider_crab.errors().count());
+}
+
+/// Goodness policy that treats 404s as good
+struct Allow404Policy;
+
+impl GoodnessPolicy for Allow404Policy {
+    fn is_good_response(&self, _url: &Url, status: StatusCode, _headers: &HeaderMap) -> bool {
+        status.is_success() || status == StatusCode::NOT_FOUND
+    }
+}
+
+/// Links to a 404 page are good when a custom goodness policy accepts them
+#[tokio::test]
+async fn test_custom_goodness_policy() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.goodness_policy = Box::new(Allow404Policy);
+
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    assert_eq!(spider_crab.page_count(), 2);
+    assert_eq!(spider_crab.errors().count(), 0);
+    assert!(spider_crab.is_page_good_by_str("https://example.com/missing.html"));
+}
+
+/// Fetcher that returns a 503 with the given `Retry-After` header for the first request, then a good page
+struct RetryAfterFetcher {
+    retry_after: &'static str,
+    requests: AtomicUsize,
+}
+
+impl Fetcher for RetryAfterFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let mut headers = HeaderMap::new();
+            let status = if self.requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                headers.insert(RETRY_AFTER, HeaderValue::from_static(self.retry_after));
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+                StatusCode::OK
+            };
+            Ok(FetchedResponse {
+                url: request.url,
+                status,
+                headers,
+                body: Box::pin(async {
+                    Ok("<!DOCTYPE html><html><title>Root</title><body></body></html>".to_string())
+                }),
+                compressed: Some(false),
+                redirect_chain: vec![],
+            })
+        })
+    }
+}
+
+async fn run_retry_after_test(retry_after: &'static str) {
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.max_retry_after = Some(Duration::from_secs(60));
+    spider_crab.fetcher = Box::new(RetryAfterFetcher {
+        retry_after,
+        requests: AtomicUsize::new(0),
+    });
+
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert!(spider_crab.is_page_good_by_str("https://example.com/"));
+}
+
+/// A 503 with a `Retry-After` in seconds is retried
+#[tokio::test]
+async fn test_retry_after_seconds() {
+    run_retry_after_test("0").await;
+}
+
+/// A 503 with a `Retry-After` HTTP-date is retried
+#[tokio::test]
+async fn test_retry_after_http_date() {
+    run_retry_after_test("Wed, 21 Oct 2015 07:28:00 GMT").await;
+}
+
+/// A 503 with a `Retry-After` is not retried unless `max_retry_after` is set
+#[tokio::test]
+async fn test_retry_after_disabled_by_default() {
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(RetryAfterFetcher {
+        retry_after: "0",
+        requests: AtomicUsize::new(0),
+    });
+
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(
+        spider_crab
+            .get_page_by_str("https://example.com/")
+            .status_code,
+        Some(StatusCode::SERVICE_UNAVAILABLE)
+    );
+}
+
+/// Three pages that link to each other in a cycle, and a root page that links to itself
+#[tokio::test]
+async fn test_find_cycles() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"/\">Root</a><a href=\"a.html\">A</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body><a href=\"c.html\">C</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/c.html",
+            "<!DOCTYPE html><html><title>C</title><body><a href=\"a.html\">A</a><a href=\"c.html\">C</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let cycle_urls = |cycles: Vec<Vec<&Page>>| -> Vec<Vec<String>> {
+        cycles
+            .iter()
+            .map(|c| c.iter().map(|p| p.url.to_string()).collect())
+            .collect()
+    };
+
+    assert_eq!(
+        cycle_urls(spider_crab.find_cycles(false)),
+        vec![vec![
+            "https://example.com/a.html",
+            "https://example.com/b.html",
+            "https://example.com/c.html"
+        ]]
+    );
+
+    let cycles = cycle_urls(spider_crab.find_cycles(true));
+    assert_eq!(cycles.len(), 2);
+    assert_eq!(cycles[0], vec!["https://example.com/"]);
+}
+
+/// Pages on two hosts are grouped into two clusters in the Dot output
+#[tokio::test]
+async fn test_clustered_dot_format() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"https://example.org/\">Other</a></body></html>",
+        )
+        .add_page(
+            "https://example.org/",
+            "<!DOCTYPE html><html><title>Other</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let dot = spider_crab.get_clustered_dot_format(ClusterBy::Host);
+    assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+    assert!(dot.contains("label=\"example.com\""));
+    assert!(dot.contains("label=\"example.org\""));
+    assert!(dot.contains("0 -> 1"));
+}
+
+/// Icon-only link with an aria-label has an accessible name
+#[tokio::test]
+async fn test_aria_labeled_link_text() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.check_link_text = true;
+
+    let mut test_page = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Test Page</title><body><a href=\"/\" aria-label=\"Home\"><i class=\"icon-home\"></i></a></body></html>")
+        .title("Test Page")
+        .build()
+        .unwrap();
+
+    test_server.add_page(&mut test_page);
+    assert!(test_server.run_test().await);
+
+    test_server.assert_page_count(1);
+    test_server.assert_link_count(1);
+}
+
+/// Fetcher for a site whose broken link is fixed after the first crawl
+#[derive(Default)]
+struct FixedAfterFirstCrawlFetcher {
+    root_requests: AtomicUsize,
+}
+
+impl Fetcher for FixedAfterFirstCrawlFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            if request.url.path() != "/" {
+                return Ok(FetchedResponse {
+                    url: request.url,
+                    status: StatusCode::NOT_FOUND,
+                    headers: HeaderMap::new(),
+                    body: Box::pin(async { Ok(String::new()) }),
+                    compressed: Some(false),
+                    redirect_chain: vec![],
+                });
+            }
+
+            let body = if self.root_requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a></body></html>"
+            } else {
+                "<!DOCTYPE html><html><title>Root</title><body></body></html>"
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+            Ok(FetchedResponse {
+                url: request.url,
+                status: StatusCode::OK,
+                headers,
+                body: Box::pin(async move { Ok(body.to_string()) }),
+                compressed: Some(false),
+                redirect_chain: vec![],
+            })
+        })
+    }
+}
+
+/// Watch mode re-crawls the site and reports the fixed error
+#[tokio::test]
+async fn test_watch() {
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(FixedAfterFirstCrawlFetcher::default());
+
+    let mut results = Vec::<(bool, CrawlDiff)>::new();
+    spider_crab
+        .watch(
+            "https://example.com/",
+            Duration::from_millis(10),
+            |_, result, diff| {
+                results.push((result, diff.clone()));
+                results.len() < 2
+            },
+        )
+        .await;
+
+    assert_eq!(results.len(), 2);
+
+    let (first_result, first_diff) = &results[0];
+    assert!(!first_result);
+    assert_eq!(first_diff.new_errors.len(), 1);
+
+    let (second_result, second_diff) = &results[1];
+    assert!(second_result);
+    assert!(second_diff.new_errors.is_empty());
+    assert_eq!(second_diff.fixed_errors, first_diff.new_errors);
+
+    // Only the pages of the latest crawl are kept
+    assert_eq!(spider_crab.page_count(), 1);
+}
+
+/// Page titles with double-quotes are escaped in the Dot output
+#[tokio::test]
+async fn test_dot_format_escapes_title() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Say \"Hi\"</title><body></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    for dot in [
+        spider_crab.get_dot_format(),
+        spider_crab.get_clustered_dot_format(ClusterBy::Host),
+    ] {
+        assert!(
+            dot.contains("label=\"Say \\\"Hi\\\"\\nhttps://example.com/\""),
+            "{}",
+            dot
+        );
+
+        // Every line has balanced, unescaped double-quotes
+        for line in dot.lines() {
+            let unescaped_quotes = line
+                .replace("\\\\", "")
+                .replace("\\\"", "")
+                .matches('"')
+                .count();
+            assert_eq!(unescaped_quotes % 2, 0, "Unbalanced quotes in: {}", line);
+        }
+    }
+}
+
+/// Paginated API that links to its second page through the Link header
+#[tokio::test]
+async fn test_follow_link_headers() {
+    let mut test_server = SpiderTestServer::default();
+    test_server.spider_crab.options.follow_link_headers = true;
+
+    let mut test_page_1 = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("{\"items\": [1, 2]}")
+        .content_type(Some("application/json"))
+        .headers(vec![("Link", "</items?page=2>; rel=\"next\"")])
+        .build()
+        .unwrap();
+    let mut test_page_2 = SpiderTestPageBuilder::default()
+        .url("/items?page=2")
+        .content("{\"items\": [3]}")
+        .content_type(Some("application/json"))
+        .headers(vec![("Link", "</>; rel=\"prev\"")])
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page_1)
+        .add_page(&mut test_page_2);
+    assert!(test_server.run_test().await);
+
+    test_server.assert_page_count(2);
+    test_server.assert_link_count(2);
+}
+
+#[tokio::test]
+async fn test_cache_validators() {
+    let mut test_server = SpiderTestServer::default();
+
+    let mut test_page_1 = SpiderTestPageBuilder::default()
+        .url("/")
+        .content("<!DOCTYPE html><html><title>Root</title><body><a href=\"page2.html\">Page 2</a></body></html>")
+        .title("Root")
+        .headers(vec![
+            ("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ("ETag", "\"33a64df5\""),
+        ])
+        .build()
+        .unwrap();
+    let mut test_page_2 = SpiderTestPageBuilder::default()
+        .url("/page2.html")
+        .content("<!DOCTYPE html><html><title>Page 2</title><body></body></html>")
+        .title("Page 2")
+        .build()
+        .unwrap();
+
+    test_server
+        .add_page(&mut test_page_1)
+        .add_page(&mut test_page_2);
+    assert!(test_server.run_test().await);
+
+    let root = test_server
+        .spider_crab
+        .get_page_by_str(format!("{}/", test_server.url()).as_str());
+    assert_eq!(
+        root.last_modified.as_deref(),
+        Some("Wed, 21 Oct 2015 07:28:00 GMT")
+    );
+    assert_eq!(root.etag.as_deref(), Some("\"33a64df5\""));
+
+    let page_2 = test_server
+        .spider_crab
+        .get_page_by_str(format!("{}/page2.html", test_server.url()).as_str());
+    assert_eq!(page_2.last_modified, None);
+    assert_eq!(page_2.etag, None);
+
+    let report: serde_json::Value =
+        serde_json::from_str(&test_server.spider_crab.to_json_report()).unwrap();
+    let pages = report["pages"].as_array().unwrap();
+    let root = pages
+        .iter()
+        .find(|page| page["url"] == format!("{}/", test_server.url()))
+        .unwrap();
+    assert_eq!(root["last_modified"], "Wed, 21 Oct 2015 07:28:00 GMT");
+    assert_eq!(root["etag"], "\"33a64df5\"");
+    let page_2 = pages
+        .iter()
+        .find(|page| page["url"] == format!("{}/page2.html", test_server.url()))
+        .unwrap();
+    assert!(page_2.get("last_modified").is_none());
+    assert!(page_2.get("etag").is_none());
+}
+
+/// Gone page that is expected to return a 410
+#[tokio::test]
+async fn test_expected_status() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"old/gone.html\">Gone</a></body></html>",
+        )
+        .add_response(
+            "https://example.com/old/gone.html",
+            410,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>Gone</title><body></body></html>",
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab
+        .options
+        .expected_statuses
+        .push(("*/old/*".to_string(), 410));
+
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+    assert!(spider_crab.is_page_good_by_str("https://example.com/old/gone.html"));
+}
+
+/// Every line of the NDJSON output is a JSON event object
+#[tokio::test]
+async fn test_ndjson_events() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"missing.html\">Missing</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    let events = spider_crab.subscribe();
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    spider_crab.event_sender = None;
+
+    let mut stdout = Vec::<u8>::new();
+    write_ndjson(events, &mut stdout).unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(events.iter().all(|e| e.is_object()));
+
+    let names: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "page_started",
+            "page_finished",
+            "page_started",
+            "page_finished",
+            "error_found",
+            "crawl_finished"
+        ]
+    );
+    assert_eq!(events[4]["rule"], "http-error");
+    assert_eq!(events[5]["result"], false);
+    assert_eq!(events[5]["pages"], 2);
+}
+
+/// Fetcher that pauses the crawl after its first request
+struct PauseAfterFirstFetcher {
+    inner: InMemoryFetcher,
+    pause_handle: PauseHandle,
+    requests: Arc<AtomicUsize>,
+}
+
+impl Fetcher for PauseAfterFirstFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        if self.requests.fetch_add(1, Ordering::SeqCst) == 0 {
+            self.pause_handle.pause();
+        }
+        self.inner.fetch(request)
+    }
+}
+
+#[tokio::test]
+async fn test_pause_resume() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    let pause_handle = spider_crab.pause_handle();
+    let requests = Arc::new(AtomicUsize::new(0));
+    spider_crab.fetcher = Box::new(PauseAfterFirstFetcher {
+        inner,
+        pause_handle: pause_handle.clone(),
+        requests: requests.clone(),
+    });
+
+    let crawl = spider_crab.visit_website("https://example.com/");
+    let control = async {
+        while requests.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(pause_handle.is_paused());
+
+        // Give the crawl time to send more requests, if it would
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        pause_handle.resume();
+    };
+    let (result, _) = tokio::join!(crawl, control);
+
+    assert!(result);
+    assert!(!spider_crab.pause_handle().is_paused());
+    // robots.txt, a.html and b.html are requested after resuming
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+    assert_eq!(spider_crab.page_count(), 3);
+}
+
+/// Only pages under /blog/ are visited, other in-host pages are only linked to
+#[tokio::test]
+async fn test_url_match() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"blog/post.html\">Post</a><a href=\"about.html\">About</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/blog/post.html",
+            "<!DOCTYPE html><html><title>Post</title><body><a href=\"../shop/\">Shop</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.url_match = Some(Regex::new("/blog/").unwrap());
+
+    // about.html and shop/ do not exist, but they are never requested
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.page_count(), 4);
+    assert_eq!(spider_crab.link_count(), 3);
+    assert_eq!(spider_crab.errors().count(), 0);
+
+    let visited = |url: &str| {
+        let url = Url::parse(url).unwrap();
+        spider_crab.graph[spider_crab.map[&url]].visited
+    };
+    assert!(visited("https://example.com/"));
+    assert!(visited("https://example.com/blog/post.html"));
+    assert!(!visited("https://example.com/about.html"));
+    assert!(!visited("https://example.com/shop/"));
+}
+
+/// Plain report and sorted errors do not depend on the order pages are visited in, so that crawls of the same site produce the same output
+#[tokio::test]
+async fn test_plain_report_is_deterministic() {
+    let mut plain_reports = Vec::<String>::new();
+    let mut sorted_errors = Vec::<Vec<String>>::new();
+    for latency in [1, 20] {
+        let mut fetcher = InMemoryFetcher::default();
+        fetcher
+            .add_page(
+                "https://example.com/",
+                "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a><a href=\"missing.html\">Missing</a></body></html>",
+            )
+            .add_response(
+                "https://example.com/a.html",
+                200,
+                Some("text/html"),
+                "<!DOCTYPE html><html><body><a href=\"missing-a.html\">Missing</a></body></html>",
+                Some(Duration::from_millis(latency)),
+            )
+            .add_response(
+                "https://example.com/b.html",
+                200,
+                Some("text/html"),
+                "<!DOCTYPE html><html><title>B</title><body><a>No href</a></body></html>",
+                Some(Duration::from_millis(21 - latency)),
+            );
+
+        let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+        spider_crab.fetcher = Box::new(fetcher);
+        assert!(!spider_crab.visit_website("https://example.com/").await);
+        plain_reports.push(spider_crab.report().plain());
+        sorted_errors.push(
+            spider_crab
+                .report()
+                .sorted_errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+        );
+    }
+
+    assert_eq!(plain_reports[0], plain_reports[1]);
+    assert_eq!(sorted_errors[0], sorted_errors[1]);
+    assert_eq!(sorted_errors[0].len(), 4);
+
+    let lines: Vec<&str> = plain_reports[0].lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(
+        lines[0],
+        "http-error\t-\thttps://example.com/missing-a.html\tHTTP GET request received status code 404 for page \"https://example.com/missing-a.html\"!"
+    );
+    assert!(lines.iter().all(|l| l.split('\t').count() == 4));
+}
+
+/// Links to directories of a website on the local disk are served from the directory's index file
+#[tokio::test]
+async fn test_file_fetcher_index_files() {
+    let root = std::env::temp_dir().join(format!("spidercrab-site-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    std::fs::create_dir_all(root.join("blog")).unwrap();
+    std::fs::write(
+        root.join("index.html"),
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"docs/\">Docs</a><a href=\"blog/\">Blog</a></body></html>",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("docs/index.html"),
+        "<!DOCTYPE html><html><title>Docs</title><body><a href=\"../index.html\">Home</a></body></html>",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("blog/default.html"),
+        "<!DOCTYPE html><html><title>Blog</title><body></body></html>",
+    )
+    .unwrap();
+
+    let root_url = Url::from_file_path(root.join("index.html")).unwrap();
+    let mut spider_crab = SpiderCrab::new(&[root_url.as_str()]);
+    spider_crab.options.index_files = vec!["index.html".to_string(), "default.html".to_string()];
+    spider_crab.fetcher = Box::new(FileFetcher::new(spider_crab.options.index_files.clone()));
+    let result = spider_crab.visit_website(root_url.as_str()).await;
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(result);
+    assert_eq!(spider_crab.page_count(), 3);
+    let docs_url = Url::from_directory_path(root.join("docs")).unwrap();
+    let docs = spider_crab.get_page(&docs_url);
+    assert_eq!(docs.title.as_deref(), Some("Docs"));
+    let blog_url = Url::from_directory_path(root.join("blog")).unwrap();
+    assert_eq!(
+        spider_crab.get_page(&blog_url).title.as_deref(),
+        Some("Blog")
+    );
+}
+
+/// Fake domain that resolves to the mock server through a host override
+#[tokio::test]
+async fn test_host_override() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Staging</title><body></body></html>")
+        .create_async()
+        .await;
+
+    let url = format!(
+        "http://staging.spidercrab.invalid:{}/",
+        server.socket_address().port()
+    );
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.host_overrides.insert(
+        String::from("staging.spidercrab.invalid"),
+        server.socket_address().ip(),
+    );
+
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    mock.assert_async().await;
+    assert!(spider_crab.is_page_good_by_str(url.as_str()));
+}
+
+/// Fetcher that records the URL of every request it sends
+struct RecordingFetcher {
+    inner: InMemoryFetcher,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Fetcher for RecordingFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        self.requests.lock().unwrap().push(request.url.to_string());
+        self.inner.fetch(request)
+    }
+}
+
+/// Checking a single URL never follows its links, even links that do not count towards the depth
+#[tokio::test]
+async fn test_check_url_ignore_query_depth() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_page(
+            "https://example.com/list.html",
+            "<!DOCTYPE html><html><title>List</title><body><a href=\"?page=2\">Next</a><a href=\"other.html\">Other</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/list.html?page=2",
+            "<!DOCTYPE html><html><title>List</title><body></body></html>",
+        );
+
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.ignore_query_depth = true;
+    spider_crab.options.respect_robots = false;
+    spider_crab.fetcher = Box::new(RecordingFetcher {
+        inner,
+        requests: requests.clone(),
+    });
+
+    let page = spider_crab.check_url("https://example.com/list.html").await;
+    assert_eq!(page.good, Some(true));
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec!["https://example.com/list.html"]
+    );
+}
+
+/// A URL given more than once to `check_urls()` is only requested once
+#[tokio::test]
+async fn test_check_urls_duplicates() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/about.html",
+            "<!DOCTYPE html><html><title>About</title><body></body></html>",
+        );
+
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.respect_robots = false;
+    spider_crab.fetcher = Box::new(RecordingFetcher {
+        inner,
+        requests: requests.clone(),
+    });
+
+    let results = spider_crab
+        .check_urls(&[
+            "https://example.com/about.html",
+            "https://example.com/",
+            "https://example.com/about.html",
+        ])
+        .await;
+    let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/about.html",
+            "https://example.com/",
+            "https://example.com/about.html",
+        ]
+    );
+    assert!(results.iter().all(|r| r.good == Some(true)));
+
+    let mut requests = requests.lock().unwrap().clone();
+    requests.sort();
+    assert_eq!(
+        requests,
+        vec!["https://example.com/", "https://example.com/about.html"]
+    );
+}
+
+/// Pages with a higher sitemap priority are requested first when priority ordering is enabled
+#[tokio::test]
+async fn test_sitemap_priority_order() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_response(
+            "https://example.com/sitemap.xml",
+            200,
+            Some("application/xml"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/archive.html</loc><priority>0.2</priority><changefreq>never</changefreq></url>
+  <url><loc>https://example.com/about.html</loc></url>
+  <url><loc>https://example.com/</loc><priority>1.0</priority><changefreq>daily</changefreq></url>
+</urlset>"#,
+            Some(Duration::from_millis(5)),
+        )
+        .add_response(
+            "https://example.com/archive.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>Archive</title><body></body></html>",
+            Some(Duration::from_millis(5)),
+        )
+        .add_response(
+            "https://example.com/about.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>About</title><body></body></html>",
+            Some(Duration::from_millis(5)),
+        )
+        .add_response(
+            "https://example.com/",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>Root</title><body></body></html>",
+            Some(Duration::from_millis(5)),
+        );
+
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.sitemap_priority_order = true;
+    spider_crab.fetcher = Box::new(RecordingFetcher {
+        inner,
+        requests: requests.clone(),
+    });
+    assert!(
+        spider_crab
+            .visit_sitemap("https://example.com/sitemap.xml")
+            .await
+    );
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![
+            "https://example.com/sitemap.xml",
+            "https://example.com/",
+            "https://example.com/about.html",
+            "https://example.com/archive.html",
+        ]
+    );
+    assert_eq!(spider_crab.page_count(), 3);
+
+    let root = spider_crab.get_page_by_str("https://example.com/");
+    assert_eq!(root.sitemap_priority, Some(1.0));
+    assert_eq!(root.sitemap_changefreq.as_deref(), Some("daily"));
+    let about = spider_crab.get_page_by_str("https://example.com/about.html");
+    assert_eq!(about.sitemap_priority, None);
+    assert_eq!(about.sitemap_changefreq, None);
+}
+
+/// Relative links are resolved against the forced base URL instead of the URL of the page
+#[tokio::test]
+async fn test_force_base() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/proxy/index.html",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"about.html\">About</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/site/about.html",
+            "<!DOCTYPE html><html><title>About</title><body></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.force_base = Some(Url::parse("https://example.com/site/").unwrap());
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(
+        spider_crab
+            .visit_website("https://example.com/proxy/index.html")
+            .await
+    );
+
+    assert_eq!(spider_crab.page_count(), 2);
+    assert!(spider_crab.contains_page_by_str("https://example.com/site/about.html"));
+    assert!(!spider_crab.contains_page_by_str("https://example.com/proxy/about.html"));
+}
+
+/// Pages and errors of each host are summarized separately
+#[tokio::test]
+async fn test_per_host_summary() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"about.html\">About</a><a href=\"https://shop.example.com/\">Shop</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/about.html",
+            "<!DOCTYPE html><html><body></body></html>",
+        )
+        .add_page(
+            "https://shop.example.com/",
+            "<!DOCTYPE html><html><title>Shop</title><body><a href=\"cart.html\">Cart</a><a href=\"missing.html\">Missing</a></body></html>",
+        )
+        .add_response(
+            "https://shop.example.com/cart.html",
+            200,
+            Some("text/html"),
+            "<!DOCTYPE html><html><title>Cart</title><body></body></html>",
+            Some(Duration::from_millis(20)),
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/", "https://shop.example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+
+    let summaries = spider_crab.report().per_host_summary();
+    assert_eq!(summaries.len(), 2);
+
+    let main = &summaries[&Host::Domain("example.com".to_string())];
+    assert_eq!(main.pages_visited, 2);
+    assert_eq!(main.errors_by_type.len(), 1);
+    assert_eq!(main.errors_by_type[&SpiderErrorType::MissingTitle], 1);
+
+    let shop = &summaries[&Host::Domain("shop.example.com".to_string())];
+    assert_eq!(shop.pages_visited, 3);
+    assert_eq!(shop.error_count(), 1);
+    assert_eq!(shop.errors_by_type[&SpiderErrorType::HTTPError], 1);
+    // Cart page responds slowly, which raises the average of the shop
+    assert!(shop.average_response_time.unwrap() >= Duration::from_millis(20) / 3);
+    assert!(shop.average_response_time > main.average_response_time);
+}
+
+/// Ignore file with comments and blank lines is valid
+#[test]
+fn test_validate_ignore_file() {
+    let path = std::env::temp_dir().join(format!("spidercrab-ignore-valid-{}", std::process::id()));
+    std::fs::write(
+        &path,
+        "# Archived page\nmissing-title   https://example.com/old.html\n\n  http-error https://example.org/  \n",
+    )
+    .unwrap();
+
+    let count = SpiderOptions::validate_ignore_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(count.unwrap(), 2);
+}
+
+/// Fetcher that counts the most requests it was sending at the same time
+struct ConcurrencyCountingFetcher {
+    inner: InMemoryFetcher,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl Fetcher for ConcurrencyCountingFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            let response = self.inner.fetch(request).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            response
+        })
+    }
+}
+
+/// At most `max_concurrency` requests are in flight at once, without a limit all links of a page are requested together
+#[tokio::test]
+async fn test_max_concurrency() {
+    let crawl = |max_concurrency: usize| async move {
+        let mut inner = InMemoryFetcher::default();
+        inner.add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"page0.html\">0</a><a href=\"page1.html\">1</a><a href=\"page2.html\">2</a><a href=\"page3.html\">3</a><a href=\"page4.html\">4</a><a href=\"page5.html\">5</a><a href=\"page6.html\">6</a><a href=\"page7.html\">7</a></body></html>",
+        );
+        for i in 0..8 {
+            inner.add_response(
+                &format!("https://example.com/page{}.html", i),
+                200,
+                Some("text/html"),
+                "<!DOCTYPE html><html><title>Page</title><body></body></html>",
+                Some(Duration::from_millis(20)),
+            );
+        }
+
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+        spider_crab.options.max_concurrency = max_concurrency;
+        spider_crab.fetcher = Box::new(ConcurrencyCountingFetcher {
+            inner,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max_in_flight: max_in_flight.clone(),
+        });
+        assert!(spider_crab.visit_website("https://example.com/").await);
+        assert_eq!(spider_crab.graph.node_count(), 9);
+        max_in_flight.load(Ordering::SeqCst)
+    };
+
+    assert_eq!(crawl(2).await, 2);
+    assert_eq!(crawl(0).await, 8);
+}
+
+/// Links to trusted hosts are assumed to be good without being requested, and are counted in the summary
+#[tokio::test]
+async fn test_trusted_external_host() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"https://en.wikipedia.org/wiki/Crab\">Crabs</a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab
+        .options
+        .trusted_external_hosts
+        .push(Host::parse("en.wikipedia.org").unwrap());
+    // The fetcher has no response for the trusted page, so requesting it would fail the crawl
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let url = Url::parse("https://en.wikipedia.org/wiki/Crab").unwrap();
+    let page = spider_crab
+        .graph
+        .node_weight(spider_crab.map[&url])
+        .unwrap();
+    assert!(!page.visited);
+    assert_eq!(page.good, Some(true));
+
+    let report = spider_crab.report();
+    assert_eq!(report.trusted_count(), 1);
+    assert!(report
+        .summary()
+        .contains("Skipped 1 pages on trusted hosts, they were assumed to be good"));
+}
+
+/// Pages of a paginated list are all reached under a shallow depth when query-only links do not count towards it
+#[tokio::test]
+async fn test_ignore_query_depth() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"list\">List</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/list",
+            "<!DOCTYPE html><html><title>List</title><body><a href=\"list?page=2\">Next</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/list?page=2",
+            "<!DOCTYPE html><html><title>List</title><body><a href=\"list?page=3\">Next</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/list?page=3",
+            "<!DOCTYPE html><html><title>List</title><body><a href=\"list?page=4\">Next</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/list?page=4",
+            "<!DOCTYPE html><html><title>List</title><body><a href=\"item.html\">Item</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.max_depth = 1;
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    // Only the root page and the first page of the list are reached
+    assert_eq!(spider_crab.graph.node_count(), 3);
+
+    spider_crab.reset();
+    spider_crab.options.ignore_query_depth = true;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    for page in 2..=4 {
+        let url = Url::parse(&format!("https://example.com/list?page={}", page)).unwrap();
+        assert!(spider_crab.graph[spider_crab.map[&url]].visited, "{}", url);
+    }
+    // Item is linked from the last page of the list, but is one page deeper than the maximum depth
+    let item = Url::parse("https://example.com/item.html").unwrap();
+    assert!(!spider_crab.graph[spider_crab.map[&item]].visited);
+}
+
+/// Assets are never parsed for links when they are checked with HEAD requests, even if they are served as HTML
+#[tokio::test]
+async fn test_head_for_assets_not_parsed() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><img src=\"logo.png\" alt=\"Logo\"><a href=\"report.pdf\">Report</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/logo.png",
+            "<!DOCTYPE html><html><title>Not an image</title><body><a href=\"hidden.html\">Hidden</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/report.pdf",
+            "<!DOCTYPE html><html><title>Not a PDF</title><body><a href=\"hidden.html\">Hidden</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    // Without HEAD requests for assets the misconfigured image and PDF are parsed as pages, and their missing link is found
+    assert!(!spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.graph.node_count(), 4);
+
+    // Links to assets are not parsed either, only the assets that pages load
+    spider_crab.reset();
+    spider_crab.options.head_for_assets = true;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.graph.node_count(), 3);
+    let logo = Url::parse("https://example.com/logo.png").unwrap();
+    let logo = &spider_crab.graph[spider_crab.map[&logo]];
+    assert!(logo.visited);
+    assert_eq!(logo.good, Some(true));
+    assert_eq!(logo.title, None);
+}
+
+/// Fetcher that records when each request was sent
+struct TimingFetcher<F> {
+    inner: F,
+    requests: Arc<Mutex<Vec<(String, Instant)>>>,
+}
+
+impl<F: Fetcher> Fetcher for TimingFetcher<F> {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((request.url.to_string(), Instant::now()));
+        self.inner.fetch(request)
+    }
+}
+
+/// Requests to the same host are spaced by the request delay, requests to other hosts are not
+#[tokio::test]
+async fn test_request_delay() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"next.html\">Next</a><a href=\"https://example.org/\">Other</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/next.html",
+            "<!DOCTYPE html><html><title>Next</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.org/",
+            "<!DOCTYPE html><html><title>Other</title><body></body></html>",
+        );
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let delay = Duration::from_millis(200);
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.request_delay = Some(delay);
+    spider_crab.fetcher = Box::new(TimingFetcher {
+        inner,
+        requests: requests.clone(),
+    });
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let requests = requests.lock().unwrap();
+    let sent_at = |url: &str| requests.iter().find(|(u, _)| u == url).unwrap().1;
+    let root = sent_at("https://example.com/");
+    // robots.txt is requested like any other page of the host
+    let robots = sent_at("https://example.com/robots.txt");
+    assert!(robots - root >= delay);
+    assert!(sent_at("https://example.com/next.html") - robots >= delay);
+    // Links are only followed once robots.txt is known, but the other host does not wait for the delay of example.com
+    assert!(sent_at("https://example.org/") - robots < delay);
+    // Other websites are only checked, their robots.txt is never requested
+    assert!(!requests
+        .iter()
+        .any(|(u, _)| u == "https://example.org/robots.txt"));
+}
+
+/// Pages disallowed by robots.txt are not requested, unless robots.txt is ignored
+#[tokio::test]
+async fn test_respect_robots() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_response(
+            "https://example.com/robots.txt",
+            200,
+            Some("text/plain"),
+            "User-agent: *\nDisallow: /admin/\nAllow: /admin/help.html\n",
+            None,
+        )
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"admin/users.html\">Users</a><a href=\"admin/help.html\">Help</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/admin/users.html",
+            "<!DOCTYPE html><html><title>Users</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/admin/help.html",
+            "<!DOCTYPE html><html><title>Help</title><body></body></html>",
+        );
+    let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(RecordingFetcher {
+        inner,
+        requests: requests.clone(),
+    });
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![
+            "https://example.com/",
+            "https://example.com/robots.txt",
+            "https://example.com/admin/help.html",
+        ]
+    );
+    let users = spider_crab.get_page_by_str("https://example.com/admin/users.html");
+    assert!(!users.visited);
+
+    // Every crawl retrieves robots.txt again, it may have changed since the crawl before
+    requests.lock().unwrap().clear();
+    spider_crab.reset();
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert!(requests
+        .lock()
+        .unwrap()
+        .contains(&String::from("https://example.com/robots.txt")));
+
+    requests.lock().unwrap().clear();
+    spider_crab.reset();
+    spider_crab.options.respect_robots = false;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert!(!requests
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|r| r.ends_with("robots.txt")));
+    let users = spider_crab.get_page_by_str("https://example.com/admin/users.html");
+    assert!(users.visited);
+}
+
+/// Fetcher that responds with `503 Service Unavailable` to the first `failures` requests for `url`
+struct FlakyFetcher {
+    inner: InMemoryFetcher,
+    url: Url,
+    failures: usize,
+    requests: Arc<AtomicUsize>,
+}
+
+impl Fetcher for FlakyFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        if request.url == self.url && self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Box::pin(async move {
+                Ok(FetchedResponse {
+                    url: request.url,
+                    status: StatusCode::SERVICE_UNAVAILABLE,
+                    headers: HeaderMap::new(),
+                    body: Box::pin(async { Ok(String::new()) }),
+                    compressed: Some(false),
+                    redirect_chain: vec![],
+                })
+            });
+        }
+        self.inner.fetch(request)
+    }
+}
+
+/// Requests that fail with a server error are retried until they succeed
+#[tokio::test]
+async fn test_retry_transient_failures() {
+    for (max_retries, expected_result) in [(2, true), (1, false)] {
+        let mut inner = InMemoryFetcher::default();
+        inner
+            .add_page(
+                "https://example.com/",
+                "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a></body></html>",
+            )
+            .add_page(
+                "https://example.com/a.html",
+                "<!DOCTYPE html><html><title>A</title><body></body></html>",
+            );
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+        spider_crab.options.max_retries = max_retries;
+        spider_crab.options.retry_backoff = Duration::from_millis(1);
+        spider_crab.fetcher = Box::new(FlakyFetcher {
+            inner,
+            url: Url::parse("https://example.com/a.html").unwrap(),
+            failures: 2,
+            requests: requests.clone(),
+        });
+        assert_eq!(
+            spider_crab.visit_website("https://example.com/").await,
+            expected_result
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), max_retries as usize + 1);
+
+        if expected_result {
+            assert_eq!(spider_crab.errors().count(), 0);
+            assert_eq!(
+                spider_crab
+                    .get_page_by_str("https://example.com/a.html")
+                    .status_code,
+                Some(StatusCode::OK)
+            );
+        } else {
+            assert_eq!(spider_crab.errors().count(), 1);
+        }
+    }
+}
+
+/// Other requests are sent while a failed request waits to be retried
+#[tokio::test]
+async fn test_retry_backoff_releases_concurrency() {
+    let mut inner = InMemoryFetcher::default();
+    inner
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body></body></html>",
+        );
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.options.respect_robots = false;
+    spider_crab.options.max_concurrency = 1;
+    spider_crab.options.max_retries = 1;
+    spider_crab.options.retry_backoff = Duration::from_millis(200);
+    spider_crab.fetcher = Box::new(TimingFetcher {
+        inner: FlakyFetcher {
+            inner,
+            url: Url::parse("https://example.com/a.html").unwrap(),
+            failures: 1,
+            requests: Arc::new(AtomicUsize::new(0)),
+        },
+        requests: requests.clone(),
+    });
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let requests = requests.lock().unwrap();
+    let urls: Vec<&str> = requests.iter().map(|(url, _)| url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/",
+            "https://example.com/a.html",
+            "https://example.com/b.html",
+            "https://example.com/a.html",
+        ]
+    );
+}
+
+/// Redirects to a subdomain of the same registrable domain are followed with the same-domain redirect policy
+#[tokio::test]
+async fn test_redirect_same_domain() {
+    let mut server = Server::new_async().await;
+    let port = server.socket_address().port();
+    let url = format!("http://spidercrab.invalid:{}/", port);
+    let root_mock = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"moved.html\">Moved</a></body></html>")
+        .create_async()
+        .await;
+    let redirect_mock = server
+        .mock("GET", "/moved.html")
+        .with_status(301)
+        .with_header(
+            "location",
+            &format!("http://www.spidercrab.invalid:{}/new.html", port),
+        )
+        .create_async()
+        .await;
+    let new_mock = server
+        .mock("GET", "/new.html")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>New</title><body></body></html>")
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.redirect_policy = RedirectPolicy::SameDomain;
+    for host in ["spidercrab.invalid", "www.spidercrab.invalid"] {
+        options
+            .host_overrides
+            .insert(String::from(host), server.socket_address().ip());
+    }
+
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    root_mock.assert_async().await;
+    redirect_mock.assert_async().await;
+    new_mock.assert_async().await;
+    assert_eq!(spider_crab.errors().count(), 0);
+    assert!(spider_crab.is_page_good_by_str(&format!("{}moved.html", url)));
+}
+
+/// Requests are sent with the custom user agent, and robots.txt rules are looked up by its product token
+#[tokio::test]
+async fn test_user_agent() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    let user_agent = "my-bot/1.0 (+https://example.com/bot)";
+    let root_mock = server
+        .mock("GET", "/")
+        .match_header("user-agent", user_agent)
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"private/\">Private</a></body></html>")
+        .create_async()
+        .await;
+    let robots_mock = server
+        .mock("GET", "/robots.txt")
+        .match_header("user-agent", user_agent)
+        .with_status(200)
+        .with_header("content-type", "text/plain")
+        .with_body("User-agent: my-bot\nDisallow: /private/\n")
+        .create_async()
+        .await;
+    let private_mock = server
+        .mock("GET", "/private/")
+        .with_status(200)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.user_agent = Some(String::from(user_agent));
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    root_mock.assert_async().await;
+    robots_mock.assert_async().await;
+    private_mock.assert_async().await;
+
+    // Without a custom user agent, requests are sent as spider-crab
+    let default_mock = server
+        .mock("GET", "/")
+        .match_header("user-agent", DEFAULT_USER_AGENT)
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body></body></html>")
+        .create_async()
+        .await;
+    let mut spider_crab = SpiderCrab::from_options(SpiderOptions::new(&[url.as_str()])).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    default_mock.assert_async().await;
+}
+
+/// The redirects that a request was redirected through are recorded, and reported when asked for
+#[tokio::test]
+async fn test_redirect_chain() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"old.html\">Old</a></body></html>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/old.html")
+        .with_status(301)
+        .with_header("location", "/middle.html")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/middle.html")
+        .with_status(302)
+        .with_header("location", "/new.html")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/new.html")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>New</title><body></body></html>")
+        .create_async()
+        .await;
+
+    let old_url = format!("{}old.html", url);
+    for report_redirects in [false, true] {
+        let mut options = SpiderOptions::new(&[url.as_str()]);
+        options.report_redirects = report_redirects;
+        let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+        // Redirects are warnings, they do not fail the crawl
+        assert!(spider_crab.visit_website(url.as_str()).await);
+
+        let chain: Vec<String> = spider_crab
+            .get_page_by_str(&old_url)
+            .redirect_chain
+            .iter()
+            .map(|u| u.to_string())
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                old_url.clone(),
+                format!("{}middle.html", url),
+                format!("{}new.html", url)
+            ]
+        );
+        assert!(spider_crab.get_page_by_str(&url).redirect_chain.is_empty());
+
+        let errors: Vec<_> = spider_crab.errors().collect();
+        if report_redirects {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].error_type, SpiderErrorType::RedirectChain);
+            assert_eq!(errors[0].count, Some(2));
+            assert_eq!(errors[0].source_page, Some(old_url.clone()));
+        } else {
+            assert!(errors.is_empty());
+        }
+    }
+}
+
+/// Anchors that link to their own page with an absolute URL are warned about when asked for
+#[tokio::test]
+async fn test_absolute_self_link() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"about\">About</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/about",
+            "<!DOCTYPE html><html><head><title>About</title><link rel=\"canonical\" href=\"https://example.com/about\"></head>\
+            <body><a href=\"https://example.com/about\">About</a><a href=\"#team\">Team</a><a href=\"/\">Home</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+
+    spider_crab.reset();
+    spider_crab.options.check_absolute_self_links = true;
+    // Warnings do not fail the crawl
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let errors: Vec<_> = spider_crab.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].error_type,
+        SpiderErrorType::RedundantAbsoluteSelfLink
+    );
+    assert!(errors[0].is_warning());
+    assert_eq!(
+        errors[0].source_page.as_deref(),
+        Some("https://example.com/about")
+    );
+}
+
+/// Images that pages load are checked with a HEAD request when asked for, links to pages are not
+#[tokio::test]
+async fn test_head_for_assets() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><img src=\"photo\" alt=\"Photo\"><a href=\"about\">About</a></body></html>")
+        .create_async()
+        .await;
+    let about_mock = server
+        .mock("GET", "/about")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>About</title><body></body></html>")
+        .create_async()
+        .await;
+    let head_mock = server
+        .mock("HEAD", "/photo")
+        .with_status(200)
+        .with_header("content-type", "image/jpeg")
+        .create_async()
+        .await;
+    let get_mock = server
+        .mock("GET", "/photo")
+        .with_status(200)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.head_for_assets = true;
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    head_mock.assert_async().await;
+    get_mock.assert_async().await;
+    about_mock.assert_async().await;
+    assert!(
+        spider_crab
+            .get_page_by_str(&format!("{}photo", url))
+            .subresource
+    );
+    assert!(spider_crab.is_page_good_by_str(&format!("{}photo", url)));
+}
+
+/// Coverage is the share of sitemap pages that were crawled and passed
+#[tokio::test]
+async fn test_sitemap_coverage() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"a.html\">A</a><a href=\"b.html\">B</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/a.html",
+            "<!DOCTYPE html><html><title>A</title><body></body></html>",
+        )
+        .add_page(
+            "https://example.com/b.html",
+            "<!DOCTYPE html><html><title>B</title><body></body></html>",
+        )
+        .add_response(
+            "https://example.com/sitemap.xml",
+            200,
+            Some("application/xml"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc></url>
+  <url><loc>https://example.com/a.html</loc></url>
+  <url><loc>https://example.com/b.html</loc></url>
+  <url><loc>https://example.com/orphan.html</loc></url>
+</urlset>"#,
+            None,
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    // Without a sitemap, coverage is not part of the summary
+    assert!(!spider_crab.report().summary().contains("sitemap"));
+
+    assert!(
+        spider_crab
+            .check_sitemap("https://example.com/sitemap.xml")
+            .await
+    );
+    assert_eq!(spider_crab.sitemap_urls.len(), 4);
+    let report = spider_crab.report();
+    assert_eq!(report.coverage(&spider_crab.sitemap_urls), 75.0);
+    assert_eq!(report.coverage(&[]), 100.0);
+    assert!(report
+        .summary()
+        .contains("Crawled and passed 75.0% of the 4 pages listed in the sitemap"));
+}
+
+/// URLs matching a request method pattern are requested with that method
+#[tokio::test]
+async fn test_request_method() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"api/orders\">Create an order</a></body></html>")
+        .create_async()
+        .await;
+    let post_mock = server
+        .mock("POST", "/api/orders")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .create_async()
+        .await;
+    let get_mock = server
+        .mock("GET", "/api/orders")
+        .with_status(405)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options
+        .request_methods
+        .push(("*/api/*".to_string(), Method::POST));
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    post_mock.assert_async().await;
+    get_mock.assert_async().await;
+    assert!(spider_crab.is_page_good_by_str(&format!("{}api/orders", url)));
+}
+
+/// Links with a fragment are good if the linked page has an element with that id or an anchor with that name
+#[tokio::test]
+async fn test_present_fragment() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"guide.html#section-3\">Section 3</a><a href=\"guide.html#legacy\">Legacy</a><a href=\"#top\">Top</a><h2 id=\"contents\">Contents</h2><a href=\"#contents\">Contents</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/guide.html",
+            "<!DOCTYPE html><html><title>Guide</title><body><h2 id=\"section-3\">Section 3</h2><a name=\"legacy\" href=\"/\">Home</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.check_fragments = true;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+}
+
+/// Shards of a crawl fetch disjoint sets of pages that together cover every page
+#[tokio::test]
+async fn test_shards() {
+    let urls: Vec<String> = (0..12)
+        .map(|i| format!("https://example.com/page{}.html", i))
+        .collect();
+    let urls: Vec<&str> = urls.iter().map(String::as_str).collect();
+
+    let mut visited_by_shard = Vec::<Vec<String>>::new();
+    for index in 1..=2 {
+        let mut fetcher = InMemoryFetcher::default();
+        for url in &urls {
+            fetcher.add_page(
+                url,
+                "<!DOCTYPE html><html><title>Page</title><body></body></html>",
+            );
+        }
+        let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+        spider_crab.fetcher = Box::new(fetcher);
+        spider_crab.options.shard = Some(Shard { index, count: 2 });
+        assert!(spider_crab.visit_pages(&urls).await);
+        visited_by_shard.push(
+            urls.iter()
+                .filter(|url| spider_crab.get_page_by_str(url).visited)
+                .map(|url| url.to_string())
+                .collect(),
+        );
+    }
+
+    assert!(!visited_by_shard[0].is_empty());
+    assert!(!visited_by_shard[1].is_empty());
+    assert!(visited_by_shard[0]
+        .iter()
+        .all(|url| !visited_by_shard[1].contains(url)));
+    assert_eq!(
+        visited_by_shard[0].len() + visited_by_shard[1].len(),
+        urls.len()
+    );
+}
+
+/// Pages with a well-formed lang attribute pass the language check
+#[tokio::test]
+async fn test_valid_lang() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html lang=\"en-US\"><title>Root</title><body></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.check_lang = true;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+}
+
+/// Links to email addresses, phone numbers and JavaScript are not visited and are not errors
+#[tokio::test]
+async fn test_non_navigational_links() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><title>Root</title><body><a href=\"mailto:foo@bar.com\">Email</a><a href=\"tel:+1-555-0100\">Call</a><a href=\"javascript:void(0)\"></a></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    spider_crab.options.validate_mailto = true;
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+    assert_eq!(spider_crab.page_count(), 1);
+    assert_eq!(spider_crab.link_count(), 0);
+}
+
+/// Fallback content inside of <noscript> is not checked by default, even if its links are broken
+#[tokio::test]
+async fn test_noscript_skipped() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher.add_page(
+        "https://example.com/",
+        "<!DOCTYPE html><html><head><title>Root</title><noscript><link rel=\"stylesheet\" href=\"missing.css\"></noscript></head><body><noscript><img src=\"missing.png\" alt=\"Tracking pixel\"></noscript></body></html>",
+    );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.visit_website("https://example.com/").await);
+    assert_eq!(spider_crab.errors().count(), 0);
+    assert!(!spider_crab.contains_page_by_str("https://example.com/missing.png"));
+    assert!(!spider_crab.contains_page_by_str("https://example.com/missing.css"));
+}
+
+/// Custom headers are sent with every request
+#[tokio::test]
+async fn test_custom_headers() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    let root_mock = server
+        .mock("GET", "/")
+        .match_header("x-api-key", "secret")
+        .match_header("accept", "text/html")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"about\">About</a></body></html>")
+        .create_async()
+        .await;
+    let about_mock = server
+        .mock("GET", "/about")
+        .match_header("x-api-key", "secret")
+        .match_header("accept", "text/html")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>About</title><body></body></html>")
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.headers.insert(
+        HeaderName::from_static("x-api-key"),
+        HeaderValue::from_static("secret"),
+    );
+    options
+        .headers
+        .insert(ACCEPT, HeaderValue::from_static("text/html"));
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    root_mock.assert_async().await;
+    about_mock.assert_async().await;
+}
+
+/// Basic Auth credentials are sent to the website, but not to the websites it links to
+#[tokio::test]
+async fn test_basic_auth() {
+    let mut server = Server::new_async().await;
+    let port = server.socket_address().port();
+    let url = format!("http://spidercrab.invalid:{}/", port);
+    let root_mock = server
+        .mock("GET", "/")
+        .match_header("authorization", "Basic dXNlcjpwYXNz")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(format!(
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"http://other.invalid:{}/page\">Other</a></body></html>",
+            port
+        ))
+        .create_async()
+        .await;
+    let other_mock = server
+        .mock("GET", "/page")
+        .match_header("authorization", mockito::Matcher::Missing)
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.basic_auth = Some((String::from("user"), String::from("pass")));
+    for host in ["spidercrab.invalid", "other.invalid"] {
+        options
+            .host_overrides
+            .insert(String::from(host), server.socket_address().ip());
+    }
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+    root_mock.assert_async().await;
+    other_mock.assert_async().await;
+}
+
+/// Cookies set by a response are sent with later requests once cookies are enabled
+#[tokio::test]
+async fn test_cookies() {
+    let mut server = Server::new_async().await;
+    let url = server.url() + "/";
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_header("set-cookie", "session=crab; Path=/")
+        .with_body("<!DOCTYPE html><html><title>Root</title><body><a href=\"page2.html\">Page 2</a></body></html>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/page2.html")
+        .match_header("cookie", "session=crab")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><title>Page 2</title><body></body></html>")
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/page2.html")
+        .match_header("cookie", mockito::Matcher::Missing)
+        .with_status(403)
+        .create_async()
+        .await;
+
+    let mut spider_crab = SpiderCrab::from_options(SpiderOptions::new(&[url.as_str()])).unwrap();
+    assert!(!spider_crab.visit_website(url.as_str()).await);
+
+    let mut options = SpiderOptions::new(&[url.as_str()]);
+    options.enable_cookies = true;
+    let mut spider_crab = SpiderCrab::from_options(options).unwrap();
+    assert!(spider_crab.visit_website(url.as_str()).await);
+}
+
+/// The pages that a crawl started from are its roots, and not the pages they link to
+#[tokio::test]
+async fn test_roots() {
+    let mut fetcher = InMemoryFetcher::default();
+    fetcher
+        .add_page(
+            "https://example.com/",
+            "<!DOCTYPE html><html><title>Root</title><body><a href=\"about.html\">About</a></body></html>",
+        )
+        .add_page(
+            "https://example.com/about.html",
+            "<!DOCTYPE html><html><title>About</title><body><a href=\"/\">Home</a></body></html>",
+        );
+
+    let mut spider_crab = SpiderCrab::new(&["https://example.com/"]);
+    spider_crab.fetcher = Box::new(fetcher);
+    assert!(spider_crab.roots().is_empty());
+    assert!(spider_crab.visit_website("https://example.com/").await);
+
+    let roots: Vec<&str> = spider_crab.roots().iter().map(|p| p.url.as_str()).collect();
+    assert_eq!(roots, vec!["https://example.com/"]);
+
+    spider_crab.reset();
+    assert!(spider_crab.roots().is_empty());
+    assert!(
+        spider_crab
+            .visit_pages(&["https://example.com/about.html", "https://example.com/"])
+            .await
+    );
+    let roots: Vec<&str> = spider_crab.roots().iter().map(|p| p.url.as_str()).collect();
+    assert_eq!(
+        roots,
+        vec!["https://example.com/about.html", "https://example.com/"]
+    );
+}
@@ -0,0 +1,320 @@
+//! Holds the `CrawlReport` struct used to summarize the results of a crawl
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use url::{Host, Url};
+
+use crate::error::{exit_code_for_errors, sort_errors, SpiderError, SpiderErrorType};
+use crate::SpiderCrab;
+
+/// Results of the pages of one host, see `CrawlReport::per_host_summary()`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HostSummary {
+    /// Number of pages of the host that were visited
+    pub pages_visited: usize,
+    /// Number of errors and warnings found on pages of the host, by type
+    pub errors_by_type: HashMap<SpiderErrorType, usize>,
+    /// Average time until the response headers of a page of the host were received. `None` if no page responded.
+    pub average_response_time: Option<Duration>,
+}
+
+impl HostSummary {
+    /// Number of errors and warnings found on pages of the host
+    pub fn error_count(&self) -> usize {
+        self.errors_by_type.values().sum()
+    }
+}
+
+/// Summary of the results of a crawl
+pub struct CrawlReport<'a> {
+    spider_crab: &'a SpiderCrab,
+}
+
+impl<'a> CrawlReport<'a> {
+    pub fn new(spider_crab: &'a SpiderCrab) -> Self {
+        Self { spider_crab }
+    }
+
+    /// Time the crawl started at. `None` if no crawl has been performed.
+    pub fn start_time(&self) -> Option<SystemTime> {
+        self.spider_crab.start_time
+    }
+
+    /// Time the crawl ended at. `None` if no crawl has been performed.
+    pub fn end_time(&self) -> Option<SystemTime> {
+        self.spider_crab.end_time
+    }
+
+    /// Wall-clock duration of the crawl. `None` if no crawl has been performed.
+    pub fn duration(&self) -> Option<Duration> {
+        self.spider_crab.duration
+    }
+
+    /// Number of errors found, not including warnings
+    pub fn error_count(&self) -> usize {
+        self.spider_crab
+            .errors()
+            .filter(|e| !e.is_warning())
+            .count()
+    }
+
+    /// Number of warnings found
+    pub fn warning_count(&self) -> usize {
+        self.spider_crab.errors().filter(|e| e.is_warning()).count()
+    }
+
+    /// Returns `true` if at most `max_allowed_errors` errors were found. Warnings are not counted.
+    pub fn is_within_error_threshold(&self, max_allowed_errors: usize) -> bool {
+        self.error_count() <= max_allowed_errors
+    }
+
+    /// Process exit code for the crawl, see `SpiderErrorType::exit_code()`.
+    /// Returns `0` if no errors were found.
+    pub fn exit_code(&self) -> i32 {
+        exit_code_for_errors(self.spider_crab.errors())
+    }
+
+    /// Returns every error and warning, sorted by source page, rule and target page (see `error::sort_errors()`).
+    /// Unlike `SpiderCrab::errors()`, the order does not depend on the order pages finished in.
+    pub fn sorted_errors(&self) -> Vec<&'a SpiderError> {
+        let mut errors: Vec<&SpiderError> = self.spider_crab.errors().collect();
+        sort_errors(&mut errors);
+        errors
+    }
+
+    /// Returns every error and warning as one line of `TYPE\tSOURCE\tTARGET\tMESSAGE`, sorted so that the output only changes when the errors do.
+    /// Missing source or target pages are written as `-`. Tabs and line breaks in the message are replaced with spaces.
+    pub fn plain(&self) -> String {
+        let mut lines: Vec<String> = self
+            .spider_crab
+            .errors()
+            .map(|e| {
+                let message: String = e
+                    .get_message()
+                    .chars()
+                    .map(|c| {
+                        if c == '\t' || c == '\n' || c == '\r' {
+                            ' '
+                        } else {
+                            c
+                        }
+                    })
+                    .collect();
+                format!(
+                    "{}\t{}\t{}\t{}",
+                    e.error_type.get_rule_name(),
+                    e.source_page.as_deref().unwrap_or("-"),
+                    e.target_page.as_deref().unwrap_or("-"),
+                    message
+                )
+            })
+            .collect();
+        lines.sort();
+        lines.dedup();
+
+        let mut plain = String::new();
+        for line in lines {
+            plain.push_str(&line);
+            plain.push('\n');
+        }
+        plain
+    }
+
+    /// Returns every error and warning as a GitHub Actions workflow command (ie. `::error file=blog/index.html,title=missing-title::message`), one per line.
+    /// When run in GitHub Actions, the errors are shown inline on the changed files of a pull request.
+    /// The file is the source page of the error, or its target page if there is no source page, see `get_annotation_file()`.
+    pub fn github_annotations(&self) -> String {
+        let mut annotations = String::new();
+        for e in self.sorted_errors() {
+            let mut properties = Vec::<String>::new();
+            if let Some(file) = e
+                .source_page
+                .as_deref()
+                .or(e.target_page.as_deref())
+                .and_then(get_annotation_file)
+            {
+                properties.push(format!("file={}", escape_annotation_property(&file)));
+            }
+            if let Some(line) = e.line {
+                properties.push(format!("line={}", line));
+            }
+            properties.push(format!(
+                "title={}",
+                escape_annotation_property(e.error_type.get_rule_name())
+            ));
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                if e.is_warning() { "warning" } else { "error" },
+                properties.join(","),
+                escape_annotation_data(&e.get_message())
+            ));
+        }
+        annotations
+    }
+
+    /// Summarizes the results of the crawl for each host. Pages without a host (ie. `file://` URLs) are not included.
+    pub fn per_host_summary(&self) -> HashMap<Host<String>, HostSummary> {
+        let mut summaries = HashMap::<Host<String>, HostSummary>::new();
+        let mut response_times = HashMap::<Host<String>, Vec<Duration>>::new();
+        for page in self.spider_crab.graph.node_weights() {
+            let Some(host) = page.url.host() else {
+                continue;
+            };
+            let host = host.to_owned();
+            let summary = summaries.entry(host.clone()).or_default();
+            if page.visited {
+                summary.pages_visited += 1;
+            }
+            for error in &page.errors {
+                *summary
+                    .errors_by_type
+                    .entry(error.error_type.clone())
+                    .or_default() += 1;
+            }
+            if let Some(response_time) = page.response_time {
+                response_times.entry(host).or_default().push(response_time);
+            }
+        }
+
+        for (host, times) in response_times {
+            let summary = summaries.get_mut(&host).unwrap();
+            summary.average_response_time =
+                Some(times.iter().sum::<Duration>() / times.len() as u32);
+        }
+        summaries
+    }
+
+    /// Returns the number of pages on the `trusted_external_hosts` that were assumed to be good instead of being requested
+    pub fn trusted_count(&self) -> usize {
+        let options = &self.spider_crab.options;
+        self.spider_crab
+            .graph
+            .node_weights()
+            .filter(|p| !p.visited && options.is_trusted(&p.url))
+            .count()
+    }
+
+    /// Percentage of `sitemap_urls` that were crawled and passed, from `0.0` to `100.0`.
+    /// Returns `100.0` if `sitemap_urls` is empty.
+    pub fn coverage(&self, sitemap_urls: &[Url]) -> f64 {
+        if sitemap_urls.is_empty() {
+            return 100.0;
+        }
+        let passed = sitemap_urls
+            .iter()
+            .filter(|url| {
+                self.spider_crab.map.get(*url).is_some_and(|i| {
+                    let page = self.spider_crab.graph.node_weight(*i).unwrap();
+                    page.visited && page.good == Some(true)
+                })
+            })
+            .count();
+        passed as f64 * 100.0 / sitemap_urls.len() as f64
+    }
+
+    /// Returns a human readable, multi-line summary of the crawl.
+    /// Includes the crawl coverage of the sitemap if the crawl was seeded from or checked against one, see `coverage()`.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Discovered {} pages\nVisited {} pages\nDiscovered {} links\nFound {} errors and {} warnings",
+            self.spider_crab.page_count(),
+            self.spider_crab.map.len(),
+            self.spider_crab.link_count(),
+            self.error_count(),
+            self.warning_count()
+        );
+        if let (Some(start), Some(end), Some(duration)) =
+            (self.start_time(), self.end_time(), self.duration())
+        {
+            summary.push_str(
+                format!(
+                    "\nCrawl started at {}, ended at {}, took {:.3} seconds",
+                    httpdate::fmt_http_date(start),
+                    httpdate::fmt_http_date(end),
+                    duration.as_secs_f64()
+                )
+                .as_str(),
+            );
+        }
+        let trusted_count = self.trusted_count();
+        if trusted_count > 0 {
+            summary.push_str(
+                format!(
+                    "\nSkipped {} pages on trusted hosts, they were assumed to be good",
+                    trusted_count
+                )
+                .as_str(),
+            );
+        }
+        let sitemap_urls = &self.spider_crab.sitemap_urls;
+        if !sitemap_urls.is_empty() {
+            summary.push_str(
+                format!(
+                    "\nCrawled and passed {:.1}% of the {} pages listed in the sitemap",
+                    self.coverage(sitemap_urls),
+                    sitemap_urls.len()
+                )
+                .as_str(),
+            );
+        }
+        summary
+    }
+}
+
+/// Returns the file that the page at `url` is served from, for the `file` of a GitHub Actions annotation.
+/// `file://` URLs are local paths, the paths of other URLs are relative to the root of the site (ie. `blog/index.html`).
+/// URLs of directories (ie. `/blog/`) are served from the `index.html` file inside of them.
+fn get_annotation_file(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if url.scheme() == "file" {
+        return url
+            .to_file_path()
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned());
+    }
+    let mut path = url.path().trim_start_matches('/').to_string();
+    if path.is_empty() || path.ends_with('/') {
+        path.push_str("index.html");
+    }
+    Some(path)
+}
+
+/// Escapes the message of a GitHub Actions workflow command
+fn escape_annotation_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property (ie. `file`) of a GitHub Actions workflow command
+fn escape_annotation_property(property: &str) -> String {
+    escape_annotation_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[test]
+fn test_get_annotation_file() {
+    assert_eq!(
+        get_annotation_file("https://example.com/blog/"),
+        Some(String::from("blog/index.html"))
+    );
+    assert_eq!(
+        get_annotation_file("https://example.com/about.html?lang=en"),
+        Some(String::from("about.html"))
+    );
+    assert_eq!(
+        get_annotation_file("file:///site/index.html"),
+        Some(String::from("/site/index.html"))
+    );
+    assert_eq!(get_annotation_file("not a url"), None);
+}
+
+#[test]
+fn test_escape_annotation_property() {
+    assert_eq!(
+        escape_annotation_property("a,b:c%d\ne"),
+        "a%2Cb%3Ac%25d%0Ae"
+    );
+    assert_eq!(escape_annotation_data("a,b:c\r\n"), "a,b:c%0D%0A");
+}
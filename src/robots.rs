@@ -0,0 +1,232 @@
+//! Holds the `RobotsCache` that retrieves the `robots.txt` of each host, and decides which URLs may be crawled
+
+use log::info;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::algo::CrawlContext;
+use crate::fetch::FetchRequest;
+
+/// Name that spider-crab looks for in the `User-agent` lines of a `robots.txt`, unless a custom user agent is set
+pub const ROBOTS_USER_AGENT: &str = "spider-crab";
+
+/// Returns the name to look for in the `User-agent` lines of a `robots.txt` when requests are sent with `user_agent`.
+/// This is the product token of the user agent (ie. `MyBot` for `MyBot/1.0 (+https://example.com/bot)`), or `ROBOTS_USER_AGENT` if no user agent is set.
+pub fn get_robots_user_agent(user_agent: Option<&str>) -> &str {
+    user_agent
+        .and_then(|ua| ua.split(['/', ' ']).next())
+        .filter(|token| !token.is_empty())
+        .unwrap_or(ROBOTS_USER_AGENT)
+}
+
+#[test]
+fn test_get_robots_user_agent() {
+    assert_eq!(get_robots_user_agent(None), ROBOTS_USER_AGENT);
+    assert_eq!(
+        get_robots_user_agent(Some("MyBot/1.0 (+https://example.com/bot)")),
+        "MyBot"
+    );
+    assert_eq!(get_robots_user_agent(Some("MyBot")), "MyBot");
+    assert_eq!(get_robots_user_agent(Some("")), ROBOTS_USER_AGENT);
+}
+
+/// `Allow` and `Disallow` rules of a `robots.txt` that apply to one user agent
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RobotsRules {
+    /// Path patterns, with `true` for `Allow` rules and `false` for `Disallow` rules
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// Parses the rules of the groups in `robots_txt` that name `user_agent`, or of the `*` group if no group names it.
+    /// User agents are compared ignoring case.
+    pub fn parse(robots_txt: &str, user_agent: &str) -> Self {
+        // Each group is the user agents it applies to, and its rules
+        let mut groups = Vec::<(Vec<String>, Vec<(bool, String)>)>::new();
+        let mut in_user_agents = false;
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    // Consecutive User-agent lines share the rules that follow them
+                    if !in_user_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    in_user_agents = true;
+                    groups
+                        .last_mut()
+                        .unwrap()
+                        .0
+                        .push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_user_agents = false;
+                    // An empty Disallow allows everything, like no rule at all
+                    if let Some((_, rules)) = groups.last_mut().filter(|_| !value.is_empty()) {
+                        rules.push((key == "allow", value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let user_agent = user_agent.to_ascii_lowercase();
+        let rules_for = |name: &str| -> Vec<(bool, String)> {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|a| a == name))
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect()
+        };
+        let named = groups
+            .iter()
+            .any(|(agents, _)| agents.contains(&user_agent));
+        Self {
+            rules: if named {
+                rules_for(&user_agent)
+            } else {
+                rules_for("*")
+            },
+        }
+    }
+
+    /// Returns `true` if the rules allow crawling `path`, which includes the query string (ie. `/search?q=crab`).
+    /// The rule with the longest matching pattern wins, `Allow` rules win ties. Paths that match no rule are allowed.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map_or(true, |(allow, _)| *allow)
+    }
+}
+
+/// Returns `true` if `path` starts with the `robots.txt` path `pattern`.
+/// A `*` in the pattern matches any characters, a `$` at the end of the pattern matches the end of the path.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[test]
+fn test_parse_robots_txt() {
+    let robots_txt = "# Comment\n\
+        User-agent: *\n\
+        Disallow: /private/\n\
+        \n\
+        User-agent: other-bot\n\
+        User-agent: Spider-Crab\n\
+        Disallow: /drafts/ # Not ready yet\n\
+        Allow: /drafts/public.html\n\
+        Disallow: /*.pdf$\n\
+        Sitemap: https://example.com/sitemap.xml\n";
+
+    let rules = RobotsRules::parse(robots_txt, ROBOTS_USER_AGENT);
+    assert!(rules.is_allowed("/private/secret.html"));
+    assert!(!rules.is_allowed("/drafts/next.html"));
+    assert!(rules.is_allowed("/drafts/public.html"));
+    assert!(!rules.is_allowed("/files/manual.pdf"));
+    assert!(rules.is_allowed("/files/manual.pdf?download=1"));
+
+    let rules = RobotsRules::parse(robots_txt, "another-bot");
+    assert!(!rules.is_allowed("/private/secret.html"));
+    assert!(rules.is_allowed("/drafts/next.html"));
+
+    let rules = RobotsRules::parse("User-agent: *\nDisallow:\n", ROBOTS_USER_AGENT);
+    assert!(rules.is_allowed("/"));
+}
+
+#[test]
+fn test_robots_pattern_matches() {
+    assert!(pattern_matches("/", "/anything"));
+    assert!(pattern_matches("/docs", "/docs.html"));
+    assert!(!pattern_matches("/docs", "/about"));
+    assert!(pattern_matches("/*/edit", "/pages/1/edit"));
+    assert!(pattern_matches("/index.html$", "/index.html"));
+    assert!(!pattern_matches("/index.html$", "/index.html?page=2"));
+    assert!(pattern_matches("/*?*sort=", "/list?page=2&sort=name"));
+}
+
+/// Cache of the `robots.txt` rules of each host, shared by all pages of a crawl.
+/// Clones of a cache share the same rules.
+#[derive(Debug, Default, Clone)]
+pub struct RobotsCache {
+    /// Rules of each origin (ie. `https://example.com`) whose `robots.txt` was retrieved
+    rules: Arc<Mutex<HashMap<String, Arc<RobotsRules>>>>,
+}
+
+impl RobotsCache {
+    /// Retrieves the `robots.txt` of the host of `url` with the fetcher of the `context`, unless it was already retrieved,
+    /// and keeps the rules for the user agent of the crawl (see `get_robots_user_agent()`).
+    /// The request waits for the pause handle, request delay and concurrency limit of the `context` like any other request of the crawl.
+    /// If the `robots.txt` can not be retrieved, every URL of the host is allowed. URLs that are not HTTP(S) are ignored.
+    pub async fn fetch_rules(&self, url: &Url, context: &CrawlContext<'_>) {
+        if !matches!(url.scheme(), "http" | "https") {
+            return;
+        }
+        let origin = url.origin().ascii_serialization();
+        if self.rules.lock().unwrap().contains_key(&origin) {
+            return;
+        }
+
+        let options = context.options;
+        let robots_url = url.join("/robots.txt").unwrap();
+        context.pause_handle.wait_while_paused().await;
+        context
+            .host_throttle
+            .wait(&robots_url, options.request_delay)
+            .await;
+        let request_permit = context
+            .request_limiter
+            .acquire(options.max_concurrency)
+            .await;
+        let robots_txt = match context.fetcher.fetch(FetchRequest::get(&robots_url)).await {
+            Ok(response) if response.status.is_success() => response.body.await.unwrap_or_default(),
+            _ => {
+                info!("No robots.txt found at {}", robots_url);
+                String::new()
+            }
+        };
+        drop(request_permit);
+        let user_agent = get_robots_user_agent(options.user_agent.as_deref());
+        let rules = RobotsRules::parse(&robots_txt, user_agent);
+        self.rules.lock().unwrap().insert(origin, Arc::new(rules));
+    }
+
+    /// Returns `false` if the `robots.txt` of the host of `url` disallows crawling it.
+    /// URLs of hosts whose `robots.txt` was not retrieved with `fetch_rules()` are allowed.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let Some(rules) = self.rules.lock().unwrap().get(&origin).cloned() else {
+            return true;
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        rules.is_allowed(&path)
+    }
+}
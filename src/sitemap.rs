@@ -0,0 +1,251 @@
+//! Holds the functions used to seed a crawl from the pages listed in an XML sitemap
+
+use log::{error, info, warn};
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use url::Url;
+
+use crate::algo::{self, CrawlContext};
+use crate::error::{Severity, SpiderError, SpiderErrorType};
+use crate::fetch::{FetchError, FetchRequest, Fetcher};
+use crate::url_helpers::{check_host, parse_relative_or_absolute_url};
+use crate::{Page, PageGraph, PageMap, SpiderCrab};
+
+/// Priority of a sitemap entry that does not give one, as defined by the sitemap protocol
+pub const DEFAULT_SITEMAP_PRIORITY: f32 = 0.5;
+
+/// Page listed in a sitemap as a `<url>` element
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    /// Value of the `<loc>` element
+    pub url: Url,
+    /// Value of the `<priority>` element, between 0.0 and 1.0
+    pub priority: Option<f32>,
+    /// Value of the `<changefreq>` element (ie. `daily`)
+    pub changefreq: Option<String>,
+}
+
+impl SitemapEntry {
+    /// Returns the priority of this entry, or `DEFAULT_SITEMAP_PRIORITY` if it does not give one
+    pub fn priority_or_default(&self) -> f32 {
+        self.priority.unwrap_or(DEFAULT_SITEMAP_PRIORITY)
+    }
+}
+
+/// Returns the trimmed text of the first child of `element` matching `selector`
+fn child_text(element: ElementRef, selector: &Selector) -> Option<String> {
+    let text: String = element.select(selector).next()?.text().collect();
+    Some(text.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Parses the `<url>` entries of the XML sitemap `xml`. Entries without a valid `<loc>` URL are skipped.
+pub fn parse_sitemap(xml: &str) -> Vec<SitemapEntry> {
+    // Sitemaps are simple enough for the HTML parser, which keeps unknown elements as they are
+    let document = Html::parse_document(xml);
+    let url_selector = Selector::parse("url").unwrap();
+    let loc_selector = Selector::parse("loc").unwrap();
+    let priority_selector = Selector::parse("priority").unwrap();
+    let changefreq_selector = Selector::parse("changefreq").unwrap();
+
+    let mut entries = Vec::<SitemapEntry>::new();
+    for element in document.select(&url_selector) {
+        let Some(loc) = child_text(element, &loc_selector) else {
+            continue;
+        };
+        let Ok(url) = Url::parse(&loc) else {
+            info!("Ignoring invalid URL {} in sitemap", loc);
+            continue;
+        };
+        entries.push(SitemapEntry {
+            url,
+            priority: child_text(element, &priority_selector).and_then(|p| p.parse().ok()),
+            changefreq: child_text(element, &changefreq_selector),
+        });
+    }
+    entries
+}
+
+/// Retrieves the XML sitemap at `url` with `fetcher` and parses its entries
+pub async fn fetch_sitemap(
+    url: &Url,
+    fetcher: &dyn Fetcher,
+) -> Result<Vec<SitemapEntry>, FetchError> {
+    let response = fetcher.fetch(FetchRequest::get(url)).await?;
+    if !response.status.is_success() {
+        return Err(FetchError {
+            status: Some(response.status),
+            message: format!("Sitemap returned HTTP status {}", response.status),
+            timed_out: false,
+        });
+    }
+    Ok(parse_sitemap(&response.body.await?))
+}
+
+/// Splits `entries` into groups of entries with the same priority, highest priority first.
+/// Entries keep their order within a group.
+pub fn group_by_priority(entries: &[SitemapEntry]) -> Vec<Vec<&SitemapEntry>> {
+    let mut sorted: Vec<&SitemapEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| b.priority_or_default().total_cmp(&a.priority_or_default()));
+
+    let mut groups = Vec::<Vec<&SitemapEntry>>::new();
+    for entry in sorted {
+        match groups.last_mut() {
+            Some(group) if group[0].priority_or_default() == entry.priority_or_default() => {
+                group.push(entry)
+            }
+            _ => groups.push(vec![entry]),
+        }
+    }
+    groups
+}
+
+impl SpiderCrab {
+    /// Begins crawling from each of the pages listed in the XML sitemap at `sitemap_url`.
+    /// The priority and change frequency of each listed page are recorded on its `Page`.
+    /// If `options.sitemap_priority_order` is set, pages are crawled one priority at a time, highest first.
+    /// Returns `true` if no errors were found.
+    /// Returns `false` if errors were found, or if the sitemap could not be retrieved.
+    pub async fn visit_sitemap(&mut self, sitemap_url: &str) -> bool {
+        let sitemap_url = Url::parse(sitemap_url).unwrap();
+        let timer = self.start_timer();
+
+        let entries = match fetch_sitemap(&sitemap_url, self.fetcher.as_ref()).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to retrieve sitemap {}: {}", sitemap_url, e);
+                self.stop_timer(timer);
+                self.send_crawl_finished(false);
+                return false;
+            }
+        };
+        info!("Sitemap {} lists {} pages", sitemap_url, entries.len());
+        self.sitemap_urls = entries.iter().map(|e| e.url.clone()).collect();
+
+        let groups = if self.options.sitemap_priority_order {
+            group_by_priority(&entries)
+        } else {
+            vec![entries.iter().collect()]
+        };
+
+        let mut result = true;
+        {
+            let context = CrawlContext::new(
+                self.fetcher.as_ref(),
+                &self.options,
+                self.pause_handle.clone(),
+                self.event_sender.clone(),
+            );
+            let map_mutex = Mutex::<&mut PageMap>::new(&mut self.map);
+            let graph_mutex = Mutex::<&mut PageGraph>::new(&mut self.graph);
+            for group in groups {
+                let urls: Vec<Url> = group.iter().map(|e| e.url.clone()).collect();
+                result &= algo::visit_root_pages(&urls, &context, &graph_mutex, &map_mutex).await;
+            }
+        }
+
+        let urls: Vec<Url> = entries.iter().map(|e| e.url.clone()).collect();
+        self.record_roots(&urls);
+        for entry in &entries {
+            if let Some(node_index) = self.map.get(&entry.url) {
+                let page = self.graph.node_weight_mut(*node_index).unwrap();
+                page.sitemap_priority = entry.priority;
+                page.sitemap_changefreq = entry.changefreq.clone();
+            }
+        }
+
+        let result = self.check_pages() && result;
+        self.stop_timer(timer);
+        self.send_crawl_finished(result);
+        result
+    }
+
+    /// Compares the pages found by the last crawl with the pages listed in the XML sitemap at `sitemap_url`.
+    /// Records a `SitemapMismatch` warning on the sitemap for each listed page that was not found by crawling,
+    /// and on each crawled HTML page of the `hosts` that is not listed.
+    /// The sitemap is added to the page graph if it is not already in it.
+    /// Returns `false` if the sitemap could not be retrieved.
+    pub async fn check_sitemap(&mut self, sitemap_url: &str) -> bool {
+        let sitemap_url = Url::parse(sitemap_url).unwrap();
+        let entries = match fetch_sitemap(&sitemap_url, self.fetcher.as_ref()).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to retrieve sitemap {}: {}", sitemap_url, e);
+                return false;
+            }
+        };
+
+        // Sitemap URLs are normalized like links, so that they can be compared with the crawled pages
+        let listed: HashSet<Url> = entries
+            .iter()
+            .filter_map(|e| {
+                parse_relative_or_absolute_url(
+                    &sitemap_url,
+                    e.url.as_str(),
+                    &self.options.query_params,
+                )
+            })
+            .collect();
+        self.sitemap_urls = listed.iter().cloned().collect();
+        self.sitemap_urls.sort();
+
+        let sitemap_index = match self.map.get(&sitemap_url) {
+            Some(i) => *i,
+            None => {
+                let mut page = Page::new(&sitemap_url);
+                page.visited = true;
+                let i = self.graph.add_node(page);
+                self.map.insert(sitemap_url.clone(), i);
+                i
+            }
+        };
+
+        let mut orphans: Vec<&Url> = listed
+            .iter()
+            .filter(|url| !self.map.contains_key(*url))
+            .collect();
+        orphans.sort();
+        let sitemap_page = self.graph.node_weight_mut(sitemap_index).unwrap();
+        for url in orphans {
+            if !self
+                .options
+                .is_rule_enabled(SpiderErrorType::SitemapMismatch, url)
+            {
+                continue;
+            }
+            warn!(
+                "Page {} is listed in sitemap {}, but was not found by crawling",
+                url, sitemap_url
+            );
+            sitemap_page.push_error(SpiderError {
+                error_type: SpiderErrorType::SitemapMismatch,
+                source_page: Some(sitemap_url.to_string()),
+                target_page: Some(url.to_string()),
+                severity: Severity::Warning,
+                ..SpiderError::default()
+            });
+        }
+
+        for page in self.graph.node_weights_mut() {
+            let is_crawled_html = page.good == Some(true)
+                && page.content_type.as_deref() == Some("text/html")
+                && check_host(&self.options.hosts, &page.url);
+            if !is_crawled_html
+                || listed.contains(&page.url)
+                || !self
+                    .options
+                    .is_rule_enabled(SpiderErrorType::SitemapMismatch, &page.url)
+            {
+                continue;
+            }
+            warn!("Page {} is not listed in sitemap {}", page.url, sitemap_url);
+            page.push_error(SpiderError {
+                error_type: SpiderErrorType::SitemapMismatch,
+                source_page: Some(page.url.to_string()),
+                severity: Severity::Warning,
+                ..SpiderError::default()
+            });
+        }
+        true
+    }
+}
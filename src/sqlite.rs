@@ -0,0 +1,123 @@
+//! Holds functions to save the Page Graph into a SQLite database
+use crate::SpiderCrab;
+use petgraph::visit::EdgeRef;
+use rusqlite::{params, Connection};
+use std::time::SystemTime;
+
+/// Creates the tables used to store crawl results, if they do not already exist
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS crawls (
+            id INTEGER PRIMARY KEY,
+            start_time TEXT,
+            end_time TEXT
+        );
+        CREATE TABLE IF NOT EXISTS pages (
+            crawl_id INTEGER NOT NULL REFERENCES crawls(id),
+            id INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            title TEXT,
+            content_type TEXT,
+            status_code INTEGER,
+            good INTEGER,
+            visited INTEGER NOT NULL,
+            last_modified TEXT,
+            etag TEXT,
+            PRIMARY KEY (crawl_id, id)
+        );
+        CREATE TABLE IF NOT EXISTS links (
+            crawl_id INTEGER NOT NULL REFERENCES crawls(id),
+            source_id INTEGER NOT NULL,
+            target_id INTEGER NOT NULL,
+            html TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS errors (
+            crawl_id INTEGER NOT NULL REFERENCES crawls(id),
+            page_id INTEGER NOT NULL,
+            error_type TEXT NOT NULL,
+            warning INTEGER NOT NULL,
+            source_page TEXT,
+            target_page TEXT,
+            http_error_code INTEGER,
+            attribute TEXT,
+            html TEXT,
+            message TEXT NOT NULL,
+            suggestion TEXT NOT NULL
+        );",
+    )
+}
+
+fn format_time(time: Option<SystemTime>) -> Option<String> {
+    time.map(httpdate::fmt_http_date)
+}
+
+impl SpiderCrab {
+    /// Saves the pages, links and errors of the last crawl into the SQLite database at `filepath`.
+    /// The database is created if it does not exist. Results of previous crawls already in the database are kept,
+    /// so that crawl history can be queried.
+    pub fn save_sqlite(&self, filepath: &str) -> rusqlite::Result<()> {
+        let mut conn = Connection::open(filepath)?;
+        create_tables(&conn)?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO crawls (start_time, end_time) VALUES (?1, ?2)",
+            params![format_time(self.start_time), format_time(self.end_time)],
+        )?;
+        let crawl_id = tx.last_insert_rowid();
+
+        for node_index in self.graph.node_indices() {
+            let page = self.graph.node_weight(node_index).unwrap();
+            tx.execute(
+                "INSERT INTO pages (crawl_id, id, url, title, content_type, status_code, good, visited, last_modified, etag)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    crawl_id,
+                    node_index.index(),
+                    page.url.as_str(),
+                    page.title,
+                    page.content_type,
+                    page.status_code.map(|s| s.as_u16()),
+                    page.good,
+                    page.visited,
+                    page.last_modified,
+                    page.etag
+                ],
+            )?;
+
+            for error in &page.errors {
+                tx.execute(
+                    "INSERT INTO errors (crawl_id, page_id, error_type, warning, source_page, target_page, http_error_code, attribute, html, message, suggestion)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        crawl_id,
+                        node_index.index(),
+                        format!("{:?}", error.error_type),
+                        error.is_warning(),
+                        error.source_page,
+                        error.target_page,
+                        error.http_error_code,
+                        error.attribute,
+                        error.html,
+                        error.to_string(),
+                        error.suggestion()
+                    ],
+                )?;
+            }
+        }
+
+        for edge in self.graph.edge_references() {
+            tx.execute(
+                "INSERT INTO links (crawl_id, source_id, target_id, html) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    crawl_id,
+                    edge.source().index(),
+                    edge.target().index(),
+                    edge.weight().html
+                ],
+            )?;
+        }
+
+        tx.commit()
+    }
+}
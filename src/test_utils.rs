@@ -1,10 +1,17 @@
 //! Contains structs used for creating test cases with less code.
 
 use derive_builder::Builder;
+use futures::future::BoxFuture;
 use mockito::{Mock, Server, ServerGuard};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::time::Duration;
 use url::Url;
 
 use crate::error::{SpiderError, SpiderErrorType};
+use crate::fetch::{FetchError, FetchRequest, FetchedResponse, Fetcher};
+use crate::url_helpers::check_host;
 use crate::SpiderCrab;
 
 #[derive(Builder, Debug)]
@@ -31,6 +38,13 @@ pub struct SpiderTestPage<'a> {
     /// Set to true if we expect this mock to be visited, set to false if it should NOT be visited
     #[builder(default = "true")]
     expect_visited: bool,
+    /// Extra headers returned in the response of the mock
+    #[builder(default = "Vec::new()")]
+    headers: Vec<(&'a str, &'a str)>,
+    /// Host the page is expected to be visited through, defaults to the host of the mock server
+    #[builder(default = "None")]
+    #[builder(setter(strip_option))]
+    host: Option<&'a str>,
     /// URL of the page
     #[builder(setter(skip))]
     absolute_url: Option<Url>,
@@ -46,16 +60,28 @@ impl SpiderTestPage<'_> {
                 .unwrap_or_else(|_| panic!("Invalid URL for test page: {}!", self.url)),
         );
 
+        if let Some(host) = self.host {
+            self.absolute_url
+                .as_mut()
+                .unwrap()
+                .set_host(Some(host))
+                .unwrap_or_else(|_| panic!("Invalid host for test page: {}!", host));
+        }
+
         let mut mock = server
             .mock(self.method, self.url)
             .with_status(self.status_code.into());
 
-        if self.content_type.is_some() {
-            mock = mock.with_header("content-type", self.content_type.unwrap());
+        if let Some(content_type) = self.content_type {
+            mock = mock.with_header("content-type", content_type);
+        }
+
+        for (name, value) in self.headers.iter().copied() {
+            mock = mock.with_header(name, value);
         }
 
-        if self.content.is_some() {
-            mock = mock.with_body(self.content.unwrap());
+        if let Some(content) = self.content {
+            mock = mock.with_body(content);
         }
 
         if !self.expect_visited {
@@ -115,7 +141,7 @@ impl SpiderTestPage<'_> {
                 self
             );
 
-            if self.content_type.is_some() {
+            if let Some(content_type) = self.content_type {
                 assert!(
                     page.content_type.is_some(),
                     "Content-Type was not recorded!\n{:?}",
@@ -123,13 +149,13 @@ impl SpiderTestPage<'_> {
                 );
                 assert_eq!(
                     page.content_type.as_ref().unwrap().as_str(),
-                    self.content_type.unwrap(),
+                    content_type,
                     "Content-Type does not match expected value!\n{:?}",
                     self
                 );
             }
 
-            if self.title.is_some() {
+            if let Some(title) = self.title {
                 assert!(
                     page.title.is_some(),
                     "Expected title to be recorded but it was not! \n{:?}",
@@ -137,11 +163,15 @@ impl SpiderTestPage<'_> {
                 );
                 assert_eq!(
                     page.title.as_ref().unwrap().as_str(),
-                    self.title.unwrap(),
+                    title,
                     "Recorded title does not match expected value for page!\n{:?}",
                     self
                 );
-            } else if self.content_type.is_some() && self.content_type.unwrap() == "text/html" {
+            } else if self.content_type == Some("text/html")
+                && page.good == Some(true)
+                && check_host(&spider.options.hosts, &page.url)
+            {
+                // Only pages that were parsed can be missing a title
                 assert!(
                     page.errors
                         .iter()
@@ -181,6 +211,29 @@ impl Default for SpiderTestServer<'_> {
 
 impl<'a> SpiderTestServer<'a> {
     pub async fn run_test(&mut self) -> bool {
+        self.setup_mocks();
+        let result = self
+            .spider_crab
+            .visit_website(self.server.url().as_str())
+            .await;
+        self.assert_pages();
+        result
+    }
+
+    /// Same as `run_test()`, but starts crawling from each of the pages given by `paths`
+    pub async fn run_test_from_pages(&mut self, paths: &[&str]) -> bool {
+        self.setup_mocks();
+        let urls: Vec<String> = paths
+            .iter()
+            .map(|p| format!("{}{}", self.server.url(), p))
+            .collect();
+        let urls: Vec<&str> = urls.iter().map(|u| u.as_str()).collect();
+        let result = self.spider_crab.visit_pages(&urls).await;
+        self.assert_pages();
+        result
+    }
+
+    fn setup_mocks(&mut self) {
         // Add the mock server to list of hosts for the traversal options
         self.spider_crab
             .options
@@ -189,14 +242,17 @@ impl<'a> SpiderTestServer<'a> {
         for p in self.pages.iter_mut() {
             p.setup_mock(&mut self.server);
         }
-        let result = self
-            .spider_crab
-            .visit_website(self.server.url().as_str())
-            .await;
+    }
+
+    fn assert_pages(&mut self) {
         for p in self.pages.iter_mut() {
-            p.assert(&mut self.spider_crab);
+            p.assert(&self.spider_crab);
         }
-        result
+    }
+
+    /// Returns the base URL of the mock server
+    pub fn url(&self) -> String {
+        self.server.url()
     }
 
     pub fn add_page(&mut self, page: &'a mut SpiderTestPage<'a>) -> &mut Self {
@@ -252,3 +308,85 @@ impl<'a> SpiderTestServer<'a> {
         );
     }
 }
+
+/// Canned response returned by the `InMemoryFetcher`
+struct InMemoryResponse {
+    status: u16,
+    content_type: Option<&'static str>,
+    body: &'static str,
+    latency: Option<Duration>,
+}
+
+/// Fetcher that returns canned responses without sending any HTTP requests.
+/// URLs without a canned response return a 404.
+#[derive(Default)]
+pub struct InMemoryFetcher {
+    responses: HashMap<Url, InMemoryResponse>,
+}
+
+impl InMemoryFetcher {
+    /// Add an HTML page with a 200 status code at `url`
+    pub fn add_page(&mut self, url: &str, body: &'static str) -> &mut Self {
+        self.add_response(url, 200, Some("text/html"), body, None)
+    }
+
+    /// Add a canned response at `url`, which is returned after waiting for `latency`
+    pub fn add_response(
+        &mut self,
+        url: &str,
+        status: u16,
+        content_type: Option<&'static str>,
+        body: &'static str,
+        latency: Option<Duration>,
+    ) -> &mut Self {
+        self.responses.insert(
+            Url::parse(url).unwrap(),
+            InMemoryResponse {
+                status,
+                content_type,
+                body,
+                latency,
+            },
+        );
+        self
+    }
+}
+
+impl Fetcher for InMemoryFetcher {
+    fn fetch(&self, request: FetchRequest) -> BoxFuture<'_, Result<FetchedResponse, FetchError>> {
+        Box::pin(async move {
+            let response = match self.responses.get(&request.url) {
+                Some(r) => r,
+                None => {
+                    return Ok(FetchedResponse {
+                        url: request.url,
+                        status: StatusCode::NOT_FOUND,
+                        headers: HeaderMap::new(),
+                        body: Box::pin(async { Ok(String::new()) }),
+                        compressed: Some(false),
+                        redirect_chain: vec![],
+                    })
+                }
+            };
+
+            if let Some(latency) = response.latency {
+                tokio::time::sleep(latency).await;
+            }
+
+            let mut headers = HeaderMap::new();
+            if let Some(content_type) = response.content_type {
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            }
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(response.body.len()));
+            let body = response.body.to_string();
+            Ok(FetchedResponse {
+                url: request.url,
+                status: StatusCode::from_u16(response.status).unwrap(),
+                headers,
+                body: Box::pin(async move { Ok(body) }),
+                compressed: Some(false),
+                redirect_chain: vec![],
+            })
+        })
+    }
+}
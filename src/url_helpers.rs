@@ -1,27 +1,109 @@
 //! Helper functions called by the page traversal algorithm
 
 use crate::error::{SpiderError, SpiderErrorType};
+use crate::fetch::find_path_ignoring_case;
+use publicsuffix::{List, Psl};
 use scraper::ElementRef;
-use url::{Host, ParseError, Url};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use url::{Host, ParseError, Position, Url};
+
+/// Controls which query parameters are kept when parsing URLs.
+/// Stripping insignificant query parameters (ie. session IDs) prevents the same page from being visited multiple times.
+#[derive(Debug, Clone, Default)]
+pub enum QueryParamFilter {
+    /// Keep all query parameters
+    #[default]
+    KeepAll,
+    /// Only keep query parameters with these names, strip all others
+    Allow(HashSet<String>),
+    /// Strip query parameters with these names, keep all others
+    Deny(HashSet<String>),
+}
+
+impl QueryParamFilter {
+    /// Returns `true` if the query parameter given by `name` should be kept
+    pub fn is_kept(&self, name: &str) -> bool {
+        match self {
+            QueryParamFilter::KeepAll => true,
+            QueryParamFilter::Allow(names) => names.contains(name),
+            QueryParamFilter::Deny(names) => !names.contains(name),
+        }
+    }
+}
+
+/// One of several crawls that split the URLs of a website between them, so that they can run on separate machines.
+/// Each URL belongs to exactly one shard, decided by a hash of the URL that is the same on every machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    /// Number of this shard, counting from 1
+    pub index: u32,
+    /// Number of shards the URLs are split between
+    pub count: u32,
+}
+
+impl Shard {
+    /// Returns `true` if `url` belongs to this shard
+    pub fn contains(&self, url: &Url) -> bool {
+        // FNV-1a, which unlike the standard library hasher is the same across builds and platforms
+        let hash = url.as_str().bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+        hash % u64::from(self.count) == u64::from(self.index - 1)
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid shard {:?}, expected index/count (ie. 1/4)", s);
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse::<u32>().map_err(|_| invalid())?;
+        let count = count.trim().parse::<u32>().map_err(|_| invalid())?;
+        if index == 0 || index > count {
+            return Err(invalid());
+        }
+        Ok(Shard { index, count })
+    }
+}
+
+#[test]
+fn test_shard() {
+    assert_eq!(Shard::from_str("2/4"), Ok(Shard { index: 2, count: 4 }));
+    assert!(Shard::from_str("0/4").is_err());
+    assert!(Shard::from_str("5/4").is_err());
+    assert!(Shard::from_str("4").is_err());
+
+    let url = Url::parse("https://example.com/page.html").unwrap();
+    let shards: Vec<Shard> = (1..=4).map(|index| Shard { index, count: 4 }).collect();
+    assert_eq!(shards.iter().filter(|s| s.contains(&url)).count(), 1);
+    assert!(Shard { index: 1, count: 1 }.contains(&url));
+}
 
 /// Attempt to extract and parse a URL from an HTML element depending on the element tag.
 /// `img``, and `script` elements will extract the URL from the `src` attribute
-/// `a`, and `link` elements will extract the URL from the `href` attribute
+/// `a`, `link` and SVG `use` elements will extract the URL from the `href` attribute, see `get_url_attribute()`
 /// Returns `Ok(Some(Url))` if extract + parse was successful
 /// Returns `Ok(None)` if element did not have a URL, but it is not required to have one (such as the `script` elemnt)
+/// Returns `Ok(None)` if the URL does not lead to a page, see `is_non_navigational_url()`
 /// Returns `Err(SpiderError)` if element did not have a URL, and is required to have one
+/// Relative URLs are resolved against `force_base` if it is given, otherwise against `current_url`
+/// Query parameters are filtered using `query_filter`
+// SpiderError is returned as-is so that it can be pushed straight into `Page::errors`
+#[allow(clippy::result_large_err)]
 pub fn get_url_from_element(
     element: ElementRef,
     current_url: &Url,
+    force_base: Option<&Url>,
+    query_filter: &QueryParamFilter,
 ) -> Result<Option<Url>, SpiderError> {
-    let (attribute_name, required) = match element.value().name() {
-        "a" | "link" => ("href", true),
-        "script" => ("src", false),
-        "img" => ("src", true),
-        &_ => panic!("Unsupported element type passed to get_url_from_element!"),
-    };
+    let (attribute_name, required) = get_url_attribute_name(element);
 
-    let attribute = element.attr(attribute_name);
+    let attribute = get_url_attribute(element);
 
     if attribute.is_none() {
         if required {
@@ -50,7 +132,12 @@ pub fn get_url_from_element(
         });
     }
 
-    let next_url = parse_relative_or_absolute_url(current_url, next_url_str);
+    if is_non_navigational_url(next_url_str) {
+        return Ok(None);
+    }
+
+    let base_url = force_base.unwrap_or(current_url);
+    let next_url = parse_relative_or_absolute_url(base_url, next_url_str, query_filter);
 
     if next_url.is_none() {
         // Failed to parse the URL, report it as an error
@@ -66,19 +153,251 @@ pub fn get_url_from_element(
     Ok(Some(next_url.unwrap()))
 }
 
+/// Schemes of URLs that do not lead to a page (ie. `mailto:foo@example.com`), which are never visited
+pub const NON_NAVIGATIONAL_SCHEMES: [&str; 3] = ["mailto", "tel", "javascript"];
+
+/// Returns `true` if `url_str` has one of the `NON_NAVIGATIONAL_SCHEMES`, ignoring case
+pub fn is_non_navigational_url(url_str: &str) -> bool {
+    url_str
+        .trim_start()
+        .split_once(':')
+        .is_some_and(|(scheme, _)| {
+            NON_NAVIGATIONAL_SCHEMES
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(scheme))
+        })
+}
+
+#[test]
+fn test_is_non_navigational_url() {
+    assert!(is_non_navigational_url("mailto:foo@bar.com"));
+    assert!(is_non_navigational_url("tel:+1-555-0100"));
+    assert!(is_non_navigational_url("javascript:void(0)"));
+    assert!(is_non_navigational_url(" JavaScript:void(0)"));
+    assert!(!is_non_navigational_url("https://example.com/"));
+    assert!(!is_non_navigational_url("/mailto:foo"));
+    assert!(!is_non_navigational_url("page.html"));
+}
+
+/// Returns `true` if `url_str` is a `mailto:` URL whose addresses are all well-formed email addresses.
+/// Only the syntax is checked: a local part, an `@`, and a domain of at least two labels of letters, digits and hyphens.
+pub fn is_valid_mailto(url_str: &str) -> bool {
+    let Some((_, rest)) = url_str.trim().split_once(':') else {
+        return false;
+    };
+    let addresses = rest.split('?').next().unwrap_or_default();
+    !addresses.is_empty() && addresses.split(',').all(is_valid_email_address)
+}
+
+/// Returns `true` if `address` is a well-formed email address, see `is_valid_mailto()`
+fn is_valid_email_address(address: &str) -> bool {
+    let Some((local, domain)) = address.trim().split_once('@') else {
+        return false;
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty()
+        && !local.contains(|c: char| c.is_whitespace() || c == '@')
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[test]
+fn test_is_valid_mailto() {
+    assert!(is_valid_mailto("mailto:foo@bar.com"));
+    assert!(is_valid_mailto(
+        "mailto:foo.bar+news@mail.example.org?subject=Hello"
+    ));
+    assert!(is_valid_mailto("mailto:a@example.com,b@example.com"));
+    assert!(!is_valid_mailto("mailto:"));
+    assert!(!is_valid_mailto("mailto:foo"));
+    assert!(!is_valid_mailto("mailto:foo@bar"));
+    assert!(!is_valid_mailto("mailto:foo@@bar.com"));
+    assert!(!is_valid_mailto("mailto:foo@bar..com"));
+    assert!(!is_valid_mailto("mailto:foo bar@baz.com"));
+}
+
+/// Returns the name of the attribute that holds the URL for `element`, and whether that attribute is required.
+pub fn get_url_attribute_name(element: ElementRef) -> (&'static str, bool) {
+    match element.value().name() {
+        "a" | "link" | "use" => ("href", true),
+        "script" => ("src", false),
+        "img" => ("src", true),
+        &_ => panic!("Unsupported element type passed to get_url_attribute_name!"),
+    }
+}
+
+/// Returns the value of the attribute that holds the URL for `element`, see `get_url_attribute_name()`.
+/// SVG elements can also give it in the older `xlink:href` attribute, which the parser keeps in the XLink namespace.
+pub fn get_url_attribute<'a>(element: ElementRef<'a>) -> Option<&'a str> {
+    let (attribute_name, _) = get_url_attribute_name(element);
+    let e = element.value();
+    e.attr(attribute_name).or_else(|| {
+        e.attrs()
+            .find(|(name, _)| *name == attribute_name)
+            .map(|(_, value)| value)
+    })
+}
+
+#[test]
+fn test_get_url_attribute() {
+    use scraper::{Html, Selector};
+    let html = Html::parse_document(
+        r#"<html><body><a href="page.html">Page</a><svg>
+        <use href="sprite.svg#home"></use>
+        <use xlink:href="sprite.svg#search"></use>
+        </svg></body></html>"#,
+    );
+    let urls: Vec<Option<&str>> = html
+        .select(&Selector::parse("a,use").unwrap())
+        .map(get_url_attribute)
+        .collect();
+    assert_eq!(
+        urls,
+        vec![
+            Some("page.html"),
+            Some("sprite.svg#home"),
+            Some("sprite.svg#search")
+        ]
+    );
+}
+
+/// Returns `true` if `element` is a resource that blocks the page from rendering until it is loaded.
+/// These are stylesheets for all media, and classic scripts that are neither `async` nor `defer`.
+/// Module scripts are deferred by default.
+pub fn is_render_blocking(element: ElementRef) -> bool {
+    let e = element.value();
+    match e.name() {
+        "link" => {
+            e.attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("stylesheet"))
+            }) && e.attr("disabled").is_none()
+                && e.attr("media").map_or(true, |media| {
+                    matches!(media.trim().to_lowercase().as_str(), "" | "all" | "screen")
+                })
+        }
+        "script" => {
+            e.attr("src").is_some()
+                && e.attr("async").is_none()
+                && e.attr("defer").is_none()
+                && !e
+                    .attr("type")
+                    .is_some_and(|t| t.trim().eq_ignore_ascii_case("module"))
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn test_is_render_blocking() {
+    use scraper::{Html, Selector};
+
+    let html = Html::parse_document(
+        r#"<html><head>
+        <link id="a" rel="stylesheet" href="a.css">
+        <link id="b" rel="stylesheet" href="b.css" media="print">
+        <link id="c" rel="stylesheet" href="c.css" media="all">
+        <link id="d" rel="preload" href="d.woff2">
+        <script id="e" src="e.js"></script>
+        <script id="f" src="f.js" async></script>
+        <script id="g" src="g.js" defer></script>
+        <script id="h" type="module" src="h.js"></script>
+        <script id="i" nomodule src="i.js"></script>
+        </head></html>"#,
+    );
+    let blocking: Vec<&str> = html
+        .select(&Selector::parse("link,script").unwrap())
+        .filter(|e| is_render_blocking(*e))
+        .map(|e| e.value().id().unwrap())
+        .collect();
+    assert_eq!(blocking, vec!["a", "c", "e", "i"]);
+}
+
+/// `rel` values of `<link>` elements that load a resource of the page, rather than point to another page
+const SUBRESOURCE_LINK_RELS: [&str; 6] = [
+    "stylesheet",
+    "icon",
+    "apple-touch-icon",
+    "manifest",
+    "preload",
+    "modulepreload",
+];
+
+/// Returns `true` if `element` loads a resource of the page (ie. an image, a script or a stylesheet), rather than linking to another page
+pub fn is_subresource(element: ElementRef) -> bool {
+    match element.value().name() {
+        "img" | "script" | "use" => true,
+        "link" => element.attr("rel").is_some_and(|rel| {
+            rel.split_whitespace().any(|r| {
+                SUBRESOURCE_LINK_RELS
+                    .iter()
+                    .any(|s| r.eq_ignore_ascii_case(s))
+            })
+        }),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_is_subresource() {
+    use scraper::{Html, Selector};
+
+    let html = Html::parse_document(
+        r#"<html><head>
+        <link id="a" rel="stylesheet" href="a.css">
+        <link id="b" rel="alternate" hreflang="de" href="/de/">
+        <link id="c" rel="preload" href="c.woff2">
+        <link id="d" rel="canonical" href="/">
+        <script id="e" src="e.js"></script>
+        </head><body><img id="f" src="f.png"><a id="g" href="g.png">Image</a></body></html>"#,
+    );
+    let subresources: Vec<&str> = html
+        .select(&Selector::parse("a,link,img,script").unwrap())
+        .filter(|e| is_subresource(*e))
+        .map(|e| e.value().id().unwrap())
+        .collect();
+    assert_eq!(subresources, vec!["a", "c", "e", "f"]);
+}
+
+/// Returns `true` if `url_str` is a protocol-relative URL (ie. `//host/path`).
+/// Protocol-relative URLs inherit the scheme of the page they are found on.
+pub fn is_protocol_relative_url(url_str: &str) -> bool {
+    url_str.starts_with("//")
+}
+
+/// Returns `true` if the protocol-relative `url_str` most likely was meant to be a relative path.
+/// For example, `//images/logo.png` has the host `images`, which is probably a typo of `/images/logo.png`.
+/// Hosts that contain a `.`, IP addresses and `localhost` are assumed to be intentional.
+pub fn is_likely_mistyped_relative_url(url_str: &str) -> bool {
+    if !is_protocol_relative_url(url_str) {
+        return false;
+    }
+    let host = url_str[2..]
+        .split(['/', ':', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    !host.is_empty() && !host.contains('.') && !host.starts_with('[') && host != "localhost"
+}
+
 /// Attempts to grab the host from `url` and see if it matches any element listed in `hosts`
 /// Returns `true` if `url` matches any entry of `hosts`
 /// Returns `false` if `url` fails to match any entry in `hosts`, or if failed to obtain a host for `url`
+/// Local `file://` URLs have no host, they match if `hosts` is empty (ie. when checking a website on the local disk)
 pub fn check_host(hosts: &[Host<String>], url: &Url) -> bool {
     let url_host = url.host();
     if url_host.is_none() {
         // URL doesn't have a host associated with it
-        return false;
+        return url.scheme() == "file" && hosts.is_empty();
     }
     let url_host = url_host.unwrap().to_owned();
 
     // Return true if the domain/IP + port matches any entry in domain_names
-    hosts.iter().any(|h| *h == url_host)
+    hosts.contains(&url_host)
 }
 
 #[test]
@@ -131,10 +450,629 @@ fn test_check_domain_match_with_params_and_fragment() {
     ));
 }
 
+#[test]
+fn test_check_host_file() {
+    let url = Url::parse("file:///var/www/index.html").unwrap();
+    assert!(check_host(&[], &url));
+    assert!(!check_host(&[Host::parse("example.com").unwrap()], &url));
+}
+
+#[test]
+fn test_protocol_relative_url() {
+    assert!(is_protocol_relative_url("//cdn.example.com/x.js"));
+    assert!(!is_protocol_relative_url("/x.js"));
+    assert!(!is_protocol_relative_url("https://cdn.example.com/x.js"));
+
+    assert!(is_likely_mistyped_relative_url("//images/logo.png"));
+    assert!(!is_likely_mistyped_relative_url("//cdn.example.com/x.js"));
+    assert!(!is_likely_mistyped_relative_url("//localhost:8080/x.js"));
+    assert!(!is_likely_mistyped_relative_url("//[::1]/x.js"));
+    assert!(!is_likely_mistyped_relative_url("/images/logo.png"));
+}
+
+/// File extensions of assets that never contain links, such as images, fonts, media and archives
+pub const ASSET_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "bmp", "ico", "woff", "woff2", "ttf", "otf",
+    "eot", "mp3", "mp4", "webm", "ogg", "wav", "pdf", "zip", "gz",
+];
+
+/// Returns `true` if the path of `url` ends in one of the `ASSET_EXTENSIONS`, ignoring case
+pub fn is_asset_url(url: &Url) -> bool {
+    let file_name = url.path().rsplit('/').next().unwrap_or_default();
+    file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+        ASSET_EXTENSIONS
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(extension))
+    })
+}
+
+#[test]
+fn test_is_asset_url() {
+    let is_asset = |url: &str| is_asset_url(&Url::parse(url).unwrap());
+    assert!(is_asset("https://example.com/images/logo.png"));
+    assert!(is_asset("https://example.com/Photo.JPG?size=large"));
+    assert!(is_asset("https://example.com/fonts/body.woff2"));
+    assert!(!is_asset("https://example.com/index.html"));
+    assert!(!is_asset("https://example.com/style.css"));
+    assert!(!is_asset("https://example.com/png/"));
+    assert!(!is_asset("https://example.com/"));
+}
+
+/// Returns `true` if `other_url` only differs from `url` in its query string (ie. `/list?page=2` and `/list?page=3`)
+pub fn is_query_variation(url: &Url, other_url: &Url) -> bool {
+    url[..Position::AfterPath] == other_url[..Position::AfterPath]
+        && url.query() != other_url.query()
+}
+
+#[test]
+fn test_is_query_variation() {
+    let url = Url::parse("https://example.com/list").unwrap();
+    let is_variation = |other: &str| is_query_variation(&url, &Url::parse(other).unwrap());
+    assert!(is_variation("https://example.com/list?page=2"));
+    assert!(!is_variation("https://example.com/list"));
+    assert!(!is_variation("https://example.com/list/?page=2"));
+    assert!(!is_variation("https://example.org/list?page=2"));
+    assert!(!is_variation("http://example.com/list?page=2"));
+}
+
+/// Public Suffix List (<https://publicsuffix.org>), used to find the registrable domain of a host.
+/// Built into the binary from `data/public_suffix_list.dat`, see the Development section of the README on how to update it.
+static PUBLIC_SUFFIX_LIST: LazyLock<List> = LazyLock::new(|| {
+    include_str!("../data/public_suffix_list.dat")
+        .parse()
+        .unwrap()
+});
+
+/// Returns the registrable domain of the host of `url`, the public suffix and the label before it (ie. `example.co.uk` for `https://www.example.co.uk/`).
+/// IP addresses, and domains that are a public suffix themselves, are returned as they are. Returns `None` if `url` has no host.
+pub fn get_registrable_domain(url: &Url) -> Option<String> {
+    match url.host()? {
+        Host::Domain(domain) => Some(
+            PUBLIC_SUFFIX_LIST
+                .domain(domain.as_bytes())
+                .and_then(|d| std::str::from_utf8(d.as_bytes()).ok())
+                .unwrap_or(domain)
+                .to_string(),
+        ),
+        host => Some(host.to_string()),
+    }
+}
+
+/// Returns `true` if `url` and `other_url` have the same registrable domain (ie. `example.com` and `www.example.com`), see `get_registrable_domain()`
+pub fn is_same_registrable_domain(url: &Url, other_url: &Url) -> bool {
+    get_registrable_domain(url).is_some_and(|d| get_registrable_domain(other_url) == Some(d))
+}
+
+#[test]
+fn test_is_same_registrable_domain() {
+    let same = |a: &str, b: &str| {
+        is_same_registrable_domain(&Url::parse(a).unwrap(), &Url::parse(b).unwrap())
+    };
+    assert!(same(
+        "https://example.com/",
+        "https://www.example.com/about.html"
+    ));
+    assert!(same("https://blog.example.co.uk/", "http://example.co.uk/"));
+    assert!(!same("https://example.co.uk/", "https://other.co.uk/"));
+    assert!(!same("https://example.com/", "https://example.org/"));
+    // Subdomains of a public suffix are owned by different people
+    assert!(!same("https://alice.github.io/", "https://bob.github.io/"));
+    assert!(same("http://127.0.0.1:8000/", "http://127.0.0.1:9000/"));
+    assert!(!same("file:///index.html", "file:///about.html"));
+}
+
+/// Returns `true` if the relative `url_str` contains more `..` segments than directories above it,
+/// so resolving it against `current_url` would go above the root of the host (ie. `../../x.html` on `/a/b.html`).
+/// Absolute and protocol-relative URLs never escape the root.
+pub fn escapes_host_root(current_url: &Url, url_str: &str) -> bool {
+    if Url::parse(url_str).is_ok() || is_protocol_relative_url(url_str) {
+        return false;
+    }
+    let path = url_str.split(['?', '#']).next().unwrap_or_default();
+
+    // Number of directories the relative path starts in
+    let mut depth: i64 = if path.starts_with('/') {
+        0
+    } else {
+        current_url
+            .path_segments()
+            .map(|s| s.count() as i64 - 1)
+            .unwrap_or_default()
+    };
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                depth -= 1;
+                if depth < 0 {
+                    return true;
+                }
+            }
+            _ => depth += 1,
+        }
+    }
+    false
+}
+
+#[test]
+fn test_escapes_host_root() {
+    let current_url = Url::parse("https://example.com/a/b.html").unwrap();
+    assert!(!escapes_host_root(&current_url, "../c.html"));
+    assert!(!escapes_host_root(&current_url, "d/../../c.html"));
+    assert!(!escapes_host_root(
+        &current_url,
+        "https://example.com/../c.html"
+    ));
+    assert!(escapes_host_root(&current_url, "../../c.html"));
+    assert!(escapes_host_root(&current_url, "/../c.html"));
+    assert!(escapes_host_root(&current_url, "../../../etc"));
+
+    let root_url = Url::parse("https://example.com/").unwrap();
+    assert!(!escapes_host_root(&root_url, "./c.html"));
+    assert!(escapes_host_root(&root_url, "../c.html?x=../.."));
+}
+
+/// Returns the URLs in the value of an HTTP `Link` header (ie. `<https://example.com/2>; rel="next"`) whose `rel` is one of `rels`
+pub fn get_link_header_urls(value: &str, rels: &[&str]) -> Vec<String> {
+    let mut urls = Vec::<String>::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let link_url = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        // Parameters of this link go until the start of the next link
+        let params_end = rest.find('<').unwrap_or(rest.len());
+        let params = &rest[..params_end];
+        let matches_rel = params.split(';').any(|param| {
+            let (name, value) = param.split_once('=').unwrap_or_default();
+            name.trim().eq_ignore_ascii_case("rel")
+                && value
+                    .trim()
+                    .trim_end_matches(',')
+                    .trim()
+                    .trim_matches('"')
+                    .split_whitespace()
+                    .any(|r| rels.iter().any(|rel| r.eq_ignore_ascii_case(rel)))
+        });
+        if matches_rel {
+            urls.push(link_url.trim().to_string());
+        }
+    }
+    urls
+}
+
+#[test]
+fn test_get_link_header_urls() {
+    let value = "<https://example.com/items?page=3>; rel=\"next\", <https://example.com/items?page=1>; rel=\"prev\", </items?page=9>; rel=last";
+    assert_eq!(
+        get_link_header_urls(value, &["next", "prev"]),
+        vec![
+            "https://example.com/items?page=3",
+            "https://example.com/items?page=1"
+        ]
+    );
+    assert_eq!(
+        get_link_header_urls(value, &["last"]),
+        vec!["/items?page=9"]
+    );
+    assert!(get_link_header_urls("</style.css>; rel=preload", &["next"]).is_empty());
+    assert!(get_link_header_urls("", &["next"]).is_empty());
+}
+
+/// Returns `true` if `url_str` matches `pattern`.
+/// A `*` in `pattern` matches any number of characters, all other characters must match exactly.
+pub fn matches_url_pattern(pattern: &str, url_str: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match url_str.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // Pattern has no wildcards
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[test]
+fn test_matches_url_pattern() {
+    assert!(matches_url_pattern(
+        "https://example.com/",
+        "https://example.com/"
+    ));
+    assert!(!matches_url_pattern(
+        "https://example.com/",
+        "https://example.com/a.html"
+    ));
+    assert!(matches_url_pattern(
+        "https://example.com/*",
+        "https://example.com/a.html"
+    ));
+    assert!(matches_url_pattern(
+        "*/old/*.html",
+        "https://example.com/old/a.html"
+    ));
+    assert!(!matches_url_pattern(
+        "*/old/*.html",
+        "https://example.com/new/a.html"
+    ));
+    assert!(matches_url_pattern("*", "https://example.com/"));
+    assert!(!matches_url_pattern("*a*a", "https://a.com/"));
+}
+
+/// Checks the syntax of the value of a `srcset` attribute.
+/// `has_sizes` should be `true` if the element also has a `sizes` attribute.
+/// Returns `Err(reason)` if the `srcset` contains duplicate descriptors, invalid descriptors,
+/// mixes width and density descriptors, or is missing width descriptors when `sizes` is present.
+pub fn validate_srcset(srcset: &str, has_sizes: bool) -> Result<(), String> {
+    let mut densities = Vec::<f64>::new();
+    let mut widths = Vec::<u64>::new();
+    let mut candidates = 0;
+
+    for candidate in srcset.split(',') {
+        let mut parts = candidate.split_whitespace();
+        let image_url = match parts.next() {
+            Some(u) => u,
+            None => return Err("srcset contains an empty image candidate".to_string()),
+        };
+        candidates += 1;
+
+        let descriptor = parts.next();
+        if parts.next().is_some() {
+            return Err(format!(
+                "image candidate {} has too many descriptors",
+                image_url
+            ));
+        }
+
+        match descriptor {
+            None => densities.push(1.0),
+            Some(d) if d.ends_with('x') => {
+                let density = d[..d.len() - 1]
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|d| *d > 0.0)
+                    .ok_or(format!("invalid density descriptor {}", d))?;
+                if densities.contains(&density) {
+                    return Err(format!("duplicate density descriptor {}", d));
+                }
+                densities.push(density);
+            }
+            Some(d) if d.ends_with('w') => {
+                let width = d[..d.len() - 1]
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|w| *w > 0)
+                    .ok_or(format!("invalid width descriptor {}", d))?;
+                if widths.contains(&width) {
+                    return Err(format!("duplicate width descriptor {}", d));
+                }
+                widths.push(width);
+            }
+            Some(d) => return Err(format!("unknown descriptor {}", d)),
+        }
+    }
+
+    if candidates == 0 {
+        return Err("srcset is empty".to_string());
+    }
+    if !widths.is_empty() && !densities.is_empty() {
+        return Err("srcset mixes width and density descriptors".to_string());
+    }
+    if has_sizes && widths.is_empty() {
+        return Err("sizes attribute is present, but srcset has no width descriptors".to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate_srcset() {
+    assert!(validate_srcset("a.png", false).is_ok());
+    assert!(validate_srcset("a.png 1x, b.png 2x", false).is_ok());
+    assert!(validate_srcset("a.png 480w, b.png 800w", true).is_ok());
+
+    assert!(validate_srcset("", false).is_err());
+    assert!(validate_srcset("a.png 1x, b.png 1x", false).is_err());
+    assert!(validate_srcset("a.png, b.png 1x", false).is_err());
+    assert!(validate_srcset("a.png 480w, b.png 480w", true).is_err());
+    assert!(validate_srcset("a.png 480w, b.png 2x", false).is_err());
+    assert!(validate_srcset("a.png 1x, b.png 2x", true).is_err());
+    assert!(validate_srcset("a.png 2y", false).is_err());
+}
+
+/// Keys of JSON-LD structured data that hold URLs
+const JSON_LD_URL_KEYS: [&str; 5] = ["url", "image", "logo", "contentUrl", "thumbnailUrl"];
+
+/// Returns `(key, url)` for every URL found in the JSON-LD structured data `json`.
+/// URLs are strings stored under one of the `JSON_LD_URL_KEYS`, either directly or inside of an array.
+/// Nested objects (ie. an `ImageObject`) are searched as well.
+pub fn get_urls_from_json_ld(json: &Value) -> Vec<(String, String)> {
+    let mut urls = Vec::<(String, String)>::new();
+    collect_json_ld_urls(json, None, &mut urls);
+    urls
+}
+
+fn collect_json_ld_urls(json: &Value, key: Option<&str>, urls: &mut Vec<(String, String)>) {
+    match json {
+        Value::String(s) => {
+            if let Some(key) = key.filter(|k| JSON_LD_URL_KEYS.contains(k)) {
+                urls.push((key.to_string(), s.clone()));
+            }
+        }
+        Value::Array(values) => {
+            for v in values {
+                collect_json_ld_urls(v, key, urls);
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                collect_json_ld_urls(v, Some(k), urls);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_get_urls_from_json_ld() {
+    let json: Value = serde_json::from_str(
+        r#"{
+            "@context": "https://schema.org",
+            "@type": "Organization",
+            "name": "Example",
+            "url": "https://example.com/",
+            "logo": {"@type": "ImageObject", "url": "/logo.png"},
+            "image": ["/a.png", "/b.png"]
+        }"#,
+    )
+    .unwrap();
+    let mut urls = get_urls_from_json_ld(&json);
+    urls.sort();
+    assert_eq!(
+        urls,
+        vec![
+            ("image".to_string(), "/a.png".to_string()),
+            ("image".to_string(), "/b.png".to_string()),
+            ("url".to_string(), "/logo.png".to_string()),
+            ("url".to_string(), "https://example.com/".to_string()),
+        ]
+    );
+}
+
+/// Returns the URL whose path only differs in case from the path of `url`, if there is one.
+/// For `file://` URLs, the path is compared against the names of the files on the disk.
+/// For other URLs, the path is compared against the path of `response_url`, the URL of the response after redirects.
+pub fn find_case_mismatch(url: &Url, response_url: &Url) -> Option<Url> {
+    if url.scheme() == "file" {
+        let path = url.to_file_path().ok()?;
+        let found = find_path_ignoring_case(&path)?;
+        if found == path {
+            return None;
+        }
+        let mut found_url = if url.path().ends_with('/') {
+            Url::from_directory_path(found).ok()?
+        } else {
+            Url::from_file_path(found).ok()?
+        };
+        found_url.set_query(url.query());
+        return Some(found_url);
+    }
+
+    let differs_in_case =
+        url.path() != response_url.path() && url.path().eq_ignore_ascii_case(response_url.path());
+    differs_in_case.then(|| response_url.clone())
+}
+
+#[test]
+fn test_find_case_mismatch() {
+    let url = Url::parse("https://example.com/About.html").unwrap();
+    assert_eq!(
+        find_case_mismatch(&url, &Url::parse("https://example.com/about.html").unwrap()),
+        Some(Url::parse("https://example.com/about.html").unwrap())
+    );
+    assert_eq!(find_case_mismatch(&url, &url), None);
+    assert_eq!(
+        find_case_mismatch(&url, &Url::parse("https://example.com/about/").unwrap()),
+        None
+    );
+}
+
+/// Returns `url` moved to the scheme, host and port of `canonical`, or `None` if `url` already uses them
+pub fn get_canonical_url(canonical: &Url, url: &Url) -> Option<Url> {
+    if url.origin() == canonical.origin() {
+        return None;
+    }
+    let mut canonical_url = url.clone();
+    canonical_url.set_scheme(canonical.scheme()).ok()?;
+    canonical_url.set_host(canonical.host_str()).ok()?;
+    canonical_url.set_port(canonical.port()).ok()?;
+    Some(canonical_url)
+}
+
+#[test]
+fn test_get_canonical_url() {
+    let canonical = Url::parse("https://example.com").unwrap();
+    let canonical_url =
+        |s: &str| get_canonical_url(&canonical, &Url::parse(s).unwrap()).map(|u| u.to_string());
+    assert_eq!(canonical_url("https://example.com/page.html?a=1#top"), None);
+    assert_eq!(
+        canonical_url("http://example.com/page.html?a=1#top"),
+        Some("https://example.com/page.html?a=1#top".to_string())
+    );
+    assert_eq!(
+        canonical_url("https://old.example.com:8443/blog/"),
+        Some("https://example.com/blog/".to_string())
+    );
+}
+
+/// Inline attributes checked by default when inline URL checks are enabled
+pub const DEFAULT_INLINE_URL_ATTRIBUTES: [&str; 4] =
+    ["onclick", "data-url", "data-href", "data-src"];
+
+/// Returns `true` if `s` looks like a URL or a path (ie. `/page.html`, `../page.html` or `https://example.com`)
+fn looks_like_url(s: &str) -> bool {
+    !s.is_empty()
+        && !s.contains(char::is_whitespace)
+        && (s.starts_with("http://")
+            || s.starts_with("https://")
+            || s.contains('/')
+            || s.ends_with(".html")
+            || s.ends_with(".htm"))
+}
+
+/// Best-effort extraction of URLs from the value of an inline attribute.
+/// Values that look like a URL (ie. `data-url="/page.html"`) are returned whole.
+/// Otherwise, the value is treated as JavaScript (ie. `onclick="location.href='/page.html'"`) and quoted strings that look like URLs are returned.
+/// This is a heuristic, so it may miss URLs or find strings that are not URLs.
+pub fn get_urls_from_inline_attribute(value: &str) -> Vec<String> {
+    let value = value.trim();
+    if looks_like_url(value) && !value.contains(['\'', '"', '(', ';', '=']) {
+        return vec![value.to_string()];
+    }
+
+    let mut urls = Vec::<String>::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(['\'', '"']) {
+        let quote = rest[start..].chars().next().unwrap();
+        let quoted = &rest[start + 1..];
+        let Some(end) = quoted.find(quote) else {
+            break;
+        };
+        if looks_like_url(&quoted[..end]) {
+            urls.push(quoted[..end].to_string());
+        }
+        rest = &quoted[end + 1..];
+    }
+    urls
+}
+
+/// Longest URL allowed by the `UrlTooLong` check if no other length is given
+pub const DEFAULT_MAX_URL_LENGTH: usize = 2000;
+
+/// Best-effort extraction of absolute `http://` and `https://` URLs from raw text (ie. a `text/plain` page).
+/// URLs end at whitespace, quotes or angle brackets, and trailing punctuation (ie. the `.` ending a sentence) is removed.
+/// This is a heuristic, so it may miss URLs or find strings that are not URLs.
+pub fn get_urls_from_text(text: &str) -> Vec<String> {
+    let mut urls = Vec::<String>::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("http") {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '`'))
+            .unwrap_or(candidate.len());
+        let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}']);
+        let has_host = url
+            .strip_prefix("http://")
+            .or_else(|| url.strip_prefix("https://"))
+            .is_some_and(|rest| !rest.is_empty());
+        if has_host {
+            urls.push(url.to_string());
+        }
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+#[test]
+fn test_get_urls_from_text() {
+    assert_eq!(
+        get_urls_from_text("See https://example.com/page.html. Or (http://example.com/a?b=c), not http:// or httpd."),
+        vec!["https://example.com/page.html", "http://example.com/a?b=c"]
+    );
+    assert_eq!(
+        get_urls_from_text("<loc>https://example.com/</loc>"),
+        vec!["https://example.com/"]
+    );
+    assert!(get_urls_from_text("no links here").is_empty());
+}
+
+#[test]
+fn test_get_urls_from_inline_attribute() {
+    assert_eq!(
+        get_urls_from_inline_attribute("/page.html"),
+        vec!["/page.html"]
+    );
+    assert_eq!(
+        get_urls_from_inline_attribute("https://example.com/"),
+        vec!["https://example.com/"]
+    );
+    assert_eq!(
+        get_urls_from_inline_attribute("location.href='page2.html'"),
+        vec!["page2.html"]
+    );
+    assert_eq!(
+        get_urls_from_inline_attribute("window.open(\"/a.html\", '_blank'); go('../b/')"),
+        vec!["/a.html", "../b/"]
+    );
+    assert!(get_urls_from_inline_attribute("toggleMenu()").is_empty());
+    assert!(get_urls_from_inline_attribute("alert('Hello world!')").is_empty());
+    assert!(get_urls_from_inline_attribute("menu").is_empty());
+    assert!(get_urls_from_inline_attribute("go('/unterminated)").is_empty());
+}
+
+/// Escapes every `%` in `url_str` that does not start a percent-encoded byte (ie. `100%.html` becomes `100%25.html`).
+/// `Url::parse()` keeps broken percent-encoding as it is, which servers may reject or decode differently.
+pub fn fix_percent_encoding(url_str: &str) -> Cow<'_, str> {
+    let bytes = url_str.as_bytes();
+    let is_broken = |i: usize| {
+        bytes[i] == b'%'
+            && !(i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit())
+    };
+    if !(0..bytes.len()).any(is_broken) {
+        return Cow::Borrowed(url_str);
+    }
+
+    let mut fixed = String::with_capacity(url_str.len() + 2);
+    for (i, c) in url_str.char_indices() {
+        if is_broken(i) {
+            fixed.push_str("%25");
+        } else {
+            fixed.push(c);
+        }
+    }
+    Cow::Owned(fixed)
+}
+
+#[test]
+fn test_fix_percent_encoding() {
+    assert_eq!(fix_percent_encoding("100%.html"), "100%25.html");
+    assert_eq!(fix_percent_encoding("a%zzb%2"), "a%25zzb%252");
+    assert!(matches!(
+        fix_percent_encoding("50%25/caf%C3%A9"),
+        Cow::Borrowed("50%25/caf%C3%A9")
+    ));
+}
+
 /// Parses a string into a URL. String can be an absolute URL, or a relative URL.
 /// If `url_str` is a relative URL, then it will be parsed relative to `current_url`
+/// Non-ASCII characters (ie. `/über uns.html`) are percent-encoded, and broken percent-encoding is fixed with `fix_percent_encoding()`
+/// Query parameters that are not kept by `query_filter` are removed from the parsed URL
 /// Returns `None` if no valid URL could be parsed
-pub fn parse_relative_or_absolute_url(current_url: &Url, url_str: &str) -> Option<Url> {
+pub fn parse_relative_or_absolute_url(
+    current_url: &Url,
+    url_str: &str,
+    query_filter: &QueryParamFilter,
+) -> Option<Url> {
+    let url_str = fix_percent_encoding(url_str);
+    let url_str = url_str.as_ref();
+
     // Try to parse an absolute URL from the string
     let mut parsed_url = Url::parse(url_str);
 
@@ -161,15 +1099,58 @@ pub fn parse_relative_or_absolute_url(current_url: &Url, url_str: &str) -> Optio
     let mut parsed_url = parsed_url.unwrap();
     parsed_url.set_fragment(None);
 
+    // Remove insignificant query parameters to deduplicate URLs pointing to the same page
+    let query_pairs: Vec<(String, String)> = parsed_url.query_pairs().into_owned().collect();
+    if query_pairs
+        .iter()
+        .any(|(name, _)| !query_filter.is_kept(name))
+    {
+        let kept: Vec<&(String, String)> = query_pairs
+            .iter()
+            .filter(|(name, _)| query_filter.is_kept(name))
+            .collect();
+        if kept.is_empty() {
+            parsed_url.set_query(None);
+        } else {
+            parsed_url.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+
     Some(parsed_url)
 }
 
+#[test]
+fn test_parse_iri() {
+    let base = Url::parse("https://example.com/docs/").unwrap();
+    let filter = QueryParamFilter::KeepAll;
+
+    let url = parse_relative_or_absolute_url(&base, "über uns.html?q=café", &filter).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://example.com/docs/%C3%BCber%20uns.html?q=caf%C3%A9"
+    );
+    let url = parse_relative_or_absolute_url(&base, "https://例え.jp/パス", &filter).unwrap();
+    assert_eq!(url.as_str(), "https://xn--r8jz45g.jp/%E3%83%91%E3%82%B9");
+    let url = parse_relative_or_absolute_url(&base, "100%.html", &filter).unwrap();
+    assert_eq!(url.as_str(), "https://example.com/docs/100%25.html");
+
+    assert_eq!(
+        parse_relative_or_absolute_url(&base, "https://exa mple.com/", &filter),
+        None
+    );
+    assert_eq!(
+        parse_relative_or_absolute_url(&base, "https://example.com:99999/", &filter),
+        None
+    );
+}
+
 #[test]
 fn test_parse_relative_url() {
     let base = Url::parse("https://example.com/").unwrap();
     let expected = Url::parse("https://example.com/relative/path").unwrap();
 
-    let result = parse_relative_or_absolute_url(&base, "relative/path").unwrap();
+    let result =
+        parse_relative_or_absolute_url(&base, "relative/path", &QueryParamFilter::KeepAll).unwrap();
 
     assert_eq!(expected, result);
 }
@@ -179,7 +1160,12 @@ fn test_parse_relative_url2() {
     let base = Url::parse("https://example.com/").unwrap();
     let expected = Url::parse("https://example.com/another_relative_path.html").unwrap();
 
-    let result = parse_relative_or_absolute_url(&base, "another_relative_path.html").unwrap();
+    let result = parse_relative_or_absolute_url(
+        &base,
+        "another_relative_path.html",
+        &QueryParamFilter::KeepAll,
+    )
+    .unwrap();
 
     assert_eq!(expected, result);
 }
@@ -189,8 +1175,75 @@ fn test_parse_absolute_url() {
     let base = Url::parse("https://example.com/").unwrap();
     let expected = Url::parse("https://this-is-another-website.org/").unwrap();
 
-    let result =
-        parse_relative_or_absolute_url(&base, "https://this-is-another-website.org").unwrap();
+    let result = parse_relative_or_absolute_url(
+        &base,
+        "https://this-is-another-website.org",
+        &QueryParamFilter::KeepAll,
+    )
+    .unwrap();
 
     assert_eq!(expected, result);
 }
+
+#[test]
+fn test_parse_url_allowed_query_params() {
+    let base = Url::parse("https://example.com/").unwrap();
+    let filter = QueryParamFilter::Allow(HashSet::from(["page".to_string()]));
+
+    // Significant query parameters keep pages distinct
+    let page2 = parse_relative_or_absolute_url(&base, "list?page=2", &filter).unwrap();
+    let page3 = parse_relative_or_absolute_url(&base, "list?page=3", &filter).unwrap();
+    assert_ne!(page2, page3);
+    assert_eq!(page2.as_str(), "https://example.com/list?page=2");
+
+    // Insignificant query parameters are stripped
+    let session_a = parse_relative_or_absolute_url(&base, "list?sessionid=a", &filter).unwrap();
+    let session_b =
+        parse_relative_or_absolute_url(&base, "list?page=2&sessionid=b", &filter).unwrap();
+    assert_eq!(session_a.as_str(), "https://example.com/list");
+    assert_eq!(session_b, page2);
+}
+
+#[test]
+fn test_parse_url_denied_query_params() {
+    let base = Url::parse("https://example.com/").unwrap();
+    let filter = QueryParamFilter::Deny(HashSet::from(["sessionid".to_string()]));
+
+    let page2 = parse_relative_or_absolute_url(&base, "list?page=2", &filter).unwrap();
+    assert_eq!(page2.as_str(), "https://example.com/list?page=2");
+
+    let session = parse_relative_or_absolute_url(&base, "list?sessionid=x", &filter).unwrap();
+    assert_eq!(session.as_str(), "https://example.com/list");
+
+    let both = parse_relative_or_absolute_url(&base, "list?sessionid=y&page=2", &filter).unwrap();
+    assert_eq!(both, page2);
+}
+
+/// Returns `true` if `tag` is a well-formed BCP 47 language tag (ie. `en`, `en-US` or `zh-Hant-TW`).
+/// Only the syntax is checked: a 2 or 3 letter language subtag, or `x` for private use, followed by subtags of 1 to 8 letters and digits.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let is_private_use = language.eq_ignore_ascii_case("x");
+    let is_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic());
+    let rest: Vec<&str> = subtags.collect();
+    (is_language || (is_private_use && !rest.is_empty()))
+        && rest
+            .iter()
+            .all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[test]
+fn test_is_valid_language_tag() {
+    assert!(is_valid_language_tag("en"));
+    assert!(is_valid_language_tag("en-US"));
+    assert!(is_valid_language_tag("zh-Hant-TW"));
+    assert!(is_valid_language_tag("es-419"));
+    assert!(is_valid_language_tag("x-klingon"));
+    assert!(!is_valid_language_tag(""));
+    assert!(!is_valid_language_tag("english"));
+    assert!(!is_valid_language_tag("en_US"));
+    assert!(!is_valid_language_tag("en-"));
+    assert!(!is_valid_language_tag("x"));
+}
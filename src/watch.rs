@@ -0,0 +1,72 @@
+//! Holds the watch mode, which re-crawls a website on an interval and reports what changed between crawls
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use crate::SpiderCrab;
+
+/// Errors and warnings that changed between two crawls
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrawlDiff {
+    /// Messages of errors found by the latest crawl, but not by the crawl before it
+    pub new_errors: Vec<String>,
+    /// Messages of errors found by the crawl before, but not by the latest crawl
+    pub fixed_errors: Vec<String>,
+}
+
+impl CrawlDiff {
+    /// Compares the error messages of two crawls
+    pub fn new(previous: &BTreeSet<String>, current: &BTreeSet<String>) -> Self {
+        Self {
+            new_errors: current.difference(previous).cloned().collect(),
+            fixed_errors: previous.difference(current).cloned().collect(),
+        }
+    }
+
+    /// Returns `true` if no errors were added or fixed
+    pub fn is_empty(&self) -> bool {
+        self.new_errors.is_empty() && self.fixed_errors.is_empty()
+    }
+}
+
+impl SpiderCrab {
+    /// Clears the page graph and the results of the last crawl, so that the website can be crawled again.
+    /// Options and the fetcher are kept.
+    pub fn reset(&mut self) {
+        self.graph.clear();
+        self.map.clear();
+        self.root_nodes.clear();
+        self.start_time = None;
+        self.end_time = None;
+        self.duration = None;
+        self.sitemap_urls.clear();
+    }
+
+    /// Returns the messages of all errors and warnings found by the last crawl
+    pub fn error_messages(&self) -> BTreeSet<String> {
+        self.errors().map(|e| e.to_string()).collect()
+    }
+
+    /// Crawls the website at `url` repeatedly, waiting `interval` between the end of a crawl and the start of the next one.
+    /// After every crawl, `on_crawl` is called with the crawl result and the errors that changed since the previous crawl.
+    /// Stops watching when `on_crawl` returns `false`.
+    pub async fn watch<F>(&mut self, url: &str, interval: Duration, mut on_crawl: F)
+    where
+        F: FnMut(&SpiderCrab, bool, &CrawlDiff) -> bool,
+    {
+        let mut previous = BTreeSet::<String>::new();
+        loop {
+            self.reset();
+            let result = self.visit_website(url).await;
+
+            let current = self.error_messages();
+            let diff = CrawlDiff::new(&previous, &current);
+            previous = current;
+
+            if !on_crawl(self, result, &diff) {
+                return;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}